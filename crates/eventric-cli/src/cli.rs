@@ -0,0 +1,149 @@
+//! The command line: the [`Cli`] arguments, the [`Command`]s and the
+//! [`Target`] stream they run against.
+
+use std::{
+    net::ToSocketAddrs as _,
+    path::PathBuf,
+    str::FromStr,
+};
+
+use clap::{
+    Args,
+    Parser,
+    Subcommand,
+    ValueEnum,
+};
+use eventric_server::server::Address;
+
+// =================================================================================================
+// CLI
+// =================================================================================================
+
+/// Inspect and administer an eventric stream.
+#[derive(Debug, Parser)]
+#[command(name = "eventric", version)]
+pub struct Cli {
+    /// The stream: its directory, or the address of a server serving it, as
+    /// `tcp://host:port` or `unix://path`.
+    pub target: Target,
+    /// The command to run.
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Command
+
+/// A command, run against the [`Cli`]'s target.
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Print the first events matching a query, as JSON lines.
+    Head {
+        /// The number of events to print.
+        #[arg(short = 'n', long, default_value_t = 10)]
+        lines: usize,
+        /// Which events to read, and how.
+        #[command(flatten)]
+        read: Read,
+    },
+    /// Print the number of events in the stream.
+    Len,
+    /// Print the last events matching a query, as JSON lines.
+    Tail {
+        /// The number of events to print.
+        #[arg(short = 'n', long, default_value_t = 10)]
+        lines: usize,
+        /// Go on printing new matches as they are committed (through a
+        /// server only: its writer holds a stream's directory while it is
+        /// open).
+        #[arg(short, long)]
+        follow: bool,
+        /// Which events to read, and how.
+        #[command(flatten)]
+        read: Read,
+    },
+    /// Print the number of events matching a query, in all and by type name
+    /// and tag, as JSON.
+    Count {
+        /// The events to count (every event, if not given).
+        #[arg(short, long)]
+        query: Option<String>,
+    },
+    /// Print every event matching a query, as JSON lines.
+    Dump {
+        /// Which events to read, and how.
+        #[command(flatten)]
+        read: Read,
+    },
+    /// Print the stream's statistics and catalog, as JSON.
+    Stats,
+    /// Check the stream's indices and statistics against its events.
+    Verify,
+    /// Export the stream's events, with their positions and timestamps.
+    Export {
+        /// The file to export to, or `-` for standard output.
+        file: PathBuf,
+    },
+    /// Import an export into the target, which must be empty (or not yet
+    /// exist).
+    Import {
+        /// The file to import from, or `-` for standard input.
+        file: PathBuf,
+    },
+}
+
+/// The events a reading command prints, and how it prints their payloads.
+#[derive(Args, Debug)]
+pub struct Read {
+    /// The events to read (every event, if not given).
+    #[arg(short, long)]
+    pub query: Option<String>,
+    /// The encoding to print each event's payload in.
+    #[arg(short, long, value_enum, default_value_t = Payload::Hex)]
+    pub payload: Payload,
+}
+
+/// An encoding of event payloads in JSON output.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum Payload {
+    /// Lowercase hexadecimal.
+    Hex,
+    /// Standard base64, with padding.
+    Base64,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Target
+
+/// The stream a command runs against.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Target {
+    /// A stream opened from its directory.
+    Directory(PathBuf),
+    /// A stream served by a server at an address.
+    Server(Address),
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(target: &str) -> Result<Self, Self::Err> {
+        if let Some(address) = target.strip_prefix("tcp://") {
+            return address
+                .to_socket_addrs()
+                .ok()
+                .and_then(|mut addresses| addresses.next())
+                .map(|address| Self::Server(Address::Tcp(address)))
+                .ok_or_else(|| format!("cannot resolve `{address}`"));
+        }
+
+        #[cfg(unix)]
+        if let Some(path) = target.strip_prefix("unix://") {
+            return Ok(Self::Server(Address::Unix(path.into())));
+        }
+
+        Ok(Self::Directory(PathBuf::from(target)))
+    }
+}
@@ -0,0 +1,721 @@
+//! The [`Command`]s, run against a [`Source`], printing to a writer.
+
+use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+        VecDeque,
+    },
+    fmt::Display,
+    fs::File,
+    io::{
+        self,
+        BufReader,
+        BufWriter,
+        Read,
+        Write,
+    },
+    path::Path,
+    process::ExitCode,
+};
+
+use base64::{
+    Engine as _,
+    engine::general_purpose::STANDARD,
+};
+use error_stack::{
+    Report,
+    ResultExt as _,
+};
+use eventric_stream::{
+    error::{
+        Error,
+        Result,
+    },
+    event::Version,
+    stream::{
+        Metadata,
+        Reader,
+        Stream,
+        operate::{
+            Condition,
+            query::Query,
+            select::{
+                EventAndMask,
+                Select as _,
+            },
+        },
+        replicate::{
+            Follower,
+            Shipper,
+            transport::socket::{
+                Receiver,
+                Transmitter,
+            },
+        },
+    },
+};
+use serde_json::{
+    Value,
+    json,
+};
+
+use crate::{
+    cli::{
+        Command,
+        Payload,
+        Read as ReadArgs,
+        Target,
+    },
+    source::{
+        Names,
+        Source,
+    },
+};
+
+// =================================================================================================
+// Command
+// =================================================================================================
+
+// Run `command` against the stream at `target`.
+pub(crate) fn run<W>(target: &Target, command: Command, out: &mut W) -> Result<ExitCode>
+where
+    W: Write,
+{
+    match command {
+        Command::Head { lines, read } => head(&Source::open(target)?, lines, &read, out),
+        Command::Len => len(&Source::open(target)?, out),
+        Command::Tail {
+            lines,
+            follow,
+            read,
+        } => tail(&Source::open(target)?, lines, follow, &read, out),
+        Command::Count { query } => count(&Source::open(target)?, query.as_deref(), out),
+        Command::Dump { read } => head(&Source::open(target)?, usize::MAX, &read, out),
+        Command::Stats => stats(&Source::open(target)?.local("stats")?, out),
+        Command::Verify => verify(&Source::open(target)?.local("verify")?, out),
+        Command::Export { file } => export(Source::open(target)?.local("export")?, &file, out),
+        Command::Import { file } => import(target, &file, out),
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Reading
+
+fn head<W>(source: &Source, lines: usize, read: &ReadArgs, out: &mut W) -> Result<ExitCode>
+where
+    W: Write,
+{
+    let names = source.names()?;
+
+    for event in source.select(condition(read.query.as_deref())?).take(lines) {
+        line(out, render(&event?, &names, read.payload))?;
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn len<W>(source: &Source, out: &mut W) -> Result<ExitCode>
+where
+    W: Write,
+{
+    let len = match source {
+        Source::Local(reader) => reader.len()?,
+        Source::Remote(_) => source
+            .select(Condition::new())
+            .try_fold(0, |len, event| event.map(|_| len + 1))?,
+    };
+
+    line(out, len)?;
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn tail<W>(
+    source: &Source,
+    lines: usize,
+    follow: bool,
+    read: &ReadArgs,
+    out: &mut W,
+) -> Result<ExitCode>
+where
+    W: Write,
+{
+    // A process writing to a stream's directory holds it while it is open, so
+    // a directory cannot be read again and again as its writer commits: a
+    // stream is followed through its server.
+    let client = match source {
+        Source::Local(_) if follow => {
+            return Err(Report::new(Error).attach(
+                "`tail -f` follows a stream through its server: give the server's address \
+                 (`tcp://host:port` or `unix://path`) as the target",
+            ));
+        }
+        Source::Remote(client) if follow => Some(client),
+        _ => None,
+    };
+
+    let names = source.names()?;
+    let condition = condition(read.query.as_deref())?;
+
+    // A local select reads backwards cheaply; a remote one is read forwards,
+    // keeping the last `lines` events, rather than held whole.
+    let events = match source {
+        Source::Local(_) => {
+            let mut events = source
+                .select(condition.clone())
+                .rev()
+                .take(lines)
+                .collect::<Result<Vec<_>>>()?;
+
+            events.reverse();
+            events
+        }
+        Source::Remote(_) => {
+            let mut events = VecDeque::with_capacity(lines.min(1024));
+
+            for event in source.select(condition.clone()) {
+                if events.len() == lines {
+                    events.pop_front();
+                }
+
+                if lines > 0 {
+                    events.push_back(event?);
+                }
+            }
+
+            events.into()
+        }
+    };
+
+    let last = events.last().map(|event| event.event.meta().position());
+
+    for event in &events {
+        line(out, render(event, &names, read.payload))?;
+    }
+
+    let Some(client) = client else {
+        return Ok(ExitCode::SUCCESS);
+    };
+
+    let condition = match last {
+        Some(last) => condition.from(last + 1),
+        None => condition,
+    };
+
+    let mut names = names;
+
+    for event in client.subscribe(condition) {
+        let event = event?;
+
+        // Events committed since may carry names not yet recorded when the
+        // names were read.
+        if !names.covers(&event.event) {
+            names = source.names()?;
+        }
+
+        line(out, render(&event, &names, read.payload))?;
+        out.flush()
+            .change_context(Error)
+            .attach("failed to write output")?;
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn count<W>(source: &Source, query: Option<&str>, out: &mut W) -> Result<ExitCode>
+where
+    W: Write,
+{
+    let names = source.names()?;
+    let mut events = 0_u64;
+    let mut tags = BTreeMap::<String, u64>::new();
+    let mut types = BTreeMap::<String, u64>::new();
+
+    for event in source.select(condition(query)?) {
+        let event = event?.event;
+
+        events += 1;
+        *types
+            .entry(names.ty(event.facets().ty().name().clone().into()))
+            .or_default() += 1;
+
+        for tag in event.facets().tags() {
+            *tags.entry(names.tag(tag.clone().into())).or_default() += 1;
+        }
+    }
+
+    pretty(
+        out,
+        &json!({ "events": events, "tags": tags, "types": types }),
+    )?;
+
+    Ok(ExitCode::SUCCESS)
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Inspection
+
+fn stats<W>(reader: &Reader, out: &mut W) -> Result<ExitCode>
+where
+    W: Write,
+{
+    let stats = reader.stats()?;
+    let catalog = reader.catalog()?;
+    let names = Names::from_catalog(&catalog);
+
+    let types = catalog
+        .types
+        .iter()
+        .map(|entry| {
+            let versions = entry
+                .versions
+                .iter()
+                .map(|(version, count)| (u8::from(*version).to_string(), *count))
+                .collect::<BTreeMap<_, _>>();
+
+            json!({
+                "name": names.ty(entry.hash),
+                "hash": entry.hash,
+                "count": entry.count,
+                "versions": versions,
+                "first": metadata(entry.first),
+                "last": metadata(entry.last),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let tags = catalog
+        .tags
+        .iter()
+        .map(|entry| {
+            json!({
+                "tag": names.tag(entry.hash),
+                "hash": entry.hash,
+                "count": entry.count,
+                "first": metadata(entry.first),
+                "last": metadata(entry.last),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    pretty(
+        out,
+        &json!({ "events": stats.events(), "tags": tags, "types": types }),
+    )?;
+
+    Ok(ExitCode::SUCCESS)
+}
+
+// What the events of one type name or tag were found to hold, by a scan.
+#[derive(Debug, Default)]
+struct Scanned {
+    count: u64,
+    first: Option<Metadata>,
+    last: Option<Metadata>,
+    versions: BTreeMap<Version, u64>,
+}
+
+impl Scanned {
+    fn add(&mut self, meta: Metadata, version: Version) {
+        self.count += 1;
+        self.first.get_or_insert(meta);
+        self.last = Some(meta);
+        *self.versions.entry(version).or_default() += 1;
+    }
+}
+
+// Scan every event, checking that their positions are dense and that the
+// stream's length, statistics (its cardinalities) and catalog (built from its
+// indices) all agree with what the events hold.
+fn verify<W>(reader: &Reader, out: &mut W) -> Result<ExitCode>
+where
+    W: Write,
+{
+    let catalog = reader.catalog()?;
+    let names = Names::from_catalog(&catalog);
+    let mut problems = Vec::new();
+    let Scan {
+        events,
+        mut tags,
+        mut types,
+    } = scan(reader, &mut problems)?;
+
+    let len = reader.len()?;
+
+    if len != events {
+        problems.push(format!(
+            "the stream's length is {len}, but it holds {events} events"
+        ));
+    }
+
+    let stats = reader.stats()?;
+
+    if stats.events() != events {
+        problems.push(format!(
+            "the statistics count {} events, but the stream holds {events}",
+            stats.events()
+        ));
+    }
+
+    compare(
+        "type",
+        &|hash| names.ty(hash),
+        &types,
+        &stats.types().collect(),
+        &mut problems,
+    );
+    compare(
+        "tag",
+        &|hash| names.tag(hash),
+        &tags,
+        &stats.tags().collect(),
+        &mut problems,
+    );
+
+    for entry in &catalog.types {
+        let expected = types.remove(&entry.hash).unwrap_or_default();
+        let indexed = Scanned {
+            count: entry.count,
+            first: Some(entry.first),
+            last: Some(entry.last),
+            versions: entry.versions.clone(),
+        };
+
+        indexes(
+            "type",
+            &names.ty(entry.hash),
+            &expected,
+            &indexed,
+            &mut problems,
+        );
+    }
+
+    for entry in &catalog.tags {
+        let mut expected = tags.remove(&entry.hash).unwrap_or_default();
+        let indexed = Scanned {
+            count: entry.count,
+            first: Some(entry.first),
+            last: Some(entry.last),
+            versions: BTreeMap::new(),
+        };
+
+        // Tags are indexed without the versions of the events carrying them.
+        expected.versions.clear();
+
+        indexes(
+            "tag",
+            &names.tag(entry.hash),
+            &expected,
+            &indexed,
+            &mut problems,
+        );
+    }
+
+    // Anything left was carried by events, but not found in the indices.
+    for (hash, scanned) in types {
+        problems.push(format!(
+            "type {}: {} events, but none are indexed",
+            names.ty(hash),
+            scanned.count
+        ));
+    }
+
+    for (hash, scanned) in tags {
+        problems.push(format!(
+            "tag {}: {} events, but none are indexed",
+            names.tag(hash),
+            scanned.count
+        ));
+    }
+
+    for problem in &problems {
+        line(out, format_args!("problem: {problem}"))?;
+    }
+
+    match problems.len() {
+        0 => {
+            line(out, format_args!("verified {events} events"))?;
+
+            Ok(ExitCode::SUCCESS)
+        }
+        count => {
+            line(
+                out,
+                format_args!("found {count} problems in {events} events"),
+            )?;
+
+            Ok(ExitCode::FAILURE)
+        }
+    }
+}
+
+// The events of a stream, in all and by type name and tag hash.
+struct Scan {
+    events: u64,
+    tags: BTreeMap<u64, Scanned>,
+    types: BTreeMap<u64, Scanned>,
+}
+
+// Scan every event, adding any position out of order, or any event outside
+// the batch it is recorded in, to `problems`. A batch runs from its first
+// event, which records its own position, until the next batch starts.
+fn scan(reader: &Reader, problems: &mut Vec<String>) -> Result<Scan> {
+    let mut batch = None;
+    let mut events = 0_u64;
+    let mut tags = BTreeMap::<u64, Scanned>::new();
+    let mut types = BTreeMap::<u64, Scanned>::new();
+
+    for event in reader.select(Condition::new()) {
+        let event = event?.event;
+        let meta = *event.meta();
+        let position = u64::from(meta.position());
+        let version = event.facets().ty().version();
+
+        if position != events {
+            problems.push(format!(
+                "event at position {position} where {events} was expected"
+            ));
+        }
+
+        if batch != Some(meta.batch()) && meta.batch() != meta.position() {
+            problems.push(format!(
+                "event at position {position} is recorded in the batch at {}, which does not hold \
+                 it",
+                u64::from(meta.batch())
+            ));
+        }
+
+        batch = Some(meta.batch());
+        events += 1;
+        types
+            .entry(event.facets().ty().name().clone().into())
+            .or_default()
+            .add(meta, version);
+
+        for tag in event.facets().tags() {
+            tags.entry(tag.clone().into())
+                .or_default()
+                .add(meta, version);
+        }
+    }
+
+    Ok(Scan {
+        events,
+        tags,
+        types,
+    })
+}
+
+// Compare the scanned counts of each type name or tag with the statistics'.
+fn compare(
+    kind: &str,
+    name: &dyn Fn(u64) -> String,
+    scanned: &BTreeMap<u64, Scanned>,
+    stats: &BTreeMap<u64, u64>,
+    problems: &mut Vec<String>,
+) {
+    let hashes = scanned.keys().chain(stats.keys()).collect::<BTreeSet<_>>();
+
+    for hash in hashes {
+        let scanned = scanned.get(hash).map_or(0, |scanned| scanned.count);
+        let counted = stats.get(hash).copied().unwrap_or_default();
+
+        if scanned != counted {
+            problems.push(format!(
+                "{kind} {}: {scanned} events, but the statistics count {counted}",
+                name(*hash)
+            ));
+        }
+    }
+}
+
+// Compare what the events of a type name or tag hold with its index.
+fn indexes(
+    kind: &str,
+    name: &str,
+    scanned: &Scanned,
+    indexed: &Scanned,
+    problems: &mut Vec<String>,
+) {
+    if scanned.count != indexed.count {
+        problems.push(format!(
+            "{kind} {name}: {} events, but {} are indexed",
+            scanned.count, indexed.count
+        ));
+    } else if scanned.first != indexed.first || scanned.last != indexed.last {
+        problems.push(format!(
+            "{kind} {name}: indexed from {} to {}, but its events are from {} to {}",
+            position(indexed.first),
+            position(indexed.last),
+            position(scanned.first),
+            position(scanned.last),
+        ));
+    } else if scanned.versions != indexed.versions {
+        problems.push(format!(
+            "{kind} {name}: indexed at other versions than its events"
+        ));
+    }
+}
+
+fn position(meta: Option<Metadata>) -> String {
+    meta.map_or_else(
+        || "none".to_owned(),
+        |meta| u64::from(meta.position()).to_string(),
+    )
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Export & Import
+
+// Export as a sequence of shipments, in the socket transport's framing, so
+// an import is a follower applying them.
+fn export<W>(reader: Reader, file: &Path, out: &mut W) -> Result<ExitCode>
+where
+    W: Write,
+{
+    if file == Path::new("-") {
+        Shipper::new(reader, Transmitter::new(out)).ship()?;
+    } else {
+        let writer = File::create(file)
+            .change_context(Error)
+            .attach_with(|| format!("failed to create {}", file.display()))?;
+        let exported = Shipper::new(reader, Transmitter::new(BufWriter::new(writer))).ship()?;
+
+        line(out, format_args!("exported {exported} events"))?;
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn import<W>(target: &Target, file: &Path, out: &mut W) -> Result<ExitCode>
+where
+    W: Write,
+{
+    let Target::Directory(path) = target else {
+        return Err(Report::new(Error).attach("import into a stream's directory, not a server"));
+    };
+
+    let stream = Stream::builder(path).open()?;
+
+    if !stream.is_empty() {
+        return Err(Report::new(Error).attach(format!(
+            "cannot import into {}: the stream is not empty",
+            path.display()
+        )));
+    }
+
+    let mut follower = Follower::new(stream);
+
+    if file == Path::new("-") {
+        follow(&mut follower, io::stdin().lock())?;
+    } else {
+        let reader = File::open(file)
+            .change_context(Error)
+            .attach_with(|| format!("failed to open {}", file.display()))?;
+
+        follow(&mut follower, BufReader::new(reader))?;
+    }
+
+    line(out, format_args!("imported {} events", follower.len()))?;
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn follow<R>(follower: &mut Follower, reader: R) -> Result<()>
+where
+    R: Read,
+{
+    follower
+        .follow(&mut Receiver::new(reader))
+        .attach("failed to import")
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Output
+
+fn condition(query: Option<&str>) -> Result<Condition> {
+    query.map_or_else(
+        || Ok(Condition::new()),
+        |query| Query::parse(query).map(Into::into),
+    )
+}
+
+// An event as a JSON object, with its type name and tags by name (tags in
+// order) and its payload encoded as `payload`. The mask of the selections it
+// matched is included only when the query had more than one.
+fn render(event: &EventAndMask, names: &Names, payload: Payload) -> Value {
+    let meta = event.event.meta();
+    let ty = event.event.facets().ty();
+    let data = event.event.data().as_ref();
+
+    let mut tags = event
+        .event
+        .facets()
+        .tags()
+        .iter()
+        .map(|tag| names.tag(tag.clone().into()))
+        .collect::<Vec<_>>();
+
+    tags.sort();
+
+    let data = match payload {
+        Payload::Hex => data
+            .iter()
+            .flat_map(|byte| [byte >> 4, byte & 0xf])
+            .map(|digit| char::from(HEX[usize::from(digit)]))
+            .collect(),
+        Payload::Base64 => STANDARD.encode(data),
+    };
+
+    let mut json = json!({
+        "position": u64::from(meta.position()),
+        "timestamp": u64::from(meta.timestamp()),
+        "batch": u64::from(meta.batch()),
+        "type": names.ty(ty.name().clone().into()),
+        "version": u8::from(ty.version()),
+        "tags": tags,
+        "data": data,
+    });
+
+    if event.mask.as_ref().len() > 1 {
+        json["mask"] = json!(event.mask.as_ref());
+    }
+
+    json
+}
+
+static HEX: &[u8; 16] = b"0123456789abcdef";
+
+fn metadata(meta: Metadata) -> Value {
+    json!({
+        "position": u64::from(meta.position()),
+        "timestamp": u64::from(meta.timestamp()),
+        "batch": u64::from(meta.batch()),
+    })
+}
+
+fn line<W, T>(out: &mut W, value: T) -> Result<()>
+where
+    W: Write,
+    T: Display,
+{
+    writeln!(out, "{value}")
+        .change_context(Error)
+        .attach("failed to write output")
+}
+
+fn pretty<W>(out: &mut W, value: &Value) -> Result<()>
+where
+    W: Write,
+{
+    let value = serde_json::to_string_pretty(value)
+        .change_context(Error)
+        .attach("failed to format output")?;
+
+    line(out, value)
+}
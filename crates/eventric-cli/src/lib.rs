@@ -0,0 +1,73 @@
+//! `eventric-cli` is the `eventric` binary, for inspecting and administering
+//! streams: it opens a stream from its directory (or reaches one through an
+//! `eventric-server`) and prints its events as JSON lines, counts them, shows
+//! its statistics and catalog, verifies its indices against its events, and
+//! exports and imports it.
+//!
+//! ```text
+//! eventric <TARGET> <COMMAND>
+//! ```
+//!
+//! | Command              | Prints                                                  |
+//! |----------------------|---------------------------------------------------------|
+//! | `head [-n N]`        | the first `N` (default 10) matching events              |
+//! | `tail [-n N] [-f]`   | the last `N` matching events, then (`-f`) each new one  |
+//! | `dump`               | every matching event                                    |
+//! | `len`                | the number of events in the stream                      |
+//! | `count`              | the number of matching events, by type name and by tag  |
+//! | `stats`              | the stream's statistics and catalog                     |
+//! | `verify`             | any disagreement between the indices and the events     |
+//! | `export <FILE>`      | the events, with positions and timestamps, to `FILE`    |
+//! | `import <FILE>`      | an export, from `FILE`, into an empty stream            |
+//!
+//! The reading commands take a `--query` in the syntax of a [`Query`] (all
+//! events, if not given), and print each event's payload as `--payload
+//! hex` (the default) or `base64`. A type name or tag without a recorded
+//! string form is printed as `#` and its hash's hex digits.
+//!
+//! The target is a stream's directory, or a server's address as
+//! `tcp://host:port` or `unix://path`. A directory is opened read-only by
+//! every command but `import`, so reading a stream creates nothing in its
+//! directory (the database's own recovery aside) and records no writer. A
+//! process writing to a directory still holds it while it is open, so a
+//! stream which a process is writing is reached through its server instead,
+//! and `tail -f` follows a stream only through its server. `stats`, `verify`
+//! and `export`, which read more than its events, need its directory. `export` and `import` use the replication [`Shipment`] format,
+//! so an imported stream holds the events at their original positions and
+//! timestamps, in their original batches.
+//!
+//! [`Query`]: eventric_stream::stream::operate::query::Query
+//! [`Shipment`]: eventric_stream::stream::replicate::Shipment
+
+#![allow(clippy::multiple_crate_versions)]
+#![allow(clippy::missing_errors_doc)]
+#![allow(clippy::missing_panics_doc)]
+#![deny(missing_docs)]
+
+pub mod cli;
+
+mod command;
+mod source;
+
+use std::{
+    io::Write,
+    process::ExitCode,
+};
+
+use eventric_stream::error::Result;
+
+use crate::cli::Cli;
+
+// =================================================================================================
+// Eventric CLI
+// =================================================================================================
+
+/// Run the command `cli` gives against its target, printing to `out`. The
+/// exit code is a failure if the command found the stream at fault (as when
+/// `verify` finds problems), and an error if it could not run.
+pub fn run<W>(cli: Cli, out: &mut W) -> Result<ExitCode>
+where
+    W: Write,
+{
+    command::run(&cli.target, cli.command, out)
+}
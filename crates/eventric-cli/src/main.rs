@@ -0,0 +1,22 @@
+use std::{
+    io,
+    process::ExitCode,
+};
+
+use clap::Parser as _;
+use eventric_cli::cli::Cli;
+
+// =================================================================================================
+// Eventric
+// =================================================================================================
+
+fn main() -> ExitCode {
+    match eventric_cli::run(Cli::parse(), &mut io::stdout().lock()) {
+        Ok(code) => code,
+        Err(report) => {
+            eprintln!("{report:?}");
+
+            ExitCode::FAILURE
+        }
+    }
+}
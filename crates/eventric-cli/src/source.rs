@@ -0,0 +1,155 @@
+//! The [`Source`] a command reads: a stream opened read-only from its
+//! directory, or one served by an `eventric-server`, through a [`Client`].
+
+use std::{
+    collections::BTreeMap,
+    path::Path,
+};
+
+use error_stack::Report;
+use eventric_server::client::Client;
+use eventric_stream::{
+    error::{
+        Error,
+        Result,
+    },
+    event::Event,
+    stream::{
+        Metadata,
+        Reader,
+        Stream,
+        catalog::Catalog,
+        operate::{
+            Condition,
+            select::{
+                Select as _,
+                SelectIter,
+            },
+        },
+    },
+};
+
+use crate::cli::Target;
+
+// =================================================================================================
+// Source
+// =================================================================================================
+
+#[derive(Debug)]
+pub(crate) enum Source {
+    Local(Reader),
+    Remote(Client),
+}
+
+impl Source {
+    // Open the stream at `target`, which must exist, for reading only: nothing
+    // is created in a stream's directory by reading it.
+    pub fn open(target: &Target) -> Result<Self> {
+        match target {
+            Target::Directory(path) => {
+                if !path.is_dir() {
+                    return Err(
+                        Report::new(Error).attach(format!("no stream at {}", path.display()))
+                    );
+                }
+
+                Self::read(path)
+            }
+            Target::Server(address) => Client::connect(address.clone()).map(Self::Remote),
+        }
+    }
+
+    // Open the stream in the directory at `path` for reading only.
+    pub fn read(path: &Path) -> Result<Self> {
+        Stream::builder(path).open_reader().map(Self::Local)
+    }
+
+    // The stream opened from its directory, for the commands which read more
+    // than its events.
+    pub fn local(self, command: &str) -> Result<Reader> {
+        match self {
+            Self::Local(reader) => Ok(reader),
+            Self::Remote(_) => Err(Report::new(Error).attach(format!(
+                "`{command}` reads the stream's directory: run it where the stream is stored"
+            ))),
+        }
+    }
+
+    pub fn select(&self, condition: Condition) -> SelectIter {
+        match self {
+            Self::Local(reader) => reader.select(condition),
+            Self::Remote(client) => client.select(condition),
+        }
+    }
+
+    pub fn names(&self) -> Result<Names> {
+        match self {
+            Self::Local(reader) => reader
+                .catalog()
+                .map(|catalog| Names::from_catalog(&catalog)),
+            Self::Remote(client) => {
+                let names = client.names()?;
+
+                Ok(Names {
+                    tags: names.tags.into_iter().collect(),
+                    types: names.types.into_iter().collect(),
+                })
+            }
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Names
+
+// The string forms of the type name and tag hashes a stream's events are read
+// with. A hash without a recorded name is shown as `#` and its hex digits.
+#[derive(Debug)]
+pub(crate) struct Names {
+    tags: BTreeMap<u64, String>,
+    types: BTreeMap<u64, String>,
+}
+
+impl Names {
+    pub fn from_catalog(catalog: &Catalog) -> Self {
+        Self {
+            tags: catalog
+                .tags
+                .iter()
+                .filter_map(|entry| Some((entry.hash, entry.tag.clone()?)))
+                .collect(),
+            types: catalog
+                .types
+                .iter()
+                .filter_map(|entry| Some((entry.hash, entry.name.clone()?)))
+                .collect(),
+        }
+    }
+
+    // Whether the names of `event`'s type name and tags are all known.
+    pub fn covers(&self, event: &Event<Metadata, u64>) -> bool {
+        self.types
+            .contains_key(&event.facets().ty().name().clone().into())
+            && event
+                .facets()
+                .tags()
+                .iter()
+                .all(|tag| self.tags.contains_key(&tag.clone().into()))
+    }
+
+    pub fn tag(&self, hash: u64) -> String {
+        name(&self.tags, hash)
+    }
+
+    pub fn ty(&self, hash: u64) -> String {
+        name(&self.types, hash)
+    }
+}
+
+fn name(names: &BTreeMap<u64, String>, hash: u64) -> String {
+    names
+        .get(&hash)
+        .cloned()
+        .unwrap_or_else(|| format!("#{hash:016x}"))
+}
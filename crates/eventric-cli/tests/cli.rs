@@ -0,0 +1,361 @@
+//! Integration tests for the `eventric` commands, run against a stream's
+//! directory and through a server: reading events by query as JSON lines,
+//! counting them, verifying a stream, and exporting it and importing the
+//! export elsewhere with its positions and timestamps intact.
+
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::{
+        self,
+        Write,
+    },
+    iter,
+    mem,
+    net::SocketAddr,
+    path::Path,
+    process::ExitCode,
+    sync::mpsc::{
+        self,
+        Sender,
+    },
+    thread,
+};
+
+use clap::Parser as _;
+use eventric_cli::cli::Cli;
+use eventric_server::server::{
+    Address,
+    Server,
+};
+use eventric_stream::{
+    error::Result,
+    event::{
+        Data,
+        Event,
+        Facets,
+        Name,
+        Tag,
+        Type,
+        Version,
+    },
+    stream::{
+        Stream,
+        concurrent::owner::Owner,
+        operate::{
+            Condition,
+            append::Append as _,
+        },
+    },
+    utils::temp_path,
+};
+use serde_json::{
+    Value,
+    json,
+};
+
+// =================================================================================================
+// Helpers
+// =================================================================================================
+
+fn event(name: &str, version: u8, data: &str, tags: &[&str]) -> Event<(), String> {
+    let ty = Type::new(Name::new(name).unwrap(), Version::new(version));
+    let tags = tags
+        .iter()
+        .map(|tag| Tag::new(*tag).unwrap())
+        .collect::<BTreeSet<_>>();
+
+    Event::new(Data::new(data).unwrap(), Facets::new(ty, tags), ())
+}
+
+// Four events of two types, at two versions, tagged by the courses and
+// students they concern.
+fn seed(stream: &mut Stream) {
+    stream
+        .append(
+            [
+                event("CourseDefined", 0, "c1", &["course:c1"]),
+                event("CourseDefined", 1, "c2", &["course:c2"]),
+                event("StudentSubscribed", 0, "s1c1", &["course:c1", "student:s1"]),
+                event("StudentSubscribed", 0, "s1c2", &["course:c2", "student:s1"]),
+            ],
+            Condition::new(),
+        )
+        .unwrap();
+}
+
+// A seeded stream's directory, closed so the commands can open it.
+fn seeded() -> String {
+    let path = temp_path();
+    let mut stream = Stream::builder(&path).open().unwrap();
+
+    seed(&mut stream);
+
+    path.to_str().unwrap().to_owned()
+}
+
+fn run(args: &[&str]) -> Result<(ExitCode, String)> {
+    let cli = Cli::try_parse_from(iter::once(&"eventric").chain(args)).unwrap();
+    let mut out = Vec::new();
+    let code = eventric_cli::run(cli, &mut out)?;
+
+    Ok((code, String::from_utf8(out).unwrap()))
+}
+
+fn output(args: &[&str]) -> String {
+    let (code, out) = run(args).unwrap();
+
+    assert_eq!(code, ExitCode::SUCCESS);
+
+    out
+}
+
+// Output sent on as it is written, a line at a time.
+struct Lines {
+    line: Vec<u8>,
+    sender: Sender<String>,
+}
+
+impl Lines {
+    fn new(sender: Sender<String>) -> Self {
+        Self {
+            line: Vec::new(),
+            sender,
+        }
+    }
+}
+
+impl Write for Lines {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for byte in buf {
+            match byte {
+                b'\n' => {
+                    let line = String::from_utf8(mem::take(&mut self.line)).unwrap();
+
+                    self.sender.send(line).ok();
+                }
+                byte => self.line.push(*byte),
+            }
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn lines(out: &str) -> Vec<Value> {
+    out.lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+fn positions(out: &str) -> Vec<u64> {
+    lines(out)
+        .iter()
+        .map(|event| event["position"].as_u64().unwrap())
+        .collect()
+}
+
+// =================================================================================================
+// Tests
+// =================================================================================================
+
+// Events are printed as JSON lines, with their names, tags and payloads, and
+// read by query from the front or back.
+#[test]
+fn reads_events_by_query() {
+    let path = seeded();
+
+    let dump = lines(&output(&[&path, "dump"]));
+
+    assert_eq!(dump.len(), 4);
+    assert_eq!(dump[2]["type"], json!("StudentSubscribed"));
+    assert_eq!(dump[2]["version"], json!(0));
+    assert_eq!(dump[2]["tags"], json!(["course:c1", "student:s1"]));
+    assert_eq!(dump[2]["data"], json!("73316331"));
+    assert!(dump[2]["timestamp"].is_u64());
+    assert_eq!(dump[2]["batch"], json!(0));
+    assert!(dump[2].get("mask").is_none());
+
+    let base64 = lines(&output(&[&path, "dump", "--payload", "base64"]));
+
+    assert_eq!(base64[2]["data"], json!("czFjMQ=="));
+    assert_eq!(
+        positions(&output(&[&path, "dump", "-q", "tag:student:s1"])),
+        [2, 3]
+    );
+    assert_eq!(
+        positions(&output(&[
+            &path,
+            "dump",
+            "-q",
+            "CourseDefined@1.. | tag:course:c1"
+        ])),
+        [0, 1, 2]
+    );
+    assert_eq!(
+        positions(&output(&[&path, "dump", "-q", "CourseDefined; batch 2"])),
+        [0, 1]
+    );
+    assert_eq!(positions(&output(&[&path, "head", "-n", "2"])), [0, 1]);
+    assert_eq!(positions(&output(&[&path, "tail", "-n", "3"])), [1, 2, 3]);
+    assert_eq!(
+        positions(&output(&[&path, "tail", "-n", "1", "-q", "CourseDefined"])),
+        [1]
+    );
+
+    let masked = lines(&output(&[
+        &path,
+        "dump",
+        "-q",
+        "CourseDefined; tag:course:c1",
+    ]));
+
+    assert_eq!(masked[0]["mask"], json!([true, true]));
+    assert_eq!(masked[1]["mask"], json!([true, false]));
+    assert_eq!(output(&[&path, "len"]), "4\n");
+}
+
+#[test]
+fn rejects_malformed_queries_and_missing_streams() {
+    let path = seeded();
+
+    assert!(run(&[&path, "dump", "-q", "CourseDefined &"]).is_err());
+    assert!(run(&["/no/such/stream", "len"]).is_err());
+}
+
+// Matching events are counted in all, by type name and by tag.
+#[test]
+fn counts_events_by_type_and_tag() {
+    let path = seeded();
+
+    let count: Value =
+        serde_json::from_str(&output(&[&path, "count", "-q", "tag:course:c2"])).unwrap();
+
+    assert_eq!(
+        count,
+        json!({
+            "events": 2,
+            "tags": { "course:c2": 2, "student:s1": 1 },
+            "types": { "CourseDefined": 1, "StudentSubscribed": 1 },
+        })
+    );
+
+    let stats: Value = serde_json::from_str(&output(&[&path, "stats"])).unwrap();
+
+    assert_eq!(stats["events"], json!(4));
+    assert_eq!(stats["types"].as_array().unwrap().len(), 2);
+    assert_eq!(stats["tags"].as_array().unwrap().len(), 3);
+}
+
+#[test]
+fn verifies_a_consistent_stream() {
+    let path = seeded();
+
+    assert_eq!(output(&[&path, "verify"]), "verified 4 events\n");
+}
+
+// A directory is read without being opened for writing, so alongside a
+// writer open in this process, but is not followed: its writer holds it, so
+// `tail -f` refuses it at once, pointing at the stream's server.
+#[test]
+fn reads_a_directory_alongside_its_writer_and_refuses_to_follow_it() {
+    let path = temp_path();
+    let mut stream = Stream::builder(&path).open().unwrap();
+
+    seed(&mut stream);
+
+    let target = path.to_str().unwrap().to_owned();
+
+    assert_eq!(output(&[&target, "len"]), "4\n");
+    assert_eq!(output(&[&target, "verify"]), "verified 4 events\n");
+
+    let followed = run(&[&target, "tail", "-f", "-n", "1", "-q", "tag:student:s1"]).unwrap_err();
+
+    assert!(format!("{followed:?}").contains("tcp://host:port"));
+    assert_eq!(stream.epoch(), 1);
+}
+
+// An export imported into a new stream holds the same events, at the same
+// positions and with the same timestamps; an import into a stream which is
+// not empty is refused.
+#[test]
+fn exports_and_imports_a_stream() {
+    let path = seeded();
+    let export = temp_path();
+    let export = export.to_str().unwrap();
+    let imported = temp_path();
+    let imported = imported.to_str().unwrap();
+
+    assert_eq!(output(&[&path, "export", export]), "exported 4 events\n");
+    assert_eq!(output(&[imported, "import", export]), "imported 4 events\n");
+    assert_eq!(output(&[imported, "dump"]), output(&[&path, "dump"]));
+    assert_eq!(output(&[imported, "verify"]), "verified 4 events\n");
+    assert!(run(&[&path, "import", export]).is_err());
+
+    for path in [Path::new(&path), Path::new(imported)] {
+        fs::remove_dir_all(path).ok();
+    }
+
+    fs::remove_file(export).ok();
+}
+
+// Through a server, events are read as from a directory, and `tail -f` goes
+// on printing each new match until the stream's writer stops. The commands
+// which read the directory itself are refused.
+#[test]
+fn follows_a_served_stream() {
+    let mut stream = Stream::builder(temp_path()).temporary(true).open().unwrap();
+
+    seed(&mut stream);
+
+    let owner = Owner::new(stream);
+    let address = Address::Tcp(SocketAddr::from(([127, 0, 0, 1], 0)));
+    let handle = Server::bind(owner.proxy(), &address).unwrap().spawn();
+    let Address::Tcp(address) = handle.address() else {
+        unreachable!()
+    };
+    let target = format!("tcp://{address}");
+
+    assert_eq!(positions(&output(&[&target, "tail", "-n", "2"])), [2, 3]);
+    assert!(run(&[&target, "verify"]).is_err());
+
+    let (sender, receiver) = mpsc::channel();
+    let tail = thread::spawn(move || {
+        let args = [&target, "tail", "-f", "-n", "1", "-q", "tag:student:s1"];
+        let cli = Cli::try_parse_from(iter::once(&"eventric").chain(&args)).unwrap();
+
+        eventric_cli::run(cli, &mut Lines::new(sender)).unwrap()
+    });
+    let next = || serde_json::from_str::<Value>(&receiver.recv().unwrap()).unwrap();
+
+    assert_eq!(next()["position"], json!(3));
+
+    // The subscription may start before or after these are committed: either
+    // way, the match is printed once.
+    owner
+        .proxy()
+        .append(
+            [
+                event("StudentSubscribed", 0, "s2c1", &["course:c1", "student:s2"]),
+                event("CourseCancelled", 0, "s1", &["student:s1"]),
+            ],
+            Condition::new(),
+        )
+        .unwrap();
+
+    let followed = next();
+
+    assert_eq!(followed["position"], json!(5));
+    assert_eq!(followed["type"], json!("CourseCancelled"));
+
+    owner.into_inner().unwrap();
+
+    assert_eq!(tail.join().unwrap(), ExitCode::SUCCESS);
+    assert!(receiver.try_recv().is_err());
+
+    handle.shutdown().unwrap();
+}
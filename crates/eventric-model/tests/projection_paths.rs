@@ -22,6 +22,7 @@ use eventric_model::{
     },
 };
 use eventric_stream::stream::{
+    Backend,
     Stream,
     operate::{
         Condition,
@@ -88,7 +89,7 @@ fn append<E: Event>(stream: &mut Stream, event: &E) -> Result<(), Report<Error>>
 #[test]
 fn qualified_event_paths_compile_and_fold() -> Result<(), Report<Error>> {
     let mut stream = Stream::builder(eventric_stream::utils::temp_path())
-        .temporary(true)
+        .backend(Backend::Memory)
         .open()
         .change_context(Error)?;
 
@@ -88,5 +88,5 @@ fn minimal_event_serialises_non_empty_and_appends() {
     let appended = events.take();
 
     assert_eq!(appended.len(), 1);
-    assert!(!appended[0].data().as_ref().is_empty());
+    assert_ne!(appended[0].data().as_ref(), []);
 }
@@ -29,6 +29,7 @@ use eventric_model::{
 };
 use eventric_runtime::reactor::Reactor;
 use eventric_stream::stream::{
+    Backend,
     Stream,
     operate::{
         Condition,
@@ -166,7 +167,7 @@ fn request(course: &str, student: &str) -> EnrolmentRequested {
 #[test]
 fn reactor_drive_routes_commands_through_actions() {
     let mut stream = Stream::builder(eventric_stream::utils::temp_path())
-        .temporary(true)
+        .backend(Backend::Memory)
         .open()
         .unwrap();
 
@@ -34,6 +34,7 @@ use eventric_stream::{
         Tag,
     },
     stream::{
+        Backend,
         Stream,
         operate::{
             Condition,
@@ -241,7 +242,7 @@ impl Act<register_item_strict::Projections> for RegisterItemStrict {
 
 fn stream() -> Stream {
     Stream::builder(eventric_stream::utils::temp_path())
-        .temporary(true)
+        .backend(Backend::Memory)
         .open()
         .expect("open temporary stream")
 }
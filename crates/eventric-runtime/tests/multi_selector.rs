@@ -42,7 +42,10 @@ use eventric_model::{
     },
 };
 use eventric_runtime::enactor::Enactor as _;
-use eventric_stream::stream::Stream;
+use eventric_stream::stream::{
+    Backend,
+    Stream,
+};
 use fancy_constructor::new;
 use revision::revisioned;
 
@@ -353,7 +356,7 @@ impl Act<read_deposit_stats::Projections> for ReadDepositStats {
 
 fn stream() -> Stream {
     Stream::builder(eventric_stream::utils::temp_path())
-        .temporary(true)
+        .backend(Backend::Memory)
         .open()
         .expect("open temporary stream")
 }
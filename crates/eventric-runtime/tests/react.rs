@@ -20,6 +20,7 @@ use eventric_model::{
 use eventric_runtime::reactor::Reactor;
 use eventric_stream::{
     stream::{
+        Backend,
         Stream,
         operate::{
             Condition,
@@ -91,7 +92,10 @@ fn subscribed(course: &str, student: &str) -> Subscribed {
 
 #[test]
 fn reactor_folds_events_into_the_view() {
-    let stream = Stream::builder(temp_path()).temporary(true).open().unwrap();
+    let stream = Stream::builder(temp_path())
+        .backend(Backend::Memory)
+        .open()
+        .unwrap();
     let (reader, mut writer) = stream.split();
 
     append(&mut writer, &[
@@ -0,0 +1,347 @@
+//! The [`Client`]: a remote stream, reached through a server over the
+//! [protocol](crate), behind the same [`Append`] and [`Select`] traits as a
+//! local one — so anything written against those traits (an `Enactor`, a
+//! `Reactor`) runs against a remote stream unchanged.
+
+use std::{
+    collections::VecDeque,
+    iter,
+    sync::{
+        Arc,
+        Mutex,
+    },
+};
+
+use error_stack::Report;
+use eventric_stream::{
+    error::{
+        Conflict,
+        Error,
+        Result,
+    },
+    event::Event,
+    stream::{
+        Durability,
+        operate::{
+            Condition,
+            append::{
+                Append,
+                AppendReceipt,
+            },
+            explain::{
+                Analysis,
+                Plan,
+            },
+            select::{
+                EventAndMask,
+                Select,
+                SelectIter,
+            },
+        },
+    },
+};
+
+use crate::{
+    connection::Connection,
+    protocol::{
+        self,
+        Request,
+        Response,
+    },
+    server::Address,
+};
+
+// =================================================================================================
+// Client
+// =================================================================================================
+
+/// A client of a stream served at an [`Address`], implementing [`Append`] and
+/// [`Select`] over the network.
+///
+/// An append rejected by its condition fails with the same [`Conflict`]
+/// attachment a local append does. A select streams its events as the server
+/// sends them, one at a time, rather than reading the whole result first.
+///
+/// Each request runs on a connection of its own, taken from those the client
+/// (and its clones, which share them) holds idle, or opened for it, so
+/// selects may be read concurrently with each other and with appends. A
+/// select read to its end returns its connection to be reused; one dropped
+/// early closes it.
+///
+/// # Panics
+///
+/// [`Select::explain`] cannot return an error, so it panics if the server
+/// cannot be reached.
+#[derive(Clone, Debug)]
+pub struct Client {
+    address: Address,
+    idle: Arc<Mutex<Vec<Connection>>>,
+}
+
+impl Client {
+    /// Connect to the server at `address`. The connection is kept for the
+    /// client's first request.
+    pub fn connect(address: Address) -> Result<Self> {
+        let connection = Connection::connect(&address)?;
+        let idle = Arc::new(Mutex::new(vec![connection]));
+
+        Ok(Self { address, idle })
+    }
+
+    /// The address of the server.
+    #[must_use]
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    /// The recorded string names of the type name and tag hashes events are
+    /// selected with.
+    pub fn names(&self) -> Result<Names> {
+        match self.call(&Request::Names)? {
+            Response::Names(types, tags) => Ok(Names { tags, types }),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    /// Subscribe to the events matching `condition`: those already committed,
+    /// then each new match as it is committed. The iterator ends once the
+    /// stream's writer has stopped; drop it to unsubscribe.
+    pub fn subscribe(
+        &self,
+        condition: Condition,
+    ) -> impl Iterator<Item = Result<EventAndMask>> + Send + use<> {
+        let connection = self.send(&Request::Subscribe(condition));
+
+        // The server closes a subscription's connection when it ends, so it
+        // is not kept for reuse.
+        Selected::new(None, connection)
+    }
+}
+
+impl Client {
+    // Send `request` on an idle (or new) connection, returning the connection
+    // to read the response from.
+    fn send(&self, request: &Request) -> Result<Connection> {
+        let idle = match self.idle.lock() {
+            Ok(mut idle) => idle.pop(),
+            Err(poisoned) => poisoned.into_inner().pop(),
+        };
+
+        let mut connection = match idle {
+            Some(connection) => connection,
+            None => Connection::connect(&self.address)?,
+        };
+
+        protocol::write(&mut connection, &protocol::encode(request))?;
+
+        Ok(connection)
+    }
+
+    // Send `request` and read its single response, keeping the connection
+    // for reuse once it is answered.
+    fn call(&self, request: &Request) -> Result<Response> {
+        let mut connection = self.send(request)?;
+        let response = receive(&mut connection)?;
+
+        self.release(connection);
+
+        Ok(response)
+    }
+
+    fn release(&self, connection: Connection) {
+        match self.idle.lock() {
+            Ok(mut idle) => idle.push(connection),
+            Err(poisoned) => poisoned.into_inner().push(connection),
+        }
+    }
+
+    fn append_with<E>(
+        &self,
+        events: E,
+        condition: Condition,
+        durability: Option<Durability>,
+    ) -> Result<AppendReceipt>
+    where
+        E: IntoIterator<Item = Event<(), String>>,
+    {
+        let events = events.into_iter().collect();
+
+        match self.call(&Request::Append(condition, events, durability))? {
+            Response::Appended(receipt) => Ok(receipt),
+            Response::Conflict => Err(Report::new(Error).attach(Conflict)),
+            response => Err(unexpected(response)),
+        }
+    }
+}
+
+impl Append for Client {
+    fn append<E>(&mut self, events: E, condition: Condition) -> Result<AppendReceipt>
+    where
+        E: IntoIterator<Item = Event<(), String>>,
+        E::IntoIter: Send + 'static,
+    {
+        self.append_with(events, condition, None)
+    }
+
+    fn append_durable<E>(
+        &mut self,
+        events: E,
+        condition: Condition,
+        durability: Durability,
+    ) -> Result<AppendReceipt>
+    where
+        E: IntoIterator<Item = Event<(), String>>,
+        E::IntoIter: Send + 'static,
+    {
+        self.append_with(events, condition, Some(durability))
+    }
+}
+
+impl Select for Client {
+    fn select(&self, condition: Condition) -> SelectIter {
+        let connection = self.send(&Request::Select(condition));
+
+        SelectIter::from_masked(Selected::new(Some(self.clone()), connection))
+    }
+
+    fn explain(&self, condition: Condition) -> Plan {
+        match self.call(&Request::Explain(condition)) {
+            Ok(Response::Planned(plan)) => plan,
+            Ok(response) => panic!("failed to explain remotely: {:?}", unexpected(response)),
+            Err(report) => panic!("failed to explain remotely: {report:?}"),
+        }
+    }
+
+    fn analyze(&self, condition: Condition) -> Result<Analysis> {
+        match self.call(&Request::Analyze(condition))? {
+            Response::Analyzed(analysis) => Ok(analysis),
+            response => Err(unexpected(response)),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Names
+
+/// The recorded string names of a served stream's type name and tag hashes,
+/// from [`Client::names`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Names {
+    /// Each tag, as `(hash, tag)`.
+    pub tags: Vec<(u64, String)>,
+    /// Each type name, as `(hash, name)`.
+    pub types: Vec<(u64, String)>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Selected
+
+// The events of a remote select (or subscription), read from its connection
+// one frame at a time, returning the connection to the client (if any) once
+// they end. Reading from the back first reads (and holds) the rest of the
+// events, as the server only sends them forwards.
+struct Selected {
+    client: Option<Client>,
+    connection: Option<Connection>,
+    failure: Option<Report<Error>>,
+    remaining: Option<VecDeque<Result<EventAndMask>>>,
+}
+
+impl Selected {
+    fn new(client: Option<Client>, connection: Result<Connection>) -> Self {
+        let (connection, failure) = match connection {
+            Ok(connection) => (Some(connection), None),
+            Err(report) => (None, Some(report)),
+        };
+
+        Self {
+            client,
+            connection,
+            failure,
+            remaining: None,
+        }
+    }
+
+    fn read(&mut self) -> Option<Result<EventAndMask>> {
+        if let Some(report) = self.failure.take() {
+            return Some(Err(report));
+        }
+
+        let mut connection = self.connection.take()?;
+
+        match receive(&mut connection) {
+            Ok(Response::Event(event)) => {
+                self.connection = Some(connection);
+
+                Some(Ok(event))
+            }
+            Ok(Response::End) => {
+                self.release(connection);
+
+                None
+            }
+            Ok(Response::Failed(description)) => {
+                self.release(connection);
+
+                Some(Err(failed(description)))
+            }
+            Ok(response) => Some(Err(unexpected(response))),
+            Err(report) => Some(Err(report)),
+        }
+    }
+
+    fn release(&self, connection: Connection) {
+        if let Some(client) = &self.client {
+            client.release(connection);
+        }
+    }
+}
+
+impl DoubleEndedIterator for Selected {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining.is_none() {
+            let remaining = iter::from_fn(|| self.read()).collect();
+
+            self.remaining = Some(remaining);
+        }
+
+        self.remaining.as_mut()?.pop_back()
+    }
+}
+
+impl Iterator for Selected {
+    type Item = Result<EventAndMask>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.remaining {
+            Some(remaining) => remaining.pop_front(),
+            None => self.read(),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Responses
+
+fn receive(connection: &mut Connection) -> Result<Response> {
+    match protocol::read(connection)? {
+        Some(frame) => protocol::decode(&frame),
+        None => Err(Report::new(Error).attach("server closed the connection")),
+    }
+}
+
+fn failed(description: String) -> Report<Error> {
+    Report::new(Error).attach(description)
+}
+
+// A response which does not answer the request: the server's failure, or a
+// protocol error.
+fn unexpected(response: Response) -> Report<Error> {
+    match response {
+        Response::Failed(description) => failed(description),
+        response => Report::new(Error).attach(format!("unexpected response {response:?}")),
+    }
+}
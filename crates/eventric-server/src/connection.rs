@@ -0,0 +1,73 @@
+//! A [`Connection`] over either kind of socket a server listens on.
+
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::{
+    io::{
+        self,
+        Read,
+        Write,
+    },
+    net::TcpStream,
+};
+
+use error_stack::ResultExt as _;
+use eventric_stream::error::{
+    Error,
+    Result,
+};
+
+use crate::server::Address;
+
+// =================================================================================================
+// Connection
+// =================================================================================================
+
+#[derive(Debug)]
+pub(crate) enum Connection {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl Connection {
+    pub fn connect(address: &Address) -> Result<Self> {
+        let connection = match address {
+            Address::Tcp(address) => TcpStream::connect(address).map(Self::Tcp),
+            #[cfg(unix)]
+            Address::Unix(path) => UnixStream::connect(path).map(Self::Unix),
+        };
+
+        connection
+            .change_context(Error)
+            .attach_with(|| format!("failed to connect to {address:?}"))
+    }
+}
+
+impl Read for Connection {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Tcp(stream) => stream.read(buf),
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Connection {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Tcp(stream) => stream.write(buf),
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Tcp(stream) => stream.flush(),
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.flush(),
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! `eventric-server` puts a stream behind a network protocol, for services
+//! which cannot link `eventric-stream` itself: a [`Server`](server::Server)
+//! accepts connections on a TCP or Unix socket and serves each through a
+//! [`Proxy`] of an [`Owner`]'s stream, so selects (with their masks),
+//! conditional appends (with conflicts reported as such) and subscriptions
+//! are all available to a client in any language. A Rust service can use a
+//! [`Client`](client::Client) instead: it implements the stream's `Append`
+//! and `Select` traits over the protocol.
+//!
+//! # Protocol
+//!
+//! A connection carries frames both ways: each frame is a big-endian `u32`
+//! byte length followed by that many bytes (at most
+//! [`MAX_FRAME_LEN`](protocol::MAX_FRAME_LEN)), holding one message. A message
+//! is a `u8` kind then its payload, whose values are encoded as described in
+//! [`eventric_stream::wire`] (big-endian integers, length-prefixed strings and
+//! data, conditions whose type names and tags may be sent as strings).
+//!
+//! The client sends a request and reads the server's responses to it before
+//! sending the next:
+//!
+//! | Request       | Kind   | Payload                                      |
+//! |---------------|--------|----------------------------------------------|
+//! | `Select`      | `0x01` | condition                                    |
+//! | `Append`      | `0x02` | condition, `u32` count, candidate events, optional durability |
+//! | `Subscribe`   | `0x03` | condition                                    |
+//! | `Names`       | `0x04` | —                                            |
+//! | `Explain`     | `0x05` | condition                                    |
+//! | `Analyze`     | `0x06` | condition                                    |
+//!
+//! | Response      | Kind   | Payload                                      |
+//! |---------------|--------|----------------------------------------------|
+//! | `Event`       | `0x01` | matched event (with its mask)                |
+//! | `End`         | `0x02` | —                                            |
+//! | `Appended`    | `0x03` | append receipt                               |
+//! | `Conflict`    | `0x04` | —                                            |
+//! | `Failed`      | `0x05` | string describing the failure                |
+//! | `Names`       | `0x06` | types then tags: `u32` count, (`u64` hash, string) each |
+//! | `Planned`     | `0x07` | plan                                         |
+//! | `Analyzed`    | `0x08` | analysis                                     |
+//!
+//! - `Select` is answered by an `Event` per matching event, in position order,
+//!   then `End` — or `Failed`, which also ends it.
+//! - `Append` is answered by `Appended`, or `Conflict` if the condition's DCB
+//!   check rejected it, or `Failed`. Its durability is a `u8` flag, `1`
+//!   followed by the durability to persist the commit with, or `0` for the
+//!   stream's default.
+//! - `Subscribe` is answered as `Select`, except that instead of `End` the
+//!   server goes on sending an `Event` for each new match as it is committed;
+//!   `End` comes only once the stream's writer has stopped. The connection
+//!   carries nothing else from then on: close it to unsubscribe.
+//! - `Names` is answered by `Names`: the recorded string names of the hashes
+//!   the events are returned with.
+//! - `Explain` is answered by `Planned`, and `Analyze` by `Analyzed` or
+//!   `Failed`.
+//! - A request which cannot be decoded is answered by `Failed`, and the
+//!   connection stays open.
+//!
+//! [`Proxy`]: eventric_stream::stream::concurrent::proxy::Proxy
+//! [`Owner`]: eventric_stream::stream::concurrent::owner::Owner
+
+#![allow(clippy::multiple_crate_versions)]
+#![allow(clippy::missing_errors_doc)]
+#![allow(clippy::missing_panics_doc)]
+#![deny(missing_docs)]
+
+pub mod client;
+pub mod protocol;
+pub mod server;
+
+mod connection;
+
+// =================================================================================================
+// Eventric Server
+// =================================================================================================
@@ -0,0 +1,350 @@
+//! The protocol's messages — the [`Request`]s a client sends and the
+//! [`Response`]s the server answers with — and the length-prefixed frames
+//! which carry them (see the [crate docs](crate) for the protocol itself).
+
+use std::io::{
+    ErrorKind,
+    Read,
+    Write,
+};
+
+use error_stack::{
+    Report,
+    ResultExt as _,
+};
+use eventric_stream::{
+    error::{
+        Error,
+        Result,
+    },
+    event::Event,
+    stream::{
+        Durability,
+        operate::{
+            Condition,
+            append::AppendReceipt,
+            explain::{
+                Analysis,
+                Plan,
+            },
+            select::EventAndMask,
+        },
+    },
+    wire::{
+        Decode,
+        Encode,
+    },
+};
+
+// =================================================================================================
+// Protocol
+// =================================================================================================
+
+// Constants
+
+/// The largest frame either end accepts, in bytes (64 MiB).
+pub static MAX_FRAME_LEN: u32 = 64 * 1024 * 1024;
+
+static SELECT: u8 = 0x01;
+static APPEND: u8 = 0x02;
+static SUBSCRIBE: u8 = 0x03;
+static NAMES: u8 = 0x04;
+static EXPLAIN: u8 = 0x05;
+static ANALYZE: u8 = 0x06;
+
+static EVENT: u8 = 0x01;
+static END: u8 = 0x02;
+static APPENDED: u8 = 0x03;
+static CONFLICT: u8 = 0x04;
+static FAILED: u8 = 0x05;
+static NAMED: u8 = 0x06;
+static PLANNED: u8 = 0x07;
+static ANALYZED: u8 = 0x08;
+
+// -------------------------------------------------------------------------------------------------
+
+// Frames
+
+/// Read one frame from `reader`: a big-endian `u32` length and that many
+/// bytes. Returns `None` if `reader` ends cleanly between frames.
+pub fn read<R>(reader: &mut R) -> Result<Option<Vec<u8>>>
+where
+    R: Read,
+{
+    let mut len = [0; size_of::<u32>()];
+
+    match reader.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => {
+            return Err(err)
+                .change_context(Error)
+                .attach("failed to read frame length");
+        }
+    }
+
+    let len = u32::from_be_bytes(len);
+
+    if len > MAX_FRAME_LEN {
+        return Err(Report::new(Error).attach("frame exceeds the maximum frame size"));
+    }
+
+    let mut frame = vec![0; len as usize];
+
+    reader
+        .read_exact(&mut frame)
+        .change_context(Error)
+        .attach("failed to read frame")?;
+
+    Ok(Some(frame))
+}
+
+/// Write `frame` to `writer`, prefixed by its length, and flush it.
+pub fn write<W>(writer: &mut W, frame: &[u8]) -> Result<()>
+where
+    W: Write,
+{
+    let len = u32::try_from(frame.len())
+        .ok()
+        .filter(|len| *len <= MAX_FRAME_LEN)
+        .ok_or_else(|| Report::new(Error).attach("frame exceeds the maximum frame size"))?;
+
+    // One write per frame, so that its length and bytes go out together
+    // rather than waiting on the peer to acknowledge the length alone.
+    let mut buffer = Vec::with_capacity(size_of::<u32>() + frame.len());
+
+    buffer.extend_from_slice(&len.to_be_bytes());
+    buffer.extend_from_slice(frame);
+
+    writer
+        .write_all(&buffer)
+        .and_then(|()| writer.flush())
+        .change_context(Error)
+        .attach("failed to write frame")
+}
+
+/// Decode a whole frame as a `T`, rejecting trailing bytes.
+pub fn decode<T>(mut frame: &[u8]) -> Result<T>
+where
+    T: Decode,
+{
+    let value = T::decode(&mut frame)?;
+
+    if !frame.is_empty() {
+        return Err(Report::new(Error).attach("frame has trailing bytes"));
+    }
+
+    Ok(value)
+}
+
+/// Encode `value` as a whole frame.
+#[must_use]
+pub fn encode<T>(value: &T) -> Vec<u8>
+where
+    T: Encode,
+{
+    let mut frame = Vec::new();
+
+    value.encode(&mut frame);
+    frame
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Request
+
+/// A request from a client, one per frame.
+#[derive(Debug)]
+pub enum Request {
+    /// Select the events matching a condition, each with its mask. Answered
+    /// by an [`Event`](Response::Event) per match, then [`End`](Response::End)
+    /// (or [`Failed`](Response::Failed), which also ends the select).
+    Select(Condition),
+    /// Append candidate events under a condition, persisted with the given
+    /// durability (or the stream's default). Answered by
+    /// [`Appended`](Response::Appended), [`Conflict`](Response::Conflict) or
+    /// [`Failed`](Response::Failed).
+    Append(Condition, Vec<Event<(), String>>, Option<Durability>),
+    /// Subscribe to the events matching a condition: answered as a select,
+    /// but instead of ending, the server goes on sending each new match as it
+    /// is committed. The connection carries nothing else from then on.
+    Subscribe(Condition),
+    /// The recorded names of the stream's type names and tags, to resolve the
+    /// hashes events are returned with. Answered by
+    /// [`Names`](Response::Names).
+    Names,
+    /// The plan a condition would be run with. Answered by
+    /// [`Planned`](Response::Planned).
+    Explain(Condition),
+    /// Run a condition as a select would, counting what it cost. Answered by
+    /// [`Analyzed`](Response::Analyzed) or [`Failed`](Response::Failed).
+    Analyze(Condition),
+}
+
+impl Encode for Request {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        match self {
+            Self::Select(condition) => {
+                SELECT.encode(bytes);
+                condition.encode(bytes);
+            }
+            Self::Append(condition, events, durability) => {
+                APPEND.encode(bytes);
+                condition.encode(bytes);
+                count(events.len()).encode(bytes);
+
+                for event in events {
+                    event.encode(bytes);
+                }
+
+                match durability {
+                    Some(durability) => {
+                        1u8.encode(bytes);
+                        durability.encode(bytes);
+                    }
+                    None => 0u8.encode(bytes),
+                }
+            }
+            Self::Subscribe(condition) => {
+                SUBSCRIBE.encode(bytes);
+                condition.encode(bytes);
+            }
+            Self::Names => NAMES.encode(bytes),
+            Self::Explain(condition) => {
+                EXPLAIN.encode(bytes);
+                condition.encode(bytes);
+            }
+            Self::Analyze(condition) => {
+                ANALYZE.encode(bytes);
+                condition.encode(bytes);
+            }
+        }
+    }
+}
+
+impl Decode for Request {
+    fn decode(bytes: &mut &[u8]) -> Result<Self> {
+        match u8::decode(bytes)? {
+            kind if kind == SELECT => Condition::decode(bytes).map(Self::Select),
+            kind if kind == APPEND => {
+                let condition = Condition::decode(bytes)?;
+                let events = (0..u32::decode(bytes)?)
+                    .map(|_| Event::decode(bytes))
+                    .collect::<Result<_>>()?;
+                let durability = match u8::decode(bytes)? {
+                    0 => None,
+                    1 => Some(Durability::decode(bytes)?),
+                    flag => {
+                        return Err(Report::new(Error).attach(format!("invalid flag {flag}")));
+                    }
+                };
+
+                Ok(Self::Append(condition, events, durability))
+            }
+            kind if kind == SUBSCRIBE => Condition::decode(bytes).map(Self::Subscribe),
+            kind if kind == NAMES => Ok(Self::Names),
+            kind if kind == EXPLAIN => Condition::decode(bytes).map(Self::Explain),
+            kind if kind == ANALYZE => Condition::decode(bytes).map(Self::Analyze),
+            kind => Err(Report::new(Error).attach(format!("unknown request kind {kind}"))),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Response
+
+/// A response from the server, one per frame.
+#[derive(Debug)]
+pub enum Response {
+    /// One event matching a select or subscription, with its mask.
+    Event(EventAndMask),
+    /// The end of a select (or of a subscription, once the stream's writer
+    /// has stopped).
+    End,
+    /// An append committed; its receipt.
+    Appended(AppendReceipt),
+    /// An append was rejected by its condition (a DCB conflict).
+    Conflict,
+    /// A request failed, with a description of why.
+    Failed(String),
+    /// The recorded names of the stream's type names and tags, each keyed by
+    /// its hash, as (types, tags).
+    Names(Vec<(u64, String)>, Vec<(u64, String)>),
+    /// The plan a condition would be run with.
+    Planned(Plan),
+    /// What running a condition cost.
+    Analyzed(Analysis),
+}
+
+impl Encode for Response {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        match self {
+            Self::Event(event) => {
+                EVENT.encode(bytes);
+                event.encode(bytes);
+            }
+            Self::End => END.encode(bytes),
+            Self::Appended(receipt) => {
+                APPENDED.encode(bytes);
+                receipt.encode(bytes);
+            }
+            Self::Conflict => CONFLICT.encode(bytes),
+            Self::Failed(description) => {
+                FAILED.encode(bytes);
+                description.encode(bytes);
+            }
+            Self::Names(types, tags) => {
+                NAMED.encode(bytes);
+
+                for names in [types, tags] {
+                    count(names.len()).encode(bytes);
+
+                    for (hash, name) in names {
+                        hash.encode(bytes);
+                        name.encode(bytes);
+                    }
+                }
+            }
+            Self::Planned(plan) => {
+                PLANNED.encode(bytes);
+                plan.encode(bytes);
+            }
+            Self::Analyzed(analysis) => {
+                ANALYZED.encode(bytes);
+                analysis.encode(bytes);
+            }
+        }
+    }
+}
+
+impl Decode for Response {
+    fn decode(bytes: &mut &[u8]) -> Result<Self> {
+        match u8::decode(bytes)? {
+            kind if kind == EVENT => EventAndMask::decode(bytes).map(Self::Event),
+            kind if kind == END => Ok(Self::End),
+            kind if kind == APPENDED => AppendReceipt::decode(bytes).map(Self::Appended),
+            kind if kind == CONFLICT => Ok(Self::Conflict),
+            kind if kind == FAILED => String::decode(bytes).map(Self::Failed),
+            kind if kind == NAMED => {
+                let types = names(bytes)?;
+                let tags = names(bytes)?;
+
+                Ok(Self::Names(types, tags))
+            }
+            kind if kind == PLANNED => Plan::decode(bytes).map(Self::Planned),
+            kind if kind == ANALYZED => Analysis::decode(bytes).map(Self::Analyzed),
+            kind => Err(Report::new(Error).attach(format!("unknown response kind {kind}"))),
+        }
+    }
+}
+
+fn count(count: usize) -> u32 {
+    u32::try_from(count).expect("message count > u32::MAX")
+}
+
+fn names(bytes: &mut &[u8]) -> Result<Vec<(u64, String)>> {
+    (0..u32::decode(bytes)?)
+        .map(|_| Ok((u64::decode(bytes)?, String::decode(bytes)?)))
+        .collect()
+}
@@ -0,0 +1,363 @@
+//! The [`Server`]: accepts connections on a TCP or Unix socket [`Address`]
+//! and serves each, on a thread of its own, through a [`Proxy`] of an
+//! `Owner`'s stream.
+
+#[cfg(unix)]
+use std::{
+    fs,
+    os::unix::net::UnixListener,
+    path::PathBuf,
+};
+use std::{
+    io,
+    net::{
+        SocketAddr,
+        TcpListener,
+    },
+    sync::{
+        Arc,
+        atomic::{
+            AtomicBool,
+            Ordering,
+        },
+    },
+    thread::{
+        self,
+        JoinHandle,
+    },
+};
+
+use error_stack::{
+    AttachmentKind,
+    FrameKind,
+    Report,
+    ResultExt as _,
+};
+use eventric_stream::{
+    error::{
+        Conflict,
+        Error,
+        Result,
+    },
+    stream::{
+        concurrent::proxy::Proxy,
+        operate::{
+            append::Append as _,
+            select::{
+                EventAndMask,
+                Select as _,
+            },
+        },
+    },
+};
+use fancy_constructor::new;
+use futures::executor;
+
+use crate::{
+    connection::Connection,
+    protocol::{
+        self,
+        Request,
+        Response,
+    },
+};
+
+// =================================================================================================
+// Server
+// =================================================================================================
+
+// Address
+
+/// Where a [`Server`] listens.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Address {
+    /// A TCP socket address. Bind port `0` to have one assigned, and read it
+    /// back from [`Server::address`].
+    Tcp(SocketAddr),
+    /// A Unix domain socket, at a path which must not exist yet. The socket
+    /// file is removed when the server stops.
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Server
+
+/// A network server putting a stream behind the [protocol](crate): each
+/// connection is served on a thread of its own through a clone of the
+/// [`Proxy`], so reads run concurrently and appends serialize through the
+/// `Owner`'s writer thread as any other proxy's do.
+#[derive(new, Debug)]
+#[new(vis())]
+pub struct Server {
+    address: Address,
+    listener: Listener,
+    proxy: Proxy,
+}
+
+impl Server {
+    /// Bind a server for `proxy`'s stream to `address`. Nothing is accepted
+    /// until the server is [`run`](Server::run) or [`spawn`](Server::spawn)ed.
+    pub fn bind(proxy: Proxy, address: &Address) -> Result<Self> {
+        let listener = Listener::bind(address)?;
+        let address = listener.address()?;
+
+        Ok(Self::new(address, listener, proxy))
+    }
+
+    /// The address the server is bound to (with any assigned port).
+    #[must_use]
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+}
+
+impl Server {
+    /// Accept and serve connections on the calling thread, until the listener
+    /// fails.
+    pub fn run(self) -> Result<()> {
+        self.serve(&AtomicBool::new(false))
+    }
+
+    /// Accept and serve connections on a background thread, until the
+    /// returned [`Handle`] is shut down.
+    #[must_use]
+    pub fn spawn(self) -> Handle {
+        let address = self.address.clone();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = thread::spawn({
+            let stop = Arc::clone(&stop);
+
+            move || self.serve(&stop)
+        });
+
+        Handle::new(address, stop, thread)
+    }
+
+    fn serve(self, stop: &AtomicBool) -> Result<()> {
+        let result = loop {
+            let connection = match self.listener.accept() {
+                Ok(connection) => connection,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => {
+                    break Err(err)
+                        .change_context(Error)
+                        .attach("failed to accept connection");
+                }
+            };
+
+            if stop.load(Ordering::Acquire) {
+                break Ok(());
+            }
+
+            let proxy = self.proxy.clone();
+
+            // A connection's failure (its client going away, say) ends only
+            // that connection.
+            thread::spawn(move || Connection::serve(connection, proxy));
+        };
+
+        #[cfg(unix)]
+        if let Address::Unix(path) = &self.address {
+            fs::remove_file(path).ok();
+        }
+
+        result
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Handle
+
+/// The handle to a [`spawn`](Server::spawn)ed server, which stops it.
+#[derive(new, Debug)]
+#[new(vis())]
+pub struct Handle {
+    address: Address,
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<Result<()>>,
+}
+
+impl Handle {
+    /// The address the server is bound to.
+    #[must_use]
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    /// Stop accepting connections and wait for the accepting thread to exit.
+    /// Connections already accepted are served until their clients close
+    /// them.
+    pub fn shutdown(self) -> Result<()> {
+        self.stop.store(true, Ordering::Release);
+
+        // Wake the accepting thread, blocked in `accept`, to see the stop.
+        Connection::connect(&self.address)?;
+
+        self.thread
+            .join()
+            .map_err(|_| Report::new(Error).attach("server thread panicked"))?
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Listener
+
+#[derive(Debug)]
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener),
+}
+
+impl Listener {
+    fn bind(address: &Address) -> Result<Self> {
+        let listener = match address {
+            Address::Tcp(address) => TcpListener::bind(address).map(Self::Tcp),
+            #[cfg(unix)]
+            Address::Unix(path) => UnixListener::bind(path).map(Self::Unix),
+        };
+
+        listener
+            .change_context(Error)
+            .attach_with(|| format!("failed to bind {address:?}"))
+    }
+
+    fn address(&self) -> Result<Address> {
+        match self {
+            Self::Tcp(listener) => listener.local_addr().map(Address::Tcp),
+            #[cfg(unix)]
+            Self::Unix(listener) => listener.local_addr().map(|address| {
+                Address::Unix(
+                    address
+                        .as_pathname()
+                        .map_or_else(PathBuf::default, Into::into),
+                )
+            }),
+        }
+        .change_context(Error)
+        .attach("failed to get bound address")
+    }
+
+    fn accept(&self) -> io::Result<Connection> {
+        match self {
+            Self::Tcp(listener) => listener.accept().map(|(stream, _)| Connection::Tcp(stream)),
+            #[cfg(unix)]
+            Self::Unix(listener) => listener
+                .accept()
+                .map(|(stream, _)| Connection::Unix(stream)),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Connection
+
+impl Connection {
+    // Answer requests until the client closes the connection (or subscribes,
+    // after which the subscription is the only thing it carries).
+    fn serve(mut self, mut proxy: Proxy) -> Result<()> {
+        while let Some(frame) = protocol::read(&mut self)? {
+            let request = match protocol::decode(&frame) {
+                Ok(request) => request,
+                Err(report) => {
+                    self.respond(&Response::Failed(describe(&report)))?;
+
+                    continue;
+                }
+            };
+
+            match request {
+                Request::Select(condition) => self.events(proxy.select(condition))?,
+                Request::Append(condition, events, durability) => {
+                    let appended = match durability {
+                        Some(durability) => proxy.append_durable(events, condition, durability),
+                        None => proxy.append(events, condition),
+                    };
+
+                    let response = match appended {
+                        Ok(receipt) => Response::Appended(receipt),
+                        Err(report) if report.contains::<Conflict>() => Response::Conflict,
+                        Err(report) => Response::Failed(describe(&report)),
+                    };
+
+                    self.respond(&response)?;
+                }
+                Request::Subscribe(condition) => {
+                    return self.events(executor::block_on_stream(proxy.subscribe(condition)));
+                }
+                Request::Names => {
+                    let response = match proxy.catalog() {
+                        Ok(catalog) => Response::Names(
+                            catalog
+                                .types
+                                .into_iter()
+                                .filter_map(|entry| Some((entry.hash, entry.name?)))
+                                .collect(),
+                            catalog
+                                .tags
+                                .into_iter()
+                                .filter_map(|entry| Some((entry.hash, entry.tag?)))
+                                .collect(),
+                        ),
+                        Err(report) => Response::Failed(describe(&report)),
+                    };
+
+                    self.respond(&response)?;
+                }
+                Request::Explain(condition) => {
+                    self.respond(&Response::Planned(proxy.explain(condition)))?;
+                }
+                Request::Analyze(condition) => {
+                    let response = match proxy.analyze(condition) {
+                        Ok(analysis) => Response::Analyzed(analysis),
+                        Err(report) => Response::Failed(describe(&report)),
+                    };
+
+                    self.respond(&response)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Send each event as it is read, then the end (or the failure which ends
+    // them early).
+    fn events<I>(&mut self, events: I) -> Result<()>
+    where
+        I: IntoIterator<Item = Result<EventAndMask>>,
+    {
+        for event in events {
+            match event {
+                Ok(event) => self.respond(&Response::Event(event))?,
+                Err(report) => return self.respond(&Response::Failed(describe(&report))),
+            }
+        }
+
+        self.respond(&Response::End)
+    }
+
+    fn respond(&mut self, response: &Response) -> Result<()> {
+        protocol::write(self, &protocol::encode(response))
+    }
+}
+
+// A report's printable attachments, most recent first, as one line for a
+// client which cannot read the report itself.
+fn describe(report: &Report<Error>) -> String {
+    report
+        .frames()
+        .filter_map(|frame| match frame.kind() {
+            FrameKind::Attachment(AttachmentKind::Printable(attachment)) => {
+                Some(attachment.to_string())
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join(": ")
+}
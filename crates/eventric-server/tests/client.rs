@@ -0,0 +1,279 @@
+//! Integration tests for the remote `Client`: through a server on localhost it
+//! appends and selects as the stream it is served from does, reports a
+//! rejected append with the `Conflict` attachment, streams selects lazily,
+//! and runs an `Enactor` unchanged.
+
+use std::net::SocketAddr;
+
+use error_stack::Report;
+use eventric_model::{
+    action::{
+        Act,
+        Action,
+    },
+    error::Error,
+    event::{
+        Event,
+        Events,
+    },
+    projection::{
+        self,
+        Project,
+        Projection,
+    },
+};
+use eventric_runtime::enactor::Enactor as _;
+use eventric_server::{
+    client::Client,
+    server::{
+        Address,
+        Handle,
+        Server,
+    },
+};
+use eventric_stream::{
+    error::Conflict,
+    event,
+    stream::{
+        Position,
+        Stream,
+        concurrent::owner::Owner,
+        operate::{
+            Condition,
+            Selection,
+            append::Append as _,
+            select::{
+                Select as _,
+                Selector,
+                TypeSelector,
+            },
+        },
+    },
+    utils::temp_path,
+};
+use fancy_constructor::new;
+use revision::revisioned;
+
+// =================================================================================================
+// Fixture
+// =================================================================================================
+
+#[revisioned(revision = 1)]
+#[derive(new, Event, Debug, PartialEq)]
+#[event(identifier: item_registered, tags: { item: sku })]
+struct ItemRegistered {
+    #[new(into)]
+    sku: String,
+    qty: u8,
+}
+
+#[derive(new, Projection, Debug)]
+#[projection(selections: {
+    registered: { events: [ItemRegistered], filter: { item: sku } },
+})]
+struct ItemPresent {
+    #[new(default)]
+    present: bool,
+    #[new(into)]
+    sku: String,
+}
+
+impl Project<item_present::Registered<'_>> for ItemPresent {
+    fn project(&mut self, _: projection::Event<item_present::Registered<'_>>) {
+        self.present = true;
+    }
+}
+
+#[derive(new, Action, Debug)]
+#[action(projections: {
+    item_present: ItemPresent::new(&self.sku),
+})]
+struct RegisterItem {
+    #[new(into)]
+    sku: String,
+    qty: u8,
+}
+
+impl Act<register_item::Projections> for RegisterItem {
+    fn act(
+        &self,
+        events: &mut Events,
+        projections: &register_item::Projections,
+    ) -> Result<Self::Ok, Self::Err> {
+        if projections.item_present.present {
+            return Err(Report::new(Error).attach("Item Already Registered"));
+        }
+
+        events.append(&ItemRegistered::new(&self.sku, self.qty))?;
+
+        Ok(())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Helpers
+
+fn serve() -> (Owner, Handle, Client) {
+    let owner = Owner::new(Stream::builder(temp_path()).temporary(true).open().unwrap());
+    let address = Address::Tcp(SocketAddr::from(([127, 0, 0, 1], 0)));
+    let handle = Server::bind(owner.proxy(), &address).unwrap().spawn();
+    let client = Client::connect(handle.address().clone()).unwrap();
+
+    (owner, handle, client)
+}
+
+fn event(name: &str, data: &str) -> event::Event<(), String> {
+    let ty = event::Type::new(event::Name::new(name).unwrap(), event::Version::new(0));
+
+    event::Event::new(
+        event::Data::new(data).unwrap(),
+        event::Facets::new(ty, [].into()),
+        (),
+    )
+}
+
+fn selecting(name: &str) -> Condition {
+    Condition::new().selections([Selection::new([Selector::types([
+        TypeSelector::new(name).unwrap()
+    ])])])
+}
+
+// =================================================================================================
+// Tests
+// =================================================================================================
+
+// A client's appends and selects are those of the stream it is served from:
+// the same receipts, positions, events and masks, and the same plans.
+#[test]
+fn client_appends_and_selects_as_the_served_stream() {
+    let (owner, handle, mut client) = serve();
+
+    let events = [
+        event("Opened", "a"),
+        event("Closed", "b"),
+        event("Opened", "c"),
+    ];
+
+    let receipt = client.append(events, Condition::new()).unwrap();
+
+    assert_eq!(receipt.last(), Position::new(2));
+    assert_eq!(
+        receipt.metadata().collect::<Vec<_>>(),
+        owner
+            .proxy()
+            .select(Condition::new())
+            .map(|event| *event.unwrap().event.meta())
+            .collect::<Vec<_>>()
+    );
+
+    let remote = client
+        .select(selecting("Opened"))
+        .map(Result::unwrap)
+        .collect::<Vec<_>>();
+    let local = owner
+        .proxy()
+        .select(selecting("Opened"))
+        .map(Result::unwrap)
+        .collect::<Vec<_>>();
+
+    assert_eq!(remote.len(), 2);
+
+    for (remote, local) in remote.iter().zip(&local) {
+        assert_eq!(remote.event.meta(), local.event.meta());
+        assert_eq!(remote.event.data(), local.event.data());
+        assert_eq!(remote.mask, local.mask);
+    }
+
+    let last = client.select(selecting("Opened")).next_back().unwrap();
+
+    assert_eq!(last.unwrap().event.meta().position(), Position::new(2));
+    assert_eq!(
+        client.explain(selecting("Opened")),
+        owner.proxy().explain(selecting("Opened"))
+    );
+    assert_eq!(
+        client.analyze(selecting("Opened")).unwrap().events,
+        owner.proxy().analyze(selecting("Opened")).unwrap().events
+    );
+
+    handle.shutdown().unwrap();
+}
+
+// An append rejected by its condition carries the `Conflict` attachment, as
+// it would appending locally.
+#[test]
+fn rejected_append_carries_the_conflict_attachment() {
+    let (_owner, handle, mut client) = serve();
+
+    client
+        .append([event("Opened", "a")], Condition::new())
+        .unwrap();
+
+    let report = client
+        .append(
+            [event("Opened", "b")],
+            selecting("Opened").from(Position::MIN),
+        )
+        .expect_err("conflicting append must be rejected");
+
+    assert!(report.contains::<Conflict>());
+
+    handle.shutdown().unwrap();
+}
+
+// A select is read as the server sends it: one dropped part way leaves the
+// client usable, and selects can be read while other requests are made.
+#[test]
+fn selects_stream_lazily() {
+    let (_owner, handle, mut client) = serve();
+
+    for index in 0..100 {
+        client
+            .append([event("Opened", &index.to_string())], Condition::new())
+            .unwrap();
+    }
+
+    let mut first = client.select(Condition::new());
+    let mut second = client.select(Condition::new());
+
+    assert_eq!(
+        first.next().unwrap().unwrap().event.meta().position(),
+        Position::new(0)
+    );
+
+    client
+        .append([event("Closed", "z")], Condition::new())
+        .unwrap();
+
+    assert_eq!(second.by_ref().take(10).count(), 10);
+    assert_eq!(first.count(), 99);
+
+    drop(second);
+
+    assert_eq!(client.select(Condition::new()).count(), 101);
+
+    handle.shutdown().unwrap();
+}
+
+// An `Enactor` runs against a client unchanged: its replay reads the remote
+// stream, so a second registration of the same item is rejected.
+#[test]
+fn enactor_runs_against_a_client() {
+    let (owner, handle, mut client) = serve();
+
+    client.enact(RegisterItem::new("widget", 7)).unwrap();
+
+    let report = client
+        .enact(RegisterItem::new("widget", 3))
+        .expect_err("duplicate registration must be rejected");
+
+    assert!(
+        report
+            .frames()
+            .any(|frame| frame.downcast_ref::<&str>() == Some(&"Item Already Registered"))
+    );
+    assert_eq!(owner.proxy().select(Condition::new()).count(), 1);
+
+    handle.shutdown().unwrap();
+}
@@ -0,0 +1,349 @@
+//! End-to-end tests for the server over localhost: a client speaking the
+//! protocol on a TCP or Unix socket selects with masks, appends (and is told
+//! of conflicts), resolves names, and follows a subscription, and the server
+//! stops when its handle is shut down.
+
+use std::{
+    collections::BTreeSet,
+    io::{
+        Read,
+        Write,
+    },
+    net::{
+        SocketAddr,
+        TcpStream,
+    },
+};
+
+use eventric_server::{
+    protocol::{
+        self,
+        Request,
+        Response,
+    },
+    server::{
+        Address,
+        Handle,
+        Server,
+    },
+};
+use eventric_stream::{
+    event::{
+        Data,
+        Event,
+        Facets,
+        Name,
+        Tag,
+        Type,
+        Version,
+    },
+    stream::{
+        Position,
+        Stream,
+        concurrent::owner::Owner,
+        operate::{
+            Condition,
+            Selection,
+            select::{
+                Selector,
+                TypeSelector,
+            },
+        },
+    },
+    utils::temp_path,
+};
+
+// =================================================================================================
+// Helpers
+// =================================================================================================
+
+fn owner() -> Owner {
+    Owner::new(Stream::builder(temp_path()).temporary(true).open().unwrap())
+}
+
+fn serve(owner: &Owner) -> Handle {
+    let address = Address::Tcp(SocketAddr::from(([127, 0, 0, 1], 0)));
+
+    Server::bind(owner.proxy(), &address).unwrap().spawn()
+}
+
+fn connect(handle: &Handle) -> TcpStream {
+    match handle.address() {
+        Address::Tcp(address) => TcpStream::connect(address).unwrap(),
+        #[cfg(unix)]
+        Address::Unix(_) => unreachable!(),
+    }
+}
+
+fn event(name: &str, data: &str, tags: &[&str]) -> Event<(), String> {
+    let ty = Type::new(Name::new(name).unwrap(), Version::new(0));
+    let tags = tags
+        .iter()
+        .map(|tag| Tag::new(*tag).unwrap())
+        .collect::<BTreeSet<_>>();
+
+    Event::new(Data::new(data).unwrap(), Facets::new(ty, tags), ())
+}
+
+fn selecting(name: &str) -> Selection {
+    Selection::new([Selector::types([TypeSelector::new(name).unwrap()])])
+}
+
+fn send<S>(stream: &mut S, request: &Request)
+where
+    S: Write,
+{
+    protocol::write(stream, &protocol::encode(request)).unwrap();
+}
+
+fn receive<S>(stream: &mut S) -> Response
+where
+    S: Read,
+{
+    protocol::decode(&protocol::read(stream).unwrap().unwrap()).unwrap()
+}
+
+// Send a request and read its responses up to (and including) the one which
+// ends it.
+fn request<S>(stream: &mut S, request: &Request) -> Vec<Response>
+where
+    S: Read + Write,
+{
+    send(stream, request);
+
+    let mut responses = Vec::new();
+
+    loop {
+        let response = receive(stream);
+        let ended = !matches!(response, Response::Event(_));
+
+        responses.push(response);
+
+        if ended {
+            break responses;
+        }
+    }
+}
+
+fn appended(responses: &[Response]) -> Position {
+    match responses {
+        [Response::Appended(receipt)] => receipt.last(),
+        responses => panic!("expected appended, got {responses:?}"),
+    }
+}
+
+// =================================================================================================
+// Tests
+// =================================================================================================
+
+// Appends are answered with their last position, and a select streams each
+// match with its mask over the selections, then ends.
+#[test]
+fn select_streams_masked_matches_then_ends() {
+    let owner = owner();
+    let handle = serve(&owner);
+    let mut client = connect(&handle);
+
+    let events = vec![
+        event("Opened", "a", &["account:1"]),
+        event("Closed", "b", &["account:1"]),
+        event("Opened", "c", &["account:2"]),
+    ];
+
+    assert_eq!(
+        appended(&request(
+            &mut client,
+            &Request::Append(Condition::new(), events, None)
+        )),
+        Position::new(2)
+    );
+
+    let condition = Condition::new().selections([selecting("Opened"), selecting("Closed")]);
+    let responses = request(&mut client, &Request::Select(condition));
+
+    let [
+        Response::Event(first),
+        Response::Event(second),
+        Response::Event(third),
+        Response::End,
+    ] = responses.as_slice()
+    else {
+        panic!("expected three events then end, got {responses:?}");
+    };
+
+    assert_eq!(first.event.meta().position(), Position::new(0));
+    assert_eq!(first.mask.as_ref(), [true, false].as_slice());
+    assert_eq!(
+        first.event.facets().ty().name(),
+        &Name::new("Opened").unwrap().into()
+    );
+    assert_eq!(second.mask.as_ref(), [false, true].as_slice());
+    assert_eq!(third.event.data().as_ref(), b"c");
+
+    drop(client);
+    handle.shutdown().unwrap();
+}
+
+// An append whose condition matches an event after its position is answered
+// as a conflict, and the connection goes on serving requests.
+#[test]
+fn conflicting_append_is_answered_with_conflict() {
+    let owner = owner();
+    let handle = serve(&owner);
+    let mut client = connect(&handle);
+
+    request(
+        &mut client,
+        &Request::Append(Condition::new(), vec![event("Opened", "a", &[])], None),
+    );
+
+    let condition = Condition::new()
+        .selections([selecting("Opened")])
+        .from(Position::MIN);
+    let responses = request(
+        &mut client,
+        &Request::Append(condition, vec![event("Opened", "b", &[])], None),
+    );
+
+    assert!(matches!(responses.as_slice(), [Response::Conflict]));
+
+    let condition = Condition::new()
+        .selections([selecting("Opened")])
+        .from(Position::new(1));
+
+    assert_eq!(
+        appended(&request(
+            &mut client,
+            &Request::Append(condition, vec![event("Opened", "b", &[])], None),
+        )),
+        Position::new(1)
+    );
+
+    drop(client);
+    handle.shutdown().unwrap();
+}
+
+// A malformed request is answered as failed without closing the connection,
+// and the names of what has been appended can be looked up.
+#[test]
+fn malformed_request_fails_and_names_resolve() {
+    let owner = owner();
+    let handle = serve(&owner);
+    let mut client = connect(&handle);
+
+    protocol::write(&mut client, &[0xff]).unwrap();
+
+    assert!(matches!(receive(&mut client), Response::Failed(_)));
+
+    request(
+        &mut client,
+        &Request::Append(
+            Condition::new(),
+            vec![event("Opened", "a", &["account:1"])],
+            None,
+        ),
+    );
+
+    let responses = request(&mut client, &Request::Names);
+    let [Response::Names(types, tags)] = responses.as_slice() else {
+        panic!("expected names, got {responses:?}");
+    };
+
+    assert_eq!(
+        types
+            .iter()
+            .map(|(_, name)| name.as_str())
+            .collect::<Vec<_>>(),
+        ["Opened"]
+    );
+    assert_eq!(
+        tags.iter().map(|(_, tag)| tag.as_str()).collect::<Vec<_>>(),
+        ["account:1"]
+    );
+
+    drop(client);
+    handle.shutdown().unwrap();
+}
+
+// A subscription streams existing matches, then matches committed by another
+// client, and ends once the stream's writer has stopped.
+#[test]
+fn subscription_streams_live_commits() {
+    let owner = owner();
+    let handle = serve(&owner);
+    let mut subscriber = connect(&handle);
+    let mut client = connect(&handle);
+
+    request(
+        &mut client,
+        &Request::Append(Condition::new(), vec![event("Opened", "a", &[])], None),
+    );
+
+    let condition = Condition::new().selections([selecting("Opened")]);
+
+    send(&mut subscriber, &Request::Subscribe(condition));
+
+    let Response::Event(existing) = receive(&mut subscriber) else {
+        panic!("expected an existing event");
+    };
+
+    assert_eq!(existing.event.meta().position(), Position::new(0));
+
+    request(
+        &mut client,
+        &Request::Append(
+            Condition::new(),
+            vec![event("Closed", "b", &[]), event("Opened", "c", &[])],
+            None,
+        ),
+    );
+
+    let Response::Event(live) = receive(&mut subscriber) else {
+        panic!("expected a live event");
+    };
+
+    assert_eq!(live.event.meta().position(), Position::new(2));
+    assert_eq!(live.event.data().as_ref(), b"c");
+
+    drop(client);
+    handle.shutdown().unwrap();
+    owner.into_inner().unwrap();
+
+    assert!(matches!(receive(&mut subscriber), Response::End));
+}
+
+// The same protocol is served on a Unix socket, whose file is removed once
+// the server has stopped.
+#[cfg(unix)]
+#[test]
+fn serves_over_a_unix_socket() {
+    use std::os::unix::net::UnixStream;
+
+    let owner = owner();
+    let path = temp_path();
+    let handle = Server::bind(owner.proxy(), &Address::Unix(path.clone()))
+        .unwrap()
+        .spawn();
+
+    let mut client = UnixStream::connect(&path).unwrap();
+
+    assert_eq!(
+        appended(&request(
+            &mut client,
+            &Request::Append(Condition::new(), vec![event("Opened", "a", &[])], None),
+        )),
+        Position::new(0)
+    );
+
+    let responses = request(&mut client, &Request::Select(Condition::new()));
+
+    assert!(matches!(responses.as_slice(), [
+        Response::Event(_),
+        Response::End
+    ]));
+
+    drop(client);
+    handle.shutdown().unwrap();
+
+    assert!(!path.exists());
+}
@@ -1,8 +1,14 @@
 //! The crate's error model: the opaque [`struct@Error`], the [`Conflict`]
-//! marker attached when an append is rejected by its condition, and the
-//! [`Result`] alias returned by every fallible operation. `error-stack` is used
-//! end-to-end, so detail rides as `.attach(..)` on the report rather than as
-//! error variants.
+//! marker attached when an append is rejected by its condition, the
+//! [`Backpressure`] and [`Timeout`] markers attached when an append through a
+//! [`Proxy`] cannot be enqueued or answered in time, the [`Locked`] marker
+//! attached when a database is already open in another process, and the
+//! [`Result`] alias returned by every fallible operation.
+//!
+//! `error-stack` is used end-to-end, so detail rides as `.attach(..)` on the
+//! report rather than as error variants.
+//!
+//! [`Proxy`]: crate::stream::concurrent::proxy::Proxy
 
 use std::result;
 
@@ -43,6 +49,52 @@ pub struct Conflict;
 
 // -------------------------------------------------------------------------------------------------
 
+// Backpressure
+
+/// Marker attached to an [`struct@Error`] report when an append through a
+/// [`Proxy`] finds the writer thread's channel full: at once, from
+/// [`Proxy::try_append`], or after waiting for room until its deadline, from
+/// [`Proxy::append_timeout`]. The append was not enqueued, so it was not
+/// committed.
+///
+/// [`Proxy`]: crate::stream::concurrent::proxy::Proxy
+/// [`Proxy::try_append`]: crate::stream::concurrent::proxy::Proxy::try_append
+/// [`Proxy::append_timeout`]: crate::stream::concurrent::proxy::Proxy::append_timeout
+#[derive(Debug, Display)]
+#[display("writer backpressure: the writer's channel is full")]
+pub struct Backpressure;
+
+// -------------------------------------------------------------------------------------------------
+
+// Timeout
+
+/// Marker attached to an [`struct@Error`] report when an append through
+/// [`Proxy::append_timeout`] is not answered by its deadline. If it could not
+/// even be enqueued, [`Backpressure`] is attached too and the append was not
+/// committed; otherwise a [`PendingAppend`] is attached, through which its
+/// eventual outcome can be awaited.
+///
+/// [`Proxy::append_timeout`]: crate::stream::concurrent::proxy::Proxy::append_timeout
+/// [`PendingAppend`]: crate::stream::concurrent::proxy::PendingAppend
+#[derive(Debug, Display)]
+#[display("append timed out")]
+pub struct Timeout;
+
+// -------------------------------------------------------------------------------------------------
+
+// Locked
+
+/// Marker attached to an [`struct@Error`] report when a database cannot be
+/// opened because another process (or another handle in this one) holds its
+/// directory's lock: a database is open in one place at a time, so a stream
+/// has a single writer across processes too. The report says which process
+/// holds the lock, where it is known.
+#[derive(Debug, Display)]
+#[display("database locked: it is open elsewhere")]
+pub struct Locked;
+
+// -------------------------------------------------------------------------------------------------
+
 // Result
 
 /// The result type for fallible stream operations: an `error-stack` [`Report`]
@@ -176,6 +176,12 @@ macro_rules! string_type {
                 }
             }
 
+            impl From<$name<u64>> for u64 {
+                fn from([< $name:lower >]: $name<u64>) -> Self {
+                    [< $name:lower >].0
+                }
+            }
+
             impl Validate for $name<String> {
                 fn validate(self) -> Result<Self> {
                     validation::validate(&self.0, stringify!([< $name:snake >]), &[
@@ -258,3 +264,9 @@ impl Version {
     /// The minimum version.
     pub const MIN: Self = Self::new(u8::MIN);
 }
+
+impl From<Version> for u8 {
+    fn from(version: Version) -> Self {
+        version.0
+    }
+}
@@ -54,6 +54,22 @@ where
     }
 }
 
+impl<I, T, E> Intersection<I, T, E>
+where
+    I: DoubleEndedIterator<Item = Result<T, E>>,
+    T: Copy + Debug + Ord + PartialOrd,
+{
+    /// The child iterators, in order.
+    pub(crate) fn iters(&self) -> impl Iterator<Item = &I> {
+        self.0.iter().map(|cursor| &cursor.inner)
+    }
+
+    /// The child iterators, in order, mutably.
+    pub(crate) fn iters_mut(&mut self) -> impl Iterator<Item = &mut I> {
+        self.0.iter_mut().map(|cursor| &mut cursor.inner)
+    }
+}
+
 impl<I, T, E> Iterator for Intersection<I, T, E>
 where
     I: DoubleEndedIterator<Item = Result<T, E>> + Seek<T>,
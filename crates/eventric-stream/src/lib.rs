@@ -8,7 +8,8 @@
 //! holds the payload `Data`, the `Facets`, and the generic `Event`; [`stream`]
 //! holds the `Stream`, its `Reader`/`Writer` split, the threaded
 //! `Owner`/`Proxy`, and the masked `Condition` query/concurrency model; with
-//! [`error`] and [`utils`] alongside.
+//! [`error`] and [`utils`] alongside, and [`wire`], the encoding a remote
+//! server and its clients exchange.
 //!
 //! The higher-level event-sourcing UX — events, projections, and actions —
 //! lives in the companion `eventric-model` crate, which is built on this one.
@@ -19,6 +20,7 @@
 #![allow(clippy::missing_errors_doc)]
 #![allow(clippy::missing_panics_doc)]
 #![allow(clippy::missing_safety_doc)]
+#![allow(stable_features)]
 #![deny(missing_docs)]
 #![deny(unsafe_code)]
 #![feature(exclusive_wrapper)]
@@ -29,6 +31,7 @@ pub mod error;
 pub mod event;
 pub mod stream;
 pub mod utils;
+pub mod wire;
 
 // =================================================================================================
 // Eventric Stream
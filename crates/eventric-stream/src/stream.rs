@@ -3,13 +3,24 @@
 //! ([`Error`], [`Conflict`](crate::error::Conflict), [`Result`]) lives in
 //! [`crate::error`].
 
+pub mod audit;
+pub mod catalog;
+pub mod clock;
 pub mod concurrent;
+pub mod database;
+pub mod dcb;
+mod flusher;
+pub mod observe;
 pub mod operate;
+pub mod replicate;
+pub mod statistics;
 mod store;
 
 use std::{
     path::Path,
+    sync::Arc,
     time::{
+        Duration,
         SystemTime,
         UNIX_EPOCH,
     },
@@ -24,26 +35,56 @@ use derive_more::{
         SubAssign,
     },
 };
-use error_stack::ResultExt;
+use error_stack::{
+    Report,
+    ResultExt,
+};
 use fancy_constructor::new;
-use fjall::Database;
 
 use crate::{
     error::{
         Error,
         Result,
     },
-    event::Event,
+    event::{
+        Data,
+        Event,
+    },
     stream::{
+        audit::Redaction,
+        catalog::Catalog,
+        clock::Clock,
+        database::{
+            Claim,
+            Database,
+            Lock,
+        },
+        flusher::Flusher,
+        observe::{
+            Observation,
+            Observer,
+            Observers,
+        },
         operate::{
             Condition,
-            append::Append,
+            append::{
+                Append,
+                AppendReceipt,
+            },
+            explain::{
+                Analysis,
+                Plan,
+            },
             select::{
                 Select,
                 SelectIter,
             },
         },
-        store::Store,
+        statistics::Statistics,
+        store::{
+            Store,
+            storage::Storage,
+        },
     },
 };
 
@@ -51,19 +92,31 @@ use crate::{
 // Stream
 // =================================================================================================
 
+// Constants
+
+// The top bit of a batch position as stored (and shipped) marks an event whose
+// payload has been redacted. A batch position is never so large (it would take
+// 2^63 events to reach), so the bit is free to carry the mark. (Events stored
+// before they carried their batch have neither, and are upgraded to carry both
+// when their stream is opened for writing.)
+static REDACTED: u64 = 1 << 63;
+
+// -------------------------------------------------------------------------------------------------
+
 // Builder
 
-/// Configures and opens a [`Stream`] at a given path. Obtained from
-/// [`Stream::builder`].
+/// Configures and opens a [`Stream`] at a given path, in a database of its own.
+/// Obtained from [`Stream::builder`]. To open several streams in one database,
+/// use a [`Database`] instead.
 #[derive(new, Debug)]
 #[new(vis())]
 pub struct Builder<P>
 where
     P: AsRef<Path>,
 {
-    path: P,
+    database: database::Builder<P>,
     #[new(default)]
-    temporary: Option<bool>,
+    name: Option<String>,
 }
 
 impl<P> Builder<P>
@@ -71,18 +124,19 @@ where
     P: AsRef<Path>,
 {
     /// Open the stream, recovering the `next` position cursor from the existing
-    /// `events` keyspace.
+    /// `events` keyspace, and advancing its writer [`epoch`](Stream::epoch).
     pub fn open(self) -> Result<Stream> {
-        let database = Database::builder(self.path)
-            .temporary(self.temporary.unwrap_or_default())
-            .open()
-            .change_context(Error)
-            .attach("failed to open database")?;
-
-        let storage = Store::open(&database)?;
-        let next = storage.len().map(Position::new)?;
+        self.database.open()?.open(self.name)
+    }
 
-        Ok(Stream::new(database, next, storage))
+    /// Open the stream for reading only, as a [`Reader`]: nothing is written to
+    /// it, its writer epoch included, and it is not recovered. The database is
+    /// opened [`read_only`](database::Builder::read_only), so the stream must
+    /// exist, and may be read alongside a writer in this process. A stream
+    /// open in another process cannot be opened here, even to read it (see
+    /// [`open`](database::Builder::open)).
+    pub fn open_reader(self) -> Result<Reader> {
+        self.database.read_only(true).open()?.read(self.name)
     }
 }
 
@@ -90,26 +144,158 @@ impl<P> Builder<P>
 where
     P: AsRef<Path>,
 {
+    /// The [`Backend`] the stream is stored in. Defaults to
+    /// [`Backend::Disk`].
+    #[must_use]
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.database = self.database.backend(backend);
+        self
+    }
+
+    /// The capacity of the database's block cache, in bytes. Defaults to
+    /// fjall's default (32 MiB).
+    #[must_use]
+    pub fn cache_size(mut self, cache_size: u64) -> Self {
+        self.database = self.database.cache_size(cache_size);
+        self
+    }
+
+    /// The [`Clock`] appended events are timestamped with. Defaults to the
+    /// [`System`](clock::System) clock; a [`Hybrid`](clock::Hybrid) clock
+    /// keeps timestamps in position order.
+    #[must_use]
+    pub fn clock<C>(mut self, clock: C) -> Self
+    where
+        C: Clock + 'static,
+    {
+        self.database = self.database.clock(clock);
+        self
+    }
+
+    /// How durably each append's commit is persisted before the append
+    /// returns, unless overridden per append with
+    /// [`append_durable`](Append::append_durable). Defaults to
+    /// [`Durability::Buffer`].
+    #[must_use]
+    pub fn durability(mut self, durability: Durability) -> Self {
+        self.database = self.database.durability(durability);
+        self
+    }
+
+    /// Fsync the journal in the background every `interval`, so buffered
+    /// commits become durable within one interval (the interval alternative
+    /// to [`Durability::Sync`] on every commit). Defaults to no background
+    /// flushing.
+    #[must_use]
+    pub fn flush_interval(mut self, interval: Duration) -> Self {
+        self.database = self.database.flush_interval(interval);
+        self
+    }
+
+    /// The maximum total size of the journal, in bytes, beyond which
+    /// in-memory writes are flushed to disk so older journal files can be
+    /// dropped. At least 64 MiB; defaults to fjall's default (512 MiB).
+    #[must_use]
+    pub fn journal_size(mut self, journal_size: u64) -> Self {
+        self.database = self.database.journal_size(journal_size);
+        self
+    }
+
+    /// The stream's name, namespacing its keyspaces so that several streams
+    /// can share the database at the path (see [`Database::stream`], which
+    /// opens them together). Defaults to no name: the unnamed stream, whose
+    /// keyspaces are those of a stream written before streams were named.
+    #[must_use]
+    pub fn name<N>(mut self, name: N) -> Self
+    where
+        N: Into<String>,
+    {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Register an [`Observer`], called with an
+    /// [`Observation`](observe::Observation) for every append and select
+    /// through the stream and the handles split from it. May be called more
+    /// than once; observers are called in registration order.
+    #[must_use]
+    pub fn observer<O>(mut self, observer: O) -> Self
+    where
+        O: Observer + 'static,
+    {
+        self.database = self.database.observer(observer);
+        self
+    }
+
     /// Whether the stream is temporary (its on-disk data is cleaned up on
     /// drop). Defaults to `false`.
     #[must_use]
     pub fn temporary(mut self, temporary: bool) -> Self {
-        self.temporary = Some(temporary);
+        self.database = self.database.temporary(temporary);
+        self
+    }
+
+    /// Whether the stream allows the administrative operations which destroy
+    /// committed events, such as [`Writer::truncate_after`] — for test and
+    /// staging environments, never production. Defaults to `false`, when they
+    /// are refused.
+    #[must_use]
+    pub fn unsafe_admin(mut self, unsafe_admin: bool) -> Self {
+        self.database = self.database.unsafe_admin(unsafe_admin);
         self
     }
 }
 
 // -------------------------------------------------------------------------------------------------
 
+// Backend
+
+/// Where a stream's events and indices are stored. Both backends share every
+/// `Select`/`Append`/DCB semantic; they differ only in where the bytes live.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Backend {
+    /// On disk, in a fjall database at the builder's path. The default.
+    #[default]
+    Disk,
+    /// In process memory: nothing touches disk, so opening is cheap and
+    /// nothing survives the last handle to the stream. The path and the
+    /// database settings (cache and journal size, durability, temporary) are
+    /// ignored. Meant for fast tests.
+    Memory,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Durability
+
+/// How durably an append's commit is persisted before the append returns.
+/// Either way the commit is atomic and crash-consistent; durability only
+/// decides which crashes it survives.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Durability {
+    /// Flush the commit to the OS buffers: it survives an application crash,
+    /// but not an OS crash or power loss until it is next fsynced (see
+    /// [`Builder::flush_interval`]). The default.
+    #[default]
+    Buffer,
+    /// Fsync the commit (data and metadata): it survives power loss.
+    Sync,
+}
+
+// -------------------------------------------------------------------------------------------------
+
 // Metadata
 
 /// The metadata a persisted event carries (its `M` in `Event<M, T>`): the
-/// [`Position`] and [`Timestamp`] assigned when it was appended.
-#[derive(new, Debug)]
+/// [`Position`] and [`Timestamp`] assigned when it was appended, the batch it
+/// was appended in, and whether its payload has since been redacted.
+#[derive(new, Clone, Copy, Debug, Eq, PartialEq)]
 #[new(const_fn, vis(pub(crate)))]
 pub struct Metadata(
     #[new(name(position))] pub(crate) Position,
     #[new(name(timestamp))] pub(crate) Timestamp,
+    #[new(name(batch))] pub(crate) Position,
+    #[new(val(false))] pub(crate) bool,
 );
 
 impl Metadata {
@@ -124,6 +310,41 @@ impl Metadata {
     pub fn timestamp(&self) -> Timestamp {
         self.1
     }
+
+    /// The batch the event was appended in, identified by the position of its
+    /// first event: the events of one append share it. Select a whole batch
+    /// with [`Condition::batch`].
+    #[must_use]
+    pub fn batch(&self) -> Position {
+        self.2
+    }
+
+    /// Whether the event's payload has been replaced by
+    /// [`Writer::redact`] since it was appended (its type, tags and the rest
+    /// of its metadata are as appended).
+    #[must_use]
+    pub fn redacted(&self) -> bool {
+        self.3
+    }
+}
+
+impl Metadata {
+    /// Metadata read with its batch position as stored, marked if redacted.
+    pub(crate) fn marked(position: Position, timestamp: Timestamp, batch: u64) -> Self {
+        let mut meta = Self::new(position, timestamp, Position(batch & !REDACTED));
+
+        meta.3 = batch & REDACTED != 0;
+        meta
+    }
+
+    /// The batch position as stored, marked if the event is redacted.
+    pub(crate) fn marked_batch(&self) -> u64 {
+        if self.3 {
+            self.2.0 | REDACTED
+        } else {
+            self.2.0
+        }
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -137,21 +358,28 @@ impl Metadata {
 #[derive(new, Debug)]
 #[new(const_fn, vis())]
 pub struct Stream {
-    #[debug("Database")]
-    database: Database,
+    claim: Arc<Claim>,
+    #[debug("Clock")]
+    clock: Arc<dyn Clock>,
+    durability: Durability,
+    flusher: Option<Arc<Flusher>>,
     next: Position,
+    observers: Observers,
+    #[debug("Storage")]
+    storage: Arc<dyn Storage>,
     store: Store,
+    unsafe_admin: bool,
 }
 
 impl Stream {
     /// Begin opening a stream at `path`. Configure with
-    /// [`temporary`](Builder::temporary) and finish with
-    /// [`open`](Builder::open).
+    /// [`temporary`](Builder::temporary) (and the durability and cache
+    /// settings on [`Builder`]) and finish with [`open`](Builder::open).
     pub fn builder<P>(path: P) -> Builder<P>
     where
         P: AsRef<Path>,
     {
-        Builder::new(path)
+        Builder::new(Database::builder(path))
     }
 
     /// Whether the stream holds no events.
@@ -166,32 +394,152 @@ impl Stream {
         self.next.0
     }
 
+    /// The stream's writer epoch: advanced (durably) each time the stream is
+    /// opened for writing, so it is greater than that of every writer the
+    /// stream had before. It identifies the writer (e.g. to tell apart what
+    /// successive writers did) but fences nothing itself: the database's lock
+    /// is what keeps a second writer out while this one is open.
+    #[must_use]
+    pub fn epoch(&self) -> u64 {
+        self.claim.epoch()
+    }
+
+    /// The stream's [`Catalog`], as [`Reader::catalog`].
+    pub fn catalog(&self) -> Result<Catalog> {
+        self.store.catalog()
+    }
+
+    /// The stream's cardinality [`Statistics`], as [`Reader::stats`].
+    pub fn stats(&self) -> Result<Statistics> {
+        self.store.statistics()
+    }
+
+    /// The [`Redaction`]s of the event at `position`, as
+    /// [`Reader::redactions`].
+    pub fn redactions(&self, position: Position) -> Result<Vec<Redaction>> {
+        self.store.redactions(position)
+    }
+
+    /// Fork the stream at `position` into a new stream stored at `path`, for
+    /// what-if runs against this stream's history: the fork holds the events
+    /// before `position` (so its next append is made at `position`) and takes
+    /// its own appends from there, which this stream never sees.
+    ///
+    /// Nothing is copied: the fork reads the events before `position` (and
+    /// their postings, names and redactions) through to this stream, which it
+    /// keeps open, and writes only its own to `path`. Any events imported in
+    /// bulk and not yet indexed are indexed first, so the fork reads their
+    /// postings too. While the fork is open, this stream refuses to redact or
+    /// truncate those events. It takes this stream's clock, durability,
+    /// observers and [`unsafe_admin`](Builder::unsafe_admin) setting. A fork
+    /// is a scratch stream — its database at `path` is temporary, removed when
+    /// the fork is dropped, as what it holds means nothing apart from the
+    /// stream it was forked from.
+    pub fn fork_at<P>(&self, position: Position, path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        if position > self.next {
+            return Err(Report::new(Error).attach(format!(
+                "cannot fork at position {} past the head {}",
+                position.0, self.next.0
+            )));
+        }
+
+        // The fork reads this stream's postings, and its counts are this
+        // stream's less those after `position`, so neither may be missing
+        // events imported without their postings.
+        self.store
+            .index(&mut || self.storage.batch(self.durability))?;
+
+        Database::builder(path)
+            .durability(self.durability)
+            .temporary(true)
+            .unsafe_admin(self.unsafe_admin)
+            .open()?
+            .fork(
+                &self.store,
+                position,
+                Arc::clone(&self.clock),
+                self.observers.clone(),
+            )
+    }
+
     /// Split into a cloneable, read-only [`Reader`] and the unique [`Writer`].
     /// Reads scale across `Reader` clones; writes serialize through the single
     /// `Writer`. Recombine into a `Stream` with `Stream::from`.
     #[must_use]
     pub fn split(self) -> (Reader, Writer) {
-        let reader = Reader::new(self.store.clone());
-        let writer = Writer::new(self.database, self.next, self.store);
+        let reader = Reader::new(
+            self.claim.lock(),
+            self.observers.clone(),
+            self.store.clone(),
+        );
+        let writer = Writer::new(
+            self.claim,
+            self.clock,
+            self.durability,
+            self.flusher,
+            self.next,
+            self.observers,
+            self.storage,
+            self.store,
+            self.unsafe_admin,
+        );
 
         (reader, writer)
     }
 }
 
+impl Stream {
+    /// Register `observers` after opening (as [`Builder::observer`] does
+    /// before), for the handles split from the stream from then on.
+    pub(crate) fn observe(&mut self, observers: Observers) {
+        self.observers.extend(observers);
+    }
+}
+
 impl Append for Stream {
-    fn append<E>(&mut self, events: E, condition: Condition) -> Result<Position, Error>
+    fn append<E>(&mut self, events: E, condition: Condition) -> Result<AppendReceipt, Error>
     where
         E: IntoIterator<Item = Event<(), String>>,
         E::IntoIter: Send + 'static,
     {
-        operate::Appender::new(&mut || self.database.batch(), &mut self.next, &self.store)
-            .append(events, condition)
+        self.append_durable(events, condition, self.durability)
+    }
+
+    fn append_durable<E>(
+        &mut self,
+        events: E,
+        condition: Condition,
+        durability: Durability,
+    ) -> Result<AppendReceipt, Error>
+    where
+        E: IntoIterator<Item = Event<(), String>>,
+        E::IntoIter: Send + 'static,
+    {
+        operate::Appender::new(
+            &mut || self.storage.batch(durability),
+            self.clock.as_ref(),
+            &mut self.next,
+            &self.observers,
+            &self.store,
+        )
+        .append(events, condition)
     }
 }
 
 impl Select for Stream {
     fn select(&self, condition: Condition) -> SelectIter {
-        self.store.select(condition)
+        self.store.select(condition).observed(&self.observers)
+    }
+
+    fn explain(&self, condition: Condition) -> Plan {
+        Select::explain(&self.store, condition)
+    }
+
+    fn analyze(&self, condition: Condition) -> Result<Analysis> {
+        Select::analyze(&self.store, condition)
     }
 }
 
@@ -204,12 +552,55 @@ impl Select for Stream {
 #[derive(new, Clone, Debug)]
 #[new(const_fn, vis())]
 pub struct Reader {
+    #[allow(dead_code)]
+    lock: Option<Arc<Lock>>,
+    observers: Observers,
     store: Store,
 }
 
+impl Reader {
+    /// The stream's [`Catalog`]: which event types (at which versions) and
+    /// which tags it contains, how many of each, and when each was first and
+    /// last seen, with their string names where recoverable.
+    pub fn catalog(&self) -> Result<Catalog> {
+        self.store.catalog()
+    }
+
+    /// The stream's cardinality [`Statistics`]: the number of events, and of
+    /// events carrying each type name and each tag, as committed.
+    pub fn stats(&self) -> Result<Statistics> {
+        self.store.statistics()
+    }
+
+    /// The number of events committed to the stream (also its `next`
+    /// position).
+    pub fn len(&self) -> Result<u64> {
+        self.store.len()
+    }
+
+    /// Whether the stream holds no committed events.
+    pub fn is_empty(&self) -> Result<bool> {
+        self.len().map(|len| len == 0)
+    }
+
+    /// The [`Redaction`]s made to the payload of the event at `position`,
+    /// oldest first: empty if it has never been redacted.
+    pub fn redactions(&self, position: Position) -> Result<Vec<Redaction>> {
+        self.store.redactions(position)
+    }
+}
+
 impl Select for Reader {
     fn select(&self, condition: Condition) -> SelectIter {
-        self.store.select(condition)
+        self.store.select(condition).observed(&self.observers)
+    }
+
+    fn explain(&self, condition: Condition) -> Plan {
+        Select::explain(&self.store, condition)
+    }
+
+    fn analyze(&self, condition: Condition) -> Result<Analysis> {
+        Select::analyze(&self.store, condition)
     }
 }
 
@@ -223,26 +614,278 @@ impl Select for Reader {
 #[derive(new, Debug)]
 #[new(const_fn, vis())]
 pub struct Writer {
-    #[debug("Database")]
-    database: Database,
+    claim: Arc<Claim>,
+    #[debug("Clock")]
+    clock: Arc<dyn Clock>,
+    durability: Durability,
+    flusher: Option<Arc<Flusher>>,
     next: Position,
+    observers: Observers,
+    #[debug("Storage")]
+    storage: Arc<dyn Storage>,
     store: Store,
+    unsafe_admin: bool,
+}
+
+impl Writer {
+    /// A fresh `Writer` over the same storage: the store is recovered and the
+    /// `next` cursor read from the committed events, so it is exactly what
+    /// re-opening the stream would produce. (The store itself holds only its
+    /// keyspaces, so it is recovered in place rather than re-opened, which
+    /// keeps a fork's layered over its base.) Used to recover the writer
+    /// thread after a failed operation.
+    pub(crate) fn restart(&self) -> Result<Self> {
+        let storage = Arc::clone(&self.storage);
+        let flusher = self.flusher.clone();
+        let store = self.store.clone();
+
+        store.recover(storage.as_ref())?;
+
+        let next = store.len().map(Position::new)?;
+
+        Ok(Self::new(
+            Arc::clone(&self.claim),
+            Arc::clone(&self.clock),
+            self.durability,
+            flusher,
+            next,
+            self.observers.clone(),
+            storage,
+            store,
+            self.unsafe_admin,
+        ))
+    }
+}
+
+impl Writer {
+    /// The stream's writer epoch, as [`Stream::epoch`].
+    #[must_use]
+    pub fn epoch(&self) -> u64 {
+        self.claim.epoch()
+    }
+}
+
+impl Writer {
+    pub(crate) fn next(&self) -> Position {
+        self.next
+    }
+
+    pub(crate) fn observers(&self) -> &Observers {
+        &self.observers
+    }
+
+    pub(crate) fn store(&self) -> &Store {
+        &self.store
+    }
 }
 
 impl Append for Writer {
-    fn append<E>(&mut self, events: E, condition: Condition) -> Result<Position, Error>
+    fn append<E>(&mut self, events: E, condition: Condition) -> Result<AppendReceipt, Error>
+    where
+        E: IntoIterator<Item = Event<(), String>>,
+        E::IntoIter: Send + 'static,
+    {
+        self.append_durable(events, condition, self.durability)
+    }
+
+    fn append_durable<E>(
+        &mut self,
+        events: E,
+        condition: Condition,
+        durability: Durability,
+    ) -> Result<AppendReceipt, Error>
     where
         E: IntoIterator<Item = Event<(), String>>,
         E::IntoIter: Send + 'static,
     {
-        operate::Appender::new(&mut || self.database.batch(), &mut self.next, &self.store)
-            .append(events, condition)
+        operate::Appender::new(
+            &mut || self.storage.batch(durability),
+            self.clock.as_ref(),
+            &mut self.next,
+            &self.observers,
+            &self.store,
+        )
+        .append(events, condition)
+    }
+}
+
+impl Writer {
+    /// Import `events` carrying their original timestamps — as when migrating
+    /// from another store — rather than stamping them with the stream's clock.
+    /// They are appended unconditionally, at the next positions and indexed as
+    /// any append, in one batch. Only the `Writer` imports: the timestamps are
+    /// taken as given, in any order, so a [`Hybrid`](clock::Hybrid) clock's
+    /// ordering holds only for the events it stamps.
+    pub fn import<E>(&mut self, events: E) -> Result<AppendReceipt>
+    where
+        E: IntoIterator<Item = (Timestamp, Event<(), String>)>,
+    {
+        self.importer(events, true)
+    }
+
+    /// Begin a [`Bulk`] import, which defers building the imported events'
+    /// postings until it [`finish`](Bulk::finish)es, for loads of many
+    /// batches.
+    pub fn bulk(&mut self) -> Bulk<'_> {
+        Bulk::new(self)
+    }
+
+    fn importer<E>(&mut self, events: E, index: bool) -> Result<AppendReceipt>
+    where
+        E: IntoIterator<Item = (Timestamp, Event<(), String>)>,
+    {
+        let durability = self.durability;
+
+        operate::Appender::new(
+            &mut || self.storage.batch(durability),
+            self.clock.as_ref(),
+            &mut self.next,
+            &self.observers,
+            &self.store,
+        )
+        .import(events, index)
+    }
+}
+
+impl Writer {
+    /// Replace the payload of the event at `position` with `replacement` — to
+    /// scrub data which should never have been appended — leaving its
+    /// position, type, tags and timestamp, and so every condition decided on
+    /// it, as they were. The event reads as [`redacted`](Metadata::redacted)
+    /// from then on, and the [`Redaction`] (the hash of the payload replaced,
+    /// `reason`, and the time by the stream's clock) is kept in the stream's
+    /// audit trail, committed with the new payload in one batch.
+    ///
+    /// An administrative operation, outside the append path: observers are
+    /// not told, and a replica or a consumer which has already read the event
+    /// keeps the payload it read. An event read by a fork still open from the
+    /// stream (one before the fork's position) is refused.
+    pub fn redact<R>(
+        &mut self,
+        position: Position,
+        replacement: Data,
+        reason: R,
+    ) -> Result<Redaction>
+    where
+        R: Into<String>,
+    {
+        self.store.redact(
+            self.storage.batch(self.durability),
+            self.clock.as_ref(),
+            position,
+            replacement,
+            reason.into(),
+        )
+    }
+
+    /// Truncate the stream after `position`, for rolling a test or staging
+    /// stream back to a known point: every later event is deleted, with its
+    /// postings, counts and redactions, in one batch, and the next append is
+    /// made at the position after `position`. Refused unless the stream was
+    /// opened with [`unsafe_admin`](Builder::unsafe_admin).
+    ///
+    /// A [`Reader`] sees the stream as it was or as it is truncated, never in
+    /// between: a select already running when the events go skips those it
+    /// has not yet read. The truncated events are gone for good, so whatever
+    /// has read them — a projection, a replica — must be rebuilt. A fork may
+    /// not be truncated before the position it was forked at, nor a stream
+    /// before that of a fork still open from it.
+    pub fn truncate_after(&mut self, position: Position) -> Result<()> {
+        if !self.unsafe_admin {
+            return Err(
+                Report::new(Error).attach("truncation requires a stream opened with unsafe_admin")
+            );
+        }
+
+        let durability = self.durability;
+        let storage = &self.storage;
+
+        self.store
+            .truncate(&mut || storage.batch(durability), position, &mut self.next)
     }
 }
 
 impl From<Writer> for Stream {
     fn from(writer: Writer) -> Self {
-        Self::new(writer.database, writer.next, writer.store)
+        Self::new(
+            writer.claim,
+            writer.clock,
+            writer.durability,
+            writer.flusher,
+            writer.next,
+            writer.observers,
+            writer.storage,
+            writer.store,
+            writer.unsafe_admin,
+        )
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Bulk
+
+/// A bulk import through a [`Writer`], from [`Writer::bulk`]: each
+/// [`import`](Bulk::import) appends its events as [`Writer::import`] does, but
+/// without their postings, which are built for every imported event at once
+/// when the bulk import [`finish`](Bulk::finish)es — far cheaper for large
+/// loads than indexing batch by batch.
+///
+/// Until then the imported events are missing from the indices: a select
+/// answered from them (and a condition checked against them) does not see the
+/// events, though a full scan does. The events still to be indexed are
+/// recorded as they are imported, so a bulk import dropped unfinished is
+/// indexed on drop, and one interrupted by a crash when the stream is next
+/// opened for writing. If the indexing fails (in `finish`, or on drop, when an
+/// [`IndexFailed`](observe::Observation::IndexFailed) is observed), the gap
+/// lasts until then too, so appends conditional on the imported events should
+/// wait for it.
+#[derive(new, Debug)]
+#[new(const_fn, vis())]
+pub struct Bulk<'a> {
+    #[new(val(false))]
+    finished: bool,
+    writer: &'a mut Writer,
+}
+
+impl Bulk<'_> {
+    /// Import `events` carrying their original timestamps, deferring their
+    /// postings.
+    pub fn import<E>(&mut self, events: E) -> Result<AppendReceipt>
+    where
+        E: IntoIterator<Item = (Timestamp, Event<(), String>)>,
+    {
+        self.writer.importer(events, false)
+    }
+
+    /// Finish the import, building the postings (and counts) of every event
+    /// imported. A failure is not retried when the import is dropped.
+    pub fn finish(mut self) -> Result<()> {
+        self.finished = true;
+        self.index()
+    }
+
+    fn index(&mut self) -> Result<()> {
+        let durability = self.writer.durability;
+        let storage = &self.writer.storage;
+
+        self.writer.store.index(&mut || storage.batch(durability))
+    }
+}
+
+impl Drop for Bulk<'_> {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+
+        // An unfinished import which fails to index here is indexed when the
+        // stream is next opened for writing.
+        if let Err(report) = self.index() {
+            self.writer.observers.observe(&Observation::IndexFailed {
+                error: format!("{report:?}"),
+            });
+        }
     }
 }
 
@@ -277,6 +920,12 @@ impl AddAssign<u64> for Position {
     }
 }
 
+impl From<Position> for u64 {
+    fn from(position: Position) -> Self {
+        position.0
+    }
+}
+
 impl Default for Position {
     fn default() -> Self {
         Self::MIN
@@ -301,8 +950,11 @@ impl SubAssign<u64> for Position {
 
 // Timestamp
 
-/// The wall-clock time an event was appended, in nanoseconds since the Unix
-/// epoch. **Not monotonic** — its order may not match position order.
+/// The time an event was appended, in nanoseconds since the Unix epoch, as
+/// read from the stream's [`Clock`]. With the default
+/// [`System`](clock::System) clock it is wall-clock time and **not monotonic**
+/// — its order may not match position order; with a
+/// [`Hybrid`](clock::Hybrid) clock it strictly increases in position order.
 #[derive(new, Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
 pub struct Timestamp(#[new(name(nanos))] pub(crate) u64);
 
@@ -322,19 +974,39 @@ impl Timestamp {
     }
 }
 
+impl From<Timestamp> for u64 {
+    fn from(timestamp: Timestamp) -> Self {
+        timestamp.0
+    }
+}
+
 // =================================================================================================
 // Tests
 // =================================================================================================
 
 #[cfg(test)]
 mod tests {
-    use std::collections::BTreeSet;
+    use std::{
+        collections::BTreeSet,
+        iter,
+        mem,
+        time::Duration,
+    };
+
+    use assertables::assert_is_empty;
 
     use super::{
+        Backend,
+        Durability,
         Position,
         Reader,
         Stream,
+        Timestamp,
         Writer,
+        clock::{
+            Hybrid,
+            Manual,
+        },
         operate::{
             Condition,
             Selection,
@@ -357,11 +1029,19 @@ mod tests {
             Type,
             Version,
         },
-        utils::temp_path,
+        parity,
+        utils::{
+            hashing,
+            temp_path,
+        },
     };
 
-    fn stream() -> Stream {
-        Stream::builder(temp_path()).temporary(true).open().unwrap()
+    fn stream(backend: Backend) -> Stream {
+        Stream::builder(temp_path())
+            .backend(backend)
+            .temporary(true)
+            .open()
+            .unwrap()
     }
 
     fn event(identifier: &str, version: u8, tags: &[&str]) -> Event<(), String> {
@@ -380,9 +1060,8 @@ mod tests {
 
     // The masked, multi-selection query surface end to end via the
     // public Stream API. Each selection is one mask bit, in order.
-    #[test]
-    fn select_masks_events_by_selection() {
-        let mut stream = stream();
+    fn select_masks_events_by_selection(backend: Backend) {
+        let mut stream = stream(backend);
 
         stream
             .append(
@@ -415,9 +1094,8 @@ mod tests {
         assert_eq!(results[2].mask.as_ref(), [false, true].as_slice()); // Dropped+student:1
     }
 
-    #[test]
-    fn select_with_no_selections_scans_all_with_empty_mask() {
-        let mut stream = stream();
+    fn select_with_no_selections_scans_all_with_empty_mask(backend: Backend) {
+        let mut stream = stream(backend);
 
         stream
             .append(
@@ -437,9 +1115,8 @@ mod tests {
         assert_eq!(results[1].event.2.0, Position::new(1));
     }
 
-    #[test]
-    fn select_filters_by_version_range() {
-        let mut stream = stream();
+    fn select_filters_by_version_range(backend: Backend) {
+        let mut stream = stream(backend);
 
         stream
             .append(
@@ -466,9 +1143,8 @@ mod tests {
 
     // An event matching several selections is emitted once with multiple mask
     // bits set; bits stay independent across events.
-    #[test]
-    fn select_overlapping_selections_set_multiple_mask_bits() {
-        let mut stream = stream();
+    fn select_overlapping_selections_set_multiple_mask_bits(backend: Backend) {
+        let mut stream = stream(backend);
 
         stream
             .append(
@@ -500,9 +1176,8 @@ mod tests {
 
     // next_back yields events in descending position order, masks still paired
     // to the right event.
-    #[test]
-    fn select_reverse_iteration_pairs_masks() {
-        let mut stream = stream();
+    fn select_reverse_iteration_pairs_masks(backend: Backend) {
+        let mut stream = stream(backend);
 
         stream
             .append(
@@ -539,9 +1214,8 @@ mod tests {
     }
 
     // The `from` lower bound is inclusive and applies to the indexed path.
-    #[test]
-    fn select_from_position_lower_bound() {
-        let mut stream = stream();
+    fn select_from_position_lower_bound(backend: Backend) {
+        let mut stream = stream(backend);
 
         stream
             .append(
@@ -571,9 +1245,8 @@ mod tests {
     }
 
     // Selectors within one selection are OR-combined and contribute one mask bit.
-    #[test]
-    fn select_multiple_selectors_in_one_selection_or() {
-        let mut stream = stream();
+    fn select_multiple_selectors_in_one_selection_or(backend: Backend) {
+        let mut stream = stream(backend);
 
         stream
             .append(
@@ -597,12 +1270,44 @@ mod tests {
         assert_eq!(results[1].mask.as_ref(), [true].as_slice());
     }
 
+    // A selector with tags but no types matches events of any type carrying
+    // all its tags, on the index and scan paths alike; one with neither
+    // matches nothing.
+    fn select_by_tags_alone_matches_any_type(backend: Backend) {
+        let mut stream = stream(backend);
+
+        stream
+            .append(
+                vec![
+                    event("A", 0, &["student:1", "course:1"]),
+                    event("B", 1, &["student:1"]),
+                    event("C", 0, &["course:1"]),
+                ],
+                Condition::new(),
+            )
+            .unwrap();
+
+        let positions = |tags: &[&str]| {
+            let tags = tags.iter().map(|tag| Tag::new(*tag).unwrap());
+            let condition = Condition::new().selections([Selection::new([Selector::tags(tags)])]);
+
+            stream
+                .select(condition)
+                .map(|result| result.unwrap().event.meta().position().0)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(positions(&["student:1"]), [0, 1]);
+        assert_eq!(positions(&["student:1", "course:1"]), [0]);
+        assert_eq!(positions(&["course:2"]), [] as [u64; 0]);
+        assert_eq!(positions(&[]), [] as [u64; 0]);
+    }
+
     // Conditional (DCB) append. A condition rejects the append iff a
     // matching event already exists at or after the condition's position.
 
-    #[test]
-    fn append_with_empty_condition_is_unconditional() {
-        let mut stream = stream();
+    fn append_with_empty_condition_is_unconditional(backend: Backend) {
+        let mut stream = stream(backend);
 
         stream
             .append(vec![event("A", 0, &[])], Condition::new())
@@ -615,9 +1320,8 @@ mod tests {
         );
     }
 
-    #[test]
-    fn append_is_rejected_when_a_matching_event_exists() {
-        let mut stream = stream();
+    fn append_is_rejected_when_a_matching_event_exists(backend: Backend) {
+        let mut stream = stream(backend);
 
         stream
             .append(vec![event("Enrolled", 0, &[])], Condition::new())
@@ -638,9 +1342,8 @@ mod tests {
         assert_eq!(stream.len(), 1);
     }
 
-    #[test]
-    fn append_is_allowed_when_no_matching_event_exists() {
-        let mut stream = stream();
+    fn append_is_allowed_when_no_matching_event_exists(backend: Backend) {
+        let mut stream = stream(backend);
 
         stream
             .append(vec![event("Enrolled", 0, &[])], Condition::new())
@@ -663,9 +1366,8 @@ mod tests {
         assert_eq!(stream.len(), 2);
     }
 
-    #[test]
-    fn append_condition_window_starts_at_position() {
-        let mut stream = stream();
+    fn append_condition_window_starts_at_position(backend: Backend) {
+        let mut stream = stream(backend);
 
         stream
             .append(
@@ -693,9 +1395,8 @@ mod tests {
     // A window starting at or after the head short-circuits the index scan: a
     // caller anchored at the head gets no spurious conflict, even though a
     // matching event exists below the window.
-    #[test]
-    fn append_condition_window_at_head_never_conflicts() {
-        let mut stream = stream();
+    fn append_condition_window_at_head_never_conflicts(backend: Backend) {
+        let mut stream = stream(backend);
 
         stream
             .append(vec![event("Enrolled", 0, &[])], Condition::new())
@@ -703,9 +1404,10 @@ mod tests {
 
         let condition = Condition::new()
             .from(Position::new(1)) // == next; the Enrolled at position 0 is below it
-            .selections([Selection::new([Selector::types([
-                TypeSelector::new("Enrolled").unwrap(),
-            ])])]);
+            .selections([Selection::new([Selector::types([TypeSelector::new(
+                "Enrolled",
+            )
+            .unwrap()])])]);
 
         assert!(
             stream
@@ -715,9 +1417,8 @@ mod tests {
     }
 
     // With no position the condition checks the whole stream.
-    #[test]
-    fn append_with_no_position_checks_whole_stream() {
-        let mut stream = stream();
+    fn append_with_no_position_checks_whole_stream(backend: Backend) {
+        let mut stream = stream(backend);
 
         stream
             .append(vec![event("Enrolled", 0, &[])], Condition::new())
@@ -747,9 +1448,8 @@ mod tests {
     }
 
     // A tag-scoped selector conflicts only when both the type and the tag match.
-    #[test]
-    fn append_conflict_via_tag_scoped_selector() {
-        let mut stream = stream();
+    fn append_conflict_via_tag_scoped_selector(backend: Backend) {
+        let mut stream = stream(backend);
 
         stream
             .append(vec![event("Enrolled", 0, &["student:1"])], Condition::new())
@@ -779,9 +1479,8 @@ mod tests {
     }
 
     // A multi-selection condition honors every selection, not just the first.
-    #[test]
-    fn append_multi_selection_condition_honors_all_selections() {
-        let mut stream = stream();
+    fn append_multi_selection_condition_honors_all_selections(backend: Backend) {
+        let mut stream = stream(backend);
 
         stream
             .append(vec![event("B", 0, &[])], Condition::new())
@@ -800,9 +1499,8 @@ mod tests {
     // Split into a cloneable Reader (reads) and the unique Writer
     // (writes). The Reader (and clones of it) sees the Writer's committed
     // appends, and the Writer folds back into a Stream.
-    #[test]
-    fn split_reader_reads_writer_writes_then_recombines() {
-        let (reader, mut writer) = stream().split();
+    fn split_reader_reads_writer_writes_then_recombines(backend: Backend) {
+        let (reader, mut writer) = stream(backend).split();
 
         writer
             .append(vec![event("Enrolled", 0, &["student:1"])], Condition::new())
@@ -829,6 +1527,266 @@ mod tests {
         assert_eq!(stream.len(), 1);
     }
 
+    // A Writer imports events with the timestamps they are given, indexed as
+    // any append. A bulk import's events are missing from index-answered selects
+    // (and counts) until it finishes, though a full scan sees them; one dropped
+    // unfinished is indexed on drop.
+    fn import_keeps_timestamps_and_bulk_import_defers_postings(backend: Backend) {
+        let (reader, mut writer) = stream(backend).split();
+        let enrolled = || {
+            reader
+                .select(
+                    Condition::new().selections([Selection::new([Selector::types([
+                        TypeSelector::new("Enrolled").unwrap(),
+                    ])])]),
+                )
+                .map(|result| result.unwrap().event.meta().position().0)
+                .collect::<Vec<_>>()
+        };
+        let counted = || reader.stats().unwrap().ty(&Name::new("Enrolled").unwrap());
+
+        // Enough other events that an "Enrolled" select stays on the index path
+        // rather than being run as a filtered full scan.
+        let receipt = writer
+            .import(
+                iter::once((Timestamp::new(300), event("Enrolled", 0, &[]))).chain(
+                    iter::repeat_with(|| (Timestamp::new(100), event("Dropped", 0, &[]))).take(15),
+                ),
+            )
+            .unwrap();
+
+        assert_eq!(receipt.timestamps()[..2], [
+            Timestamp::new(300),
+            Timestamp::new(100)
+        ]);
+        assert_eq!(enrolled(), [0]);
+
+        let mut bulk = writer.bulk();
+
+        for nanos in [400, 500] {
+            bulk.import([(Timestamp::new(nanos), event("Enrolled", 0, &[]))])
+                .unwrap();
+        }
+
+        assert_eq!(enrolled(), [0]);
+        assert_eq!(counted(), 1);
+        assert_eq!(reader.select(Condition::new()).count(), 18);
+
+        bulk.finish().unwrap();
+
+        assert_eq!(enrolled(), [0, 16, 17]);
+        assert_eq!(counted(), 3);
+
+        writer
+            .bulk()
+            .import([(Timestamp::new(600), event("Enrolled", 0, &[]))])
+            .unwrap();
+
+        assert_eq!(enrolled(), [0, 16, 17, 18]);
+        assert_eq!(counted(), 4);
+        assert_eq!(reader.stats().unwrap().events(), 19);
+
+        // An append after a bulk import is indexed as usual.
+        writer
+            .append(vec![event("Enrolled", 0, &[])], Condition::new())
+            .unwrap();
+
+        assert_eq!(enrolled(), [0, 16, 17, 18, 19]);
+    }
+
+    // Redaction replaces only an event's payload: it keeps its position, facets
+    // and timestamp (so selects and conditions decide as before), reads as
+    // redacted, and each redaction is audited in order.
+    fn redaction_replaces_only_the_payload_and_is_audited(backend: Backend) {
+        let clock = Manual::new(Timestamp::new(100));
+        let (reader, mut writer) = Stream::builder(temp_path())
+            .backend(backend)
+            .clock(clock.clone())
+            .temporary(true)
+            .open()
+            .unwrap()
+            .split();
+
+        writer
+            .append(
+                vec![
+                    event("Enrolled", 0, &["student:1"]),
+                    event("Dropped", 0, &[]),
+                ],
+                Condition::new(),
+            )
+            .unwrap();
+
+        clock.set(Timestamp::new(200));
+
+        let redaction = writer
+            .redact(
+                Position::new(0),
+                Data::new("scrubbed").unwrap(),
+                "leaked PII",
+            )
+            .unwrap();
+
+        assert_eq!(redaction.hash(), hashing::hash(&"payload"));
+        assert_eq!(redaction.reason(), "leaked PII");
+        assert_eq!(redaction.timestamp(), Timestamp::new(200));
+
+        let condition = || {
+            Condition::new().selections([Selection::new([Selector::tags([
+                Tag::new("student:1").unwrap()
+            ])])])
+        };
+        let events = reader
+            .select(condition())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event.data().as_ref(), b"scrubbed");
+        assert!(events[0].event.meta().redacted());
+        assert_eq!(events[0].event.meta().position(), Position::new(0));
+        assert_eq!(events[0].event.meta().timestamp(), Timestamp::new(100));
+        assert_eq!(
+            events[0].event.facets().ty().name().0,
+            hashing::hash(&"Enrolled")
+        );
+
+        let untouched = reader.select(Condition::new()).last().unwrap().unwrap();
+
+        assert_eq!(untouched.event.data().as_ref(), b"payload");
+        assert!(!untouched.event.meta().redacted());
+        assert_is_empty!(reader.redactions(Position::new(1)).unwrap());
+
+        // A second redaction is audited after the first.
+        writer
+            .redact(Position::new(0), Data::new("blank").unwrap(), "again")
+            .unwrap();
+
+        let reasons = reader
+            .redactions(Position::new(0))
+            .unwrap()
+            .into_iter()
+            .map(|redaction| redaction.reason().to_owned())
+            .collect::<Vec<_>>();
+
+        assert_eq!(reasons, ["leaked PII", "again"]);
+        assert_eq!(
+            reader.redactions(Position::new(0)).unwrap()[1].hash(),
+            hashing::hash(&"scrubbed")
+        );
+
+        // The redacted event still decides a condition.
+        assert!(
+            writer
+                .append(vec![event("Enrolled", 0, &[])], condition())
+                .is_err()
+        );
+        assert!(
+            writer
+                .redact(Position::new(2), Data::new("none").unwrap(), "absent")
+                .is_err()
+        );
+    }
+
+    // Truncation is refused without the unsafe-admin flag. With it, every
+    // event after the position goes with its postings, counts and redactions,
+    // the next append follows the position, and a select already running skips
+    // the events it had yet to read. A fork is not truncated below its fork.
+    fn truncate_after_removes_later_events_whole(backend: Backend) {
+        let (_, mut writer) = stream(backend).split();
+
+        writer
+            .append(vec![event("Enrolled", 0, &[])], Condition::new())
+            .unwrap();
+
+        assert!(writer.truncate_after(Position::MIN).is_err());
+
+        let mut stream = Stream::builder(temp_path())
+            .backend(backend)
+            .temporary(true)
+            .unsafe_admin(true)
+            .open()
+            .unwrap();
+
+        // Enough other events that a student's select stays on the index path.
+        stream
+            .append(
+                iter::repeat_with(|| event("Audited", 0, &[])).take(16),
+                Condition::new(),
+            )
+            .unwrap();
+        stream
+            .append(
+                vec![
+                    event("Enrolled", 0, &["student:1"]),
+                    event("Dropped", 0, &["student:1"]),
+                    event("Enrolled", 0, &["student:1", "student:2"]),
+                ],
+                Condition::new(),
+            )
+            .unwrap();
+
+        let fork = stream.fork_at(Position::new(17), temp_path()).unwrap();
+
+        assert!(fork.split().1.truncate_after(Position::new(15)).is_err());
+
+        let (reader, mut writer) = stream.split();
+        let positions = |tag: &str| {
+            reader
+                .select(
+                    Condition::new()
+                        .selections([Selection::new([Selector::tags([Tag::new(tag).unwrap()])])]),
+                )
+                .map(|result| result.unwrap().event.meta().position().0)
+                .collect::<Vec<_>>()
+        };
+
+        writer
+            .redact(Position::new(18), Data::new("scrubbed").unwrap(), "test")
+            .unwrap();
+
+        let mut running = reader
+            .select(
+                Condition::new()
+                    .selections([Selection::new([Selector::tags([
+                        Tag::new("student:1").unwrap()
+                    ])])]),
+            )
+            .map(|result| result.unwrap().event.meta().position().0);
+
+        assert_eq!(running.next_back(), Some(18));
+
+        writer.truncate_after(Position::new(16)).unwrap();
+
+        assert_eq!(running.rev().collect::<Vec<_>>(), [16]);
+        assert_eq!(positions("student:1"), [16]);
+        assert_eq!(positions("student:2"), [0u64; 0]);
+        assert_eq!(reader.select(Condition::new()).count(), 17);
+        assert_eq!(reader.stats().unwrap().events(), 17);
+        assert_eq!(
+            reader.stats().unwrap().ty(&Name::new("Dropped").unwrap()),
+            0
+        );
+        assert_eq!(
+            reader.stats().unwrap().tag(&Tag::new("student:1").unwrap()),
+            1
+        );
+        assert_is_empty!(reader.redactions(Position::new(18)).unwrap());
+
+        let receipt = writer
+            .append(vec![event("Enrolled", 0, &["student:1"])], Condition::new())
+            .unwrap();
+
+        assert_eq!(receipt.last(), Position::new(17));
+        assert_eq!(positions("student:1"), [16, 17]);
+
+        // Truncating after the head (or at it) removes nothing.
+        writer.truncate_after(Position::new(17)).unwrap();
+        writer.truncate_after(Position::MAX).unwrap();
+
+        assert_eq!(Stream::from(writer).len(), 18);
+    }
+
     // The concurrency contract the multi-thread wrapper relies on: a `Reader` is
     // shareable across threads and cloneable, and a `Writer` can be moved to the
     // dedicated writer thread. Compile-time assertion only.
@@ -872,10 +1830,325 @@ mod tests {
         assert_eq!(results.len(), 2);
     }
 
-    // A full scan (no selections) honors the `from` lower bound.
+    // Events are stamped by the stream's clock: a manual clock's time exactly,
+    // and a hybrid clock's strictly increasing in position order, though the
+    // physical time goes back — across appends and a reopen alike.
+    #[test]
+    fn events_are_stamped_by_the_stream_clock() {
+        let path = temp_path();
+        let physical = Manual::new(Timestamp::new(100));
+        let timestamps = |stream: &Stream| {
+            stream
+                .select(Condition::new())
+                .map(|result| result.unwrap().event.meta().timestamp().0)
+                .collect::<Vec<_>>()
+        };
+
+        {
+            let mut stream = Stream::builder(&path)
+                .clock(physical.clone())
+                .open()
+                .unwrap();
+
+            stream
+                .append(vec![event("Enrolled", 0, &[])], Condition::new())
+                .unwrap();
+
+            assert_eq!(timestamps(&stream), [100]);
+        }
+
+        physical.set(Timestamp::new(50));
+
+        let mut stream = Stream::builder(&path)
+            .clock(Hybrid::new(physical.clone()))
+            .temporary(true)
+            .open()
+            .unwrap();
+
+        let receipt = stream
+            .append(
+                vec![event("Enrolled", 0, &[]), event("Dropped", 0, &[])],
+                Condition::new(),
+            )
+            .unwrap();
+
+        assert_eq!(receipt.timestamps(), [
+            Timestamp::new(101),
+            Timestamp::new(102)
+        ]);
+
+        physical.set(Timestamp::new(200));
+
+        stream
+            .append(vec![event("Enrolled", 0, &[])], Condition::new())
+            .unwrap();
+
+        assert_eq!(timestamps(&stream), [100, 101, 102, 200]);
+    }
+
+    // A bulk import interrupted before it is indexed (as by a crash, simulated
+    // by forgetting it) is indexed when the stream is next opened for writing.
+    #[test]
+    fn interrupted_bulk_import_is_indexed_on_reopen() {
+        let path = temp_path();
+
+        {
+            let (_, mut writer) = Stream::builder(&path).open().unwrap().split();
+            let mut bulk = writer.bulk();
+
+            bulk.import([
+                (Timestamp::new(100), event("Enrolled", 0, &["student:1"])),
+                (Timestamp::new(200), event("Enrolled", 0, &["student:2"])),
+            ])
+            .unwrap();
+
+            mem::forget(bulk);
+        }
+
+        let stream = Stream::builder(&path).temporary(true).open().unwrap();
+        let results = stream
+            .select(
+                Condition::new()
+                    .selections([Selection::new([Selector::tags([
+                        Tag::new("student:2").unwrap()
+                    ])])]),
+            )
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].event.meta().timestamp(), Timestamp::new(200));
+        assert_eq!(stream.stats().unwrap().events(), 2);
+    }
+
+    // A fork holds the stream's history up to the fork position — events,
+    // postings and counts alike — and appends of its own from there, and
+    // neither it nor the stream it was forked from sees the other's appends.
+    #[test]
+    fn fork_shares_history_up_to_its_position() {
+        let mut stream = Stream::builder(temp_path()).temporary(true).open().unwrap();
+
+        // Enough other events that a student's select stays on the index path.
+        stream
+            .append(
+                iter::repeat_with(|| event("Audited", 0, &[])).take(12),
+                Condition::new(),
+            )
+            .unwrap();
+
+        stream
+            .append(
+                vec![
+                    event("Enrolled", 0, &["student:1"]),
+                    event("Dropped", 0, &["student:1"]),
+                    event("Enrolled", 0, &["student:2"]),
+                ],
+                Condition::new(),
+            )
+            .unwrap();
+
+        assert!(stream.fork_at(Position::new(16), temp_path()).is_err());
+
+        let mut fork = stream.fork_at(Position::new(14), temp_path()).unwrap();
+
+        assert_eq!(fork.len(), 14);
+        assert_eq!(fork.epoch(), 1);
+
+        let positions = |stream: &Stream, tag: &str| {
+            stream
+                .select(
+                    Condition::new()
+                        .selections([Selection::new([Selector::tags([Tag::new(tag).unwrap()])])]),
+                )
+                .map(|result| result.unwrap().event.meta().position().0)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(positions(&fork, "student:1"), [12, 13]);
+        assert_eq!(positions(&fork, "student:2"), [0u64; 0]);
+        assert_eq!(fork.stats().unwrap().events(), 14);
+        assert_eq!(fork.stats().unwrap().ty(&Name::new("Enrolled").unwrap()), 1);
+
+        let receipt = fork
+            .append(vec![event("Enrolled", 0, &["student:3"])], Condition::new())
+            .unwrap();
+
+        stream
+            .append(vec![event("Enrolled", 0, &["student:3"])], Condition::new())
+            .unwrap();
+
+        assert_eq!(receipt.last(), Position::new(14));
+        assert_eq!(positions(&fork, "student:3"), [14]);
+        assert_eq!(positions(&stream, "student:3"), [15]);
+        assert_eq!(
+            fork.select(Condition::new())
+                .rev()
+                .take(3)
+                .map(|result| result.unwrap().event.facets().ty().name().0)
+                .collect::<Vec<_>>(),
+            ["Enrolled", "Dropped", "Enrolled"].map(|name| hashing::hash(&name))
+        );
+
+        // A redaction in the fork leaves the stream's payload as it was.
+        fork.split()
+            .1
+            .redact(Position::new(12), Data::new("scrubbed").unwrap(), "what-if")
+            .unwrap();
+
+        let first = stream
+            .select(Condition::new().from(Position::new(12)))
+            .next()
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(first.event.data().as_ref(), b"payload");
+        assert_eq!(stream.len(), 16);
+    }
+
+    // Events imported in bulk whose indexing never ran (as after a crash) are
+    // indexed before a fork is made, so the fork selects and counts them.
     #[test]
-    fn full_scan_respects_from_position() {
-        let mut stream = stream();
+    fn fork_indexes_events_imported_without_postings() {
+        let mut stream = Stream::builder(temp_path()).temporary(true).open().unwrap();
+
+        // Enough other events that a student's select stays on the index path.
+        stream
+            .append(
+                iter::repeat_with(|| event("Audited", 0, &[])).take(12),
+                Condition::new(),
+            )
+            .unwrap();
+        stream
+            .append(vec![event("Enrolled", 0, &["student:1"])], Condition::new())
+            .unwrap();
+
+        let (_, mut writer) = stream.split();
+        let mut bulk = writer.bulk();
+
+        bulk.import([(Timestamp::new(1), event("Enrolled", 0, &["student:1"]))])
+            .unwrap();
+
+        mem::forget(bulk);
+
+        let stream = Stream::from(writer);
+        let enrolled =
+            |stream: &Stream| stream.stats().unwrap().ty(&Name::new("Enrolled").unwrap());
+
+        assert_eq!(enrolled(&stream), 1);
+
+        let fork = stream.fork_at(Position::new(14), temp_path()).unwrap();
+
+        assert_eq!(enrolled(&fork), 2);
+        assert_eq!(
+            fork.select(
+                Condition::new()
+                    .selections([Selection::new([Selector::tags([
+                        Tag::new("student:1").unwrap()
+                    ])])])
+            )
+            .map(|result| result.unwrap().event.meta().position().0)
+            .collect::<Vec<_>>(),
+            [12, 13]
+        );
+    }
+
+    // While a fork is open, the stream it was forked from may not redact or
+    // truncate the events the fork reads through to it, only those from the
+    // fork position on; once the fork is dropped, it may again.
+    #[test]
+    fn fork_pins_the_history_it_reads() {
+        let mut stream = Stream::builder(temp_path())
+            .temporary(true)
+            .unsafe_admin(true)
+            .open()
+            .unwrap();
+
+        stream
+            .append(
+                iter::repeat_with(|| event("Enrolled", 0, &["student:1"])).take(4),
+                Condition::new(),
+            )
+            .unwrap();
+
+        let fork = stream.fork_at(Position::new(2), temp_path()).unwrap();
+        let (reader, mut writer) = stream.split();
+        let scrubbed = || Data::new("scrubbed").unwrap();
+        let payload = |position: u64| {
+            fork.select(Condition::new().from(Position::new(position)))
+                .next()
+                .unwrap()
+                .unwrap()
+                .event
+                .data()
+                .as_ref()
+                .to_vec()
+        };
+
+        assert!(writer.redact(Position::new(1), scrubbed(), "gdpr").is_err());
+        assert!(writer.truncate_after(Position::new(0)).is_err());
+        assert_eq!(payload(1), b"payload");
+        assert_eq!(fork.stats().unwrap().events(), 2);
+
+        writer.redact(Position::new(2), scrubbed(), "gdpr").unwrap();
+        writer.truncate_after(Position::new(2)).unwrap();
+
+        assert_eq!(reader.len().unwrap(), 3);
+
+        drop(fork);
+
+        writer.redact(Position::new(1), scrubbed(), "gdpr").unwrap();
+        writer.truncate_after(Position::new(0)).unwrap();
+
+        assert_eq!(reader.len().unwrap(), 1);
+    }
+
+    // A fully configured stream (fsync per commit, a background flush interval,
+    // and explicit cache/journal sizes) behaves as the default one, including a
+    // per-append durability override, and its data persists across re-open.
+    #[test]
+    fn configured_stream_persists_across_reopen() {
+        let path = temp_path();
+
+        {
+            let mut stream = Stream::builder(&path)
+                .cache_size(8 * 1024 * 1024)
+                .durability(Durability::Sync)
+                .flush_interval(Duration::from_millis(10))
+                .journal_size(64 * 1024 * 1024)
+                .open()
+                .unwrap();
+
+            stream
+                .append(vec![event("Audited", 0, &[])], Condition::new())
+                .unwrap();
+            stream
+                .append_durable(
+                    vec![event("Imported", 0, &[]), event("Imported", 0, &[])],
+                    Condition::new(),
+                    Durability::Buffer,
+                )
+                .unwrap();
+        }
+
+        let stream = Stream::builder(&path).temporary(true).open().unwrap();
+
+        assert_eq!(stream.len(), 3);
+    }
+
+    // fjall asserts the journal size minimum; the builder rejects it gracefully.
+    #[test]
+    fn journal_size_below_the_minimum_is_an_error() {
+        let result = Stream::builder(temp_path())
+            .temporary(true)
+            .journal_size(1024)
+            .open();
+
+        assert!(result.is_err());
+    }
+
+    // A full scan (no selections) honors the `from` lower bound.
+    fn full_scan_respects_from_position(backend: Backend) {
+        let mut stream = stream(backend);
 
         stream
             .append(
@@ -895,9 +2168,8 @@ mod tests {
     }
 
     // Appending zero events is a usage error, not a panic/underflow.
-    #[test]
-    fn append_with_no_events_is_an_error() {
-        let mut stream = stream();
+    fn append_with_no_events_is_an_error(backend: Backend) {
+        let mut stream = stream(backend);
 
         assert!(
             stream
@@ -909,9 +2181,8 @@ mod tests {
 
     // A queried event is fully readable through the public accessors a
     // consumer (e.g. the model layer) needs — payload, metadata, and type.
-    #[test]
-    fn queried_event_exposes_public_accessors() {
-        let mut stream = stream();
+    fn queried_event_exposes_public_accessors(backend: Backend) {
+        let mut stream = stream(backend);
         stream
             .append(vec![event("Enrolled", 0, &["student:1"])], Condition::new())
             .unwrap();
@@ -936,4 +2207,30 @@ mod tests {
         let expected: Name<u64> = Name::<String>::new("Enrolled").unwrap().into();
         assert_eq!(event.facets().ty().name(), &expected);
     }
+
+    parity! {
+        select_masks_events_by_selection,
+        select_with_no_selections_scans_all_with_empty_mask,
+        select_filters_by_version_range,
+        select_overlapping_selections_set_multiple_mask_bits,
+        select_reverse_iteration_pairs_masks,
+        select_from_position_lower_bound,
+        select_multiple_selectors_in_one_selection_or,
+        select_by_tags_alone_matches_any_type,
+        append_with_empty_condition_is_unconditional,
+        append_is_rejected_when_a_matching_event_exists,
+        append_is_allowed_when_no_matching_event_exists,
+        append_condition_window_starts_at_position,
+        append_condition_window_at_head_never_conflicts,
+        append_with_no_position_checks_whole_stream,
+        append_conflict_via_tag_scoped_selector,
+        append_multi_selection_condition_honors_all_selections,
+        split_reader_reads_writer_writes_then_recombines,
+        full_scan_respects_from_position,
+        append_with_no_events_is_an_error,
+        queried_event_exposes_public_accessors,
+        import_keeps_timestamps_and_bulk_import_defers_postings,
+        redaction_replaces_only_the_payload_and_is_audited,
+        truncate_after_removes_later_events_whole,
+    }
 }
@@ -0,0 +1,45 @@
+//! The audit trail of [`Redaction`]s: a record, kept apart from the events, of
+//! each payload rewritten by [`Writer::redact`](crate::stream::Writer::redact),
+//! read back with [`Reader::redactions`](crate::stream::Reader::redactions).
+
+use fancy_constructor::new;
+
+use crate::stream::Timestamp;
+
+// =================================================================================================
+// Audit
+// =================================================================================================
+
+// Redaction
+
+/// The record of one redaction of an event's payload: the stable hash of the
+/// payload it replaced (so the original can be matched against a copy held
+/// elsewhere, without being kept), why it was replaced, and when — by the
+/// stream's [`Clock`](crate::stream::clock::Clock).
+#[derive(new, Clone, Debug, Eq, PartialEq)]
+#[new(vis(pub(crate)))]
+pub struct Redaction {
+    hash: u64,
+    reason: String,
+    timestamp: Timestamp,
+}
+
+impl Redaction {
+    /// The stable hash of the replaced payload's bytes.
+    #[must_use]
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// The reason given for the redaction.
+    #[must_use]
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+
+    /// When the payload was replaced.
+    #[must_use]
+    pub fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+}
@@ -0,0 +1,93 @@
+//! The [`Catalog`] of what a stream contains, from
+//! [`Reader::catalog`](crate::stream::Reader::catalog): every event type (by
+//! name, with its versions) and every tag, each with how many events carry it
+//! and when it was first and last seen — listed from the indices, without
+//! decoding a payload.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    event::{
+        Name,
+        Tag,
+        Version,
+    },
+    stream::Metadata,
+};
+
+// =================================================================================================
+// Catalog
+// =================================================================================================
+
+/// The event types and tags in a stream, each ordered by its hash. Built by a
+/// full scan of the indices, so its cost grows with the stream: it is meant
+/// for operational tooling rather than hot paths (see
+/// [`Reader::stats`](crate::stream::Reader::stats) for counts alone).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Catalog {
+    /// Every tag carried by any event.
+    pub tags: Vec<TagEntry>,
+    /// Every event type name of any event.
+    pub types: Vec<TypeEntry>,
+}
+
+impl Catalog {
+    /// The entry for `tag`, if any event carries it.
+    #[must_use]
+    pub fn tag(&self, tag: &Tag<String>) -> Option<&TagEntry> {
+        let tag: Tag<u64> = tag.clone().into();
+
+        self.tags.iter().find(|entry| entry.hash == tag.0)
+    }
+
+    /// The entry for type name `name`, if any event has it.
+    #[must_use]
+    pub fn ty(&self, name: &Name<String>) -> Option<&TypeEntry> {
+        let name: Name<u64> = name.clone().into();
+
+        self.types.iter().find(|entry| entry.hash == name.0)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Tag Entry
+
+/// One tag in a [`Catalog`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TagEntry {
+    /// The tag's stable hash.
+    pub hash: u64,
+    /// The tag's string form, when recoverable: it is recorded with the first
+    /// event appended to carry it, so it is `None` only for a tag carried
+    /// solely by events written before names were recorded.
+    pub tag: Option<String>,
+    /// The number of events carrying the tag.
+    pub count: u64,
+    /// The metadata of the first event (by position) carrying the tag.
+    pub first: Metadata,
+    /// The metadata of the last event (by position) carrying the tag.
+    pub last: Metadata,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Type Entry
+
+/// One event type name in a [`Catalog`], across all its versions.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TypeEntry {
+    /// The type name's stable hash.
+    pub hash: u64,
+    /// The type name's string form, when recoverable (as for
+    /// [`TagEntry::tag`]).
+    pub name: Option<String>,
+    /// The number of events with the type name, of any version.
+    pub count: u64,
+    /// The number of events at each version of the type.
+    pub versions: BTreeMap<Version, u64>,
+    /// The metadata of the first event (by position) with the type name.
+    pub first: Metadata,
+    /// The metadata of the last event (by position) with the type name.
+    pub last: Metadata,
+}
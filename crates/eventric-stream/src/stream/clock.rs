@@ -0,0 +1,198 @@
+//! The [`Clock`] an appended event's [`Timestamp`] is read from, registered on
+//! a stream [`Builder`](crate::stream::Builder): the [`System`] clock (the
+//! default), a [`Manual`] clock for tests, and a [`Hybrid`] logical clock,
+//! whose timestamps never go backwards in position order.
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{
+            AtomicU64,
+            Ordering,
+        },
+    },
+    time::Duration,
+};
+
+use fancy_constructor::new;
+
+use crate::{
+    error::Result,
+    stream::Timestamp,
+};
+
+// =================================================================================================
+// Clock
+// =================================================================================================
+
+// Clock
+
+/// A source of the [`Timestamp`]s events are appended with. Called on the
+/// appending thread for each event, with the timestamp of the event before it
+/// (`None` for the first event of a stream), which a clock may use to keep its
+/// timestamps in order.
+pub trait Clock: Send + Sync {
+    /// The timestamp for an event following one stamped `last`.
+    fn now(&self, last: Option<Timestamp>) -> Result<Timestamp>;
+
+    /// Whether the clock reads `last`. If not, the stream's last event is not
+    /// read for the first event of each append, which is then passed `None`.
+    /// Defaults to `true`.
+    fn reads_last(&self) -> bool {
+        true
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// System
+
+/// The system's wall clock, as [`Timestamp::now`]. The default. Its timestamps
+/// follow the system clock, so one set back (or a step in its adjustment) can
+/// stamp an event earlier than the event before it.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct System;
+
+impl Clock for System {
+    fn now(&self, _last: Option<Timestamp>) -> Result<Timestamp> {
+        Timestamp::now()
+    }
+
+    fn reads_last(&self) -> bool {
+        false
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Manual
+
+/// A clock which reads whatever time it was last set to, for tests: every
+/// event is stamped with that time until it is set or advanced again. Clones
+/// share the time, so a clone kept by a test drives the clock registered on
+/// the stream.
+#[derive(new, Clone, Debug)]
+#[new(args(timestamp: Timestamp))]
+pub struct Manual {
+    #[new(val(Arc::new(AtomicU64::new(timestamp.0))))]
+    nanos: Arc<AtomicU64>,
+}
+
+impl Manual {
+    /// Set the clock to `timestamp` (which may be earlier than its time).
+    pub fn set(&self, timestamp: Timestamp) {
+        self.nanos.store(timestamp.0, Ordering::Release);
+    }
+
+    /// Advance the clock by `duration` (saturating at the latest timestamp).
+    pub fn advance(&self, duration: Duration) {
+        let nanos = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+
+        self.nanos
+            .try_update(Ordering::AcqRel, Ordering::Acquire, |current| {
+                Some(current.saturating_add(nanos))
+            })
+            .ok();
+    }
+}
+
+impl Clock for Manual {
+    fn now(&self, _last: Option<Timestamp>) -> Result<Timestamp> {
+        Ok(Timestamp::new(self.nanos.load(Ordering::Acquire)))
+    }
+
+    fn reads_last(&self) -> bool {
+        false
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Hybrid
+
+/// A hybrid logical clock over a physical clock `C` (the [`System`] clock by
+/// default): an event is stamped with the physical time, unless that is not
+/// after the event before it, when it is stamped one nanosecond after that
+/// event instead. Timestamps therefore strictly increase in position order —
+/// across appends and reopens alike, as the event before is read from the
+/// stream — while staying within a clock step of the physical time.
+#[derive(new, Clone, Copy, Debug, Default)]
+#[new(const_fn)]
+pub struct Hybrid<C = System> {
+    physical: C,
+}
+
+impl<C> Clock for Hybrid<C>
+where
+    C: Clock,
+{
+    fn now(&self, last: Option<Timestamp>) -> Result<Timestamp> {
+        let physical = self.physical.now(last)?;
+
+        Ok(match last {
+            Some(last) if physical <= last => Timestamp::new(last.0.saturating_add(1)),
+            _ => physical,
+        })
+    }
+}
+
+// =================================================================================================
+// Tests
+// =================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{
+        Clock as _,
+        Hybrid,
+        Manual,
+    };
+    use crate::stream::Timestamp;
+
+    #[test]
+    fn manual_clock_reads_the_time_it_was_set_to() {
+        let clock = Manual::new(Timestamp::new(100));
+        let handle = clock.clone();
+
+        assert_eq!(clock.now(None).unwrap(), Timestamp::new(100));
+
+        handle.advance(Duration::from_nanos(5));
+
+        assert_eq!(clock.now(None).unwrap(), Timestamp::new(105));
+
+        handle.set(Timestamp::new(50));
+
+        assert_eq!(
+            clock.now(Some(Timestamp::new(105))).unwrap(),
+            Timestamp::new(50)
+        );
+    }
+
+    #[test]
+    fn hybrid_clock_never_goes_backwards() {
+        let physical = Manual::new(Timestamp::new(100));
+        let clock = Hybrid::new(physical.clone());
+
+        assert_eq!(clock.now(None).unwrap(), Timestamp::new(100));
+        assert_eq!(
+            clock.now(Some(Timestamp::new(100))).unwrap(),
+            Timestamp::new(101)
+        );
+
+        physical.set(Timestamp::new(10));
+
+        assert_eq!(
+            clock.now(Some(Timestamp::new(101))).unwrap(),
+            Timestamp::new(102)
+        );
+
+        physical.set(Timestamp::new(200));
+
+        assert_eq!(
+            clock.now(Some(Timestamp::new(102))).unwrap(),
+            Timestamp::new(200)
+        );
+    }
+}
@@ -1,9 +1,17 @@
 //! Concurrent access to a single-threaded [`Stream`](crate::stream::Stream): an
 //! [`owner::Owner`] spawns a dedicated writer thread and hands out
 //! [`proxy::Proxy`] clones that funnel writes over a bounded channel (the
-//! global write lock) and read through cloned `Reader`s.
+//! global write lock) and read through cloned `Reader`s, or run a closure on
+//! the writer thread itself as a [`transaction::Transaction`]. With the `async`
+//! feature, [`asynchronous`] adds an executor-agnostic async surface over the
+//! same `Proxy`.
 
+#[cfg(feature = "async")]
+pub mod asynchronous;
 pub mod owner;
 pub mod proxy;
+pub mod transaction;
 
+mod health;
+mod notifier;
 mod processor;
@@ -0,0 +1,239 @@
+//! The executor-agnostic async surface over an [`Owner`](super::owner::Owner)'s
+//! stream (behind the `async` feature): [`AsyncAppend`], whose
+//! [`AppendFuture`] resolves on the writer thread's reply; [`AsyncSelect`], a
+//! [`SelectStream`] adapter over any [`Select`]; and the live [`Subscription`]
+//! returned by [`Proxy::subscribe`](super::proxy::Proxy::subscribe). Nothing
+//! here depends on a particular runtime — every future is woken by the writer
+//! thread itself.
+
+use std::{
+    pin::Pin,
+    sync::Arc,
+    task::{
+        Context,
+        Poll,
+    },
+};
+
+use crossbeam::channel::{
+    self,
+    TrySendError,
+};
+use derive_more::Debug;
+use error_stack::Report;
+use fancy_constructor::new;
+use futures_core::Stream;
+
+use super::{
+    notifier::Notifier,
+    processor::Operation,
+};
+use crate::{
+    error::{
+        Error,
+        Result,
+    },
+    event::Event,
+    stream::{
+        Reader,
+        operate::{
+            Condition,
+            append::AppendReceipt,
+            select::{
+                EventAndMask,
+                Select,
+                SelectIter,
+            },
+        },
+    },
+};
+
+// =================================================================================================
+// Asynchronous
+// =================================================================================================
+
+// Async Append
+
+/// The async mirror of [`Append`](crate::stream::operate::append::Append):
+/// appends candidate events subject to a `Condition` without blocking the
+/// calling thread.
+pub trait AsyncAppend {
+    /// Appends `events` as [`Append::append`] does, returning a future which
+    /// resolves to the [`AppendReceipt`] of the appended events (or the
+    /// rejection, carrying its `Conflict` marker) once the writer thread
+    /// replies.
+    ///
+    /// [`Append::append`]: crate::stream::operate::append::Append::append
+    fn append_async<E>(&mut self, events: E, condition: Condition) -> AppendFuture
+    where
+        E: IntoIterator<Item = Event<(), String>>,
+        E::IntoIter: Send + 'static;
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Append Future
+
+/// The future returned by [`AsyncAppend::append_async`]. It first enqueues the
+/// append on the bounded writer channel, then waits for the writer thread's
+/// reply; it owns everything it needs, so it is `'static` and `Send`.
+#[derive(new, Debug)]
+#[new(const_fn, vis(pub(crate)))]
+pub struct AppendFuture {
+    notifier: Arc<Notifier>,
+    operation: Option<Operation>,
+    #[debug("AsyncReceiver")]
+    receiver: oneshot::AsyncReceiver<Result<AppendReceipt>>,
+    sender: channel::Sender<Operation>,
+}
+
+impl Future for AppendFuture {
+    type Output = Result<AppendReceipt>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(operation) = self.operation.take() {
+            // The channel is full (writer backpressure): register to be woken when
+            // the writer next takes an operation from it, then retry once, so a
+            // slot freed between the first attempt and the registration is not
+            // missed.
+            let operation = match self.sender.try_send(operation) {
+                Err(TrySendError::Full(operation)) => {
+                    self.notifier.register_sender(cx.waker());
+                    self.sender.try_send(operation)
+                }
+                sent => sent,
+            };
+
+            match operation {
+                Ok(()) => {}
+                Err(TrySendError::Full(operation)) => {
+                    self.operation = Some(operation);
+
+                    return Poll::Pending;
+                }
+                Err(TrySendError::Disconnected(_)) => {
+                    return Poll::Ready(Err(Report::new(Error).attach("append_future/poll/send")));
+                }
+            }
+        }
+
+        Pin::new(&mut self.receiver).poll(cx).map(|reply| {
+            reply
+                .map_err(|_| Report::new(Error).attach("append_future/poll/receive"))
+                .flatten()
+        })
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Async Select
+
+/// The async mirror of [`Select`]: run a [`Condition`] as a masked query,
+/// yielding a [`futures_core::Stream`] rather than an iterator. Implemented for
+/// every [`Select`].
+pub trait AsyncSelect {
+    /// Run `condition` as a query, as [`Select::select`] does, as a
+    /// [`SelectStream`].
+    fn select_async(&self, condition: Condition) -> SelectStream;
+}
+
+impl<T> AsyncSelect for T
+where
+    T: Select,
+{
+    fn select_async(&self, condition: Condition) -> SelectStream {
+        self.select(condition).into()
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Select Stream
+
+/// A [`futures_core::Stream`] adapter over a [`SelectIter`]. Reads are local
+/// LSM lookups which never wait on the writer, so each poll is always ready.
+#[derive(Debug)]
+pub struct SelectStream(SelectIter);
+
+impl From<SelectIter> for SelectStream {
+    fn from(iter: SelectIter) -> Self {
+        Self(iter)
+    }
+}
+
+impl Stream for SelectStream {
+    type Item = Result<EventAndMask>;
+
+    fn poll_next(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Poll::Ready(self.0.next())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Subscription
+
+/// A live, unbounded [`futures_core::Stream`] over the events matching a
+/// [`Condition`], returned by
+/// [`Proxy::subscribe`](super::proxy::Proxy::subscribe). It yields every
+/// matching event already in the stream, then waits for the writer thread to
+/// commit more, resuming just past the last event it yielded. It ends once the
+/// writer thread has exited and every committed match has been yielded.
+#[derive(new, Debug)]
+#[new(vis(pub(crate)))]
+pub struct Subscription {
+    condition: Condition,
+    #[new(default)]
+    commits: Option<u64>,
+    #[new(default)]
+    iter: Option<SelectIter>,
+    notifier: Arc<Notifier>,
+    reader: Reader,
+}
+
+impl Stream for Subscription {
+    type Item = Result<EventAndMask>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        loop {
+            if let Some(iter) = &mut this.iter {
+                match iter.next() {
+                    Some(Ok(event)) => {
+                        this.condition.position = Some(event.event.meta().position() + 1);
+
+                        return Poll::Ready(Some(Ok(event)));
+                    }
+                    Some(Err(err)) => return Poll::Ready(Some(Err(err))),
+                    None => this.iter = None,
+                }
+            }
+
+            // Re-run the query whenever a commit has landed since the last run. The
+            // count is read *before* the query, so a commit racing the query is
+            // picked up by the next run rather than lost.
+            let commits = this.notifier.commits();
+
+            if this.commits != Some(commits) {
+                this.commits = Some(commits);
+                this.iter = Some(this.reader.select(this.condition.clone()));
+
+                continue;
+            }
+
+            if this.notifier.is_closed() {
+                return Poll::Ready(None);
+            }
+
+            // Register, then re-check: a commit (or close) between the check above
+            // and the registration would otherwise never wake this task.
+            this.notifier.register(cx.waker());
+
+            if this.notifier.commits() == commits && !this.notifier.is_closed() {
+                return Poll::Pending;
+            }
+        }
+    }
+}
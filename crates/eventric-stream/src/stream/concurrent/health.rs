@@ -0,0 +1,72 @@
+use std::{
+    sync::{
+        Condvar,
+        Mutex,
+        MutexGuard,
+    },
+    time::Instant,
+};
+
+use super::owner::Status;
+
+// =================================================================================================
+// Health
+// =================================================================================================
+
+/// The writer thread's shared lifecycle [`Status`]: set by the `Processor` as
+/// the thread ends, read by the `Owner` (and attached to `Proxy` errors), and
+/// waited on by a bounded shutdown.
+#[derive(Debug)]
+pub struct Health {
+    changed: Condvar,
+    status: Mutex<Status>,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self {
+            changed: Condvar::new(),
+            status: Mutex::new(Status::Running),
+        }
+    }
+}
+
+impl Health {
+    /// The current status.
+    pub fn status(&self) -> Status {
+        self.lock().clone()
+    }
+
+    /// Replace the current status, waking any waiter.
+    pub fn set(&self, status: Status) {
+        *self.lock() = status;
+
+        self.changed.notify_all();
+    }
+
+    /// Wait until the status is no longer [`Status::Running`], or `deadline`
+    /// passes, returning the status at that point.
+    pub fn wait(&self, deadline: Instant) -> Status {
+        let mut status = self.lock();
+
+        while matches!(*status, Status::Running) {
+            let Some(timeout) = deadline.checked_duration_since(Instant::now()) else {
+                break;
+            };
+
+            status = match self.changed.wait_timeout(status, timeout) {
+                Ok((status, _)) => status,
+                Err(poisoned) => poisoned.into_inner().0,
+            };
+        }
+
+        status.clone()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Status> {
+        match self.status.lock() {
+            Ok(status) => status,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
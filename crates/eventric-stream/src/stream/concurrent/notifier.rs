@@ -0,0 +1,102 @@
+use std::{
+    sync::{
+        Mutex,
+        MutexGuard,
+        atomic::{
+            AtomicBool,
+            AtomicU64,
+            Ordering,
+        },
+    },
+    task::Waker,
+};
+
+// =================================================================================================
+// Notifier
+// =================================================================================================
+
+/// The writer thread's signals: a monotonically increasing commit count plus
+/// the wakers of any task waiting for the next commit, and the wakers of any
+/// task waiting for room on the full writer channel. The `Processor` bumps the
+/// count after each successful append, wakes the senders on each operation it
+/// takes from the channel (and closes it on exit); waiting tasks register a
+/// waker and re-check, so no commit (or freed slot) is missed between a check
+/// and the registration.
+#[derive(Debug, Default)]
+pub struct Notifier {
+    closed: AtomicBool,
+    commits: AtomicU64,
+    senders: Mutex<Vec<Waker>>,
+    wakers: Mutex<Vec<Waker>>,
+}
+
+impl Notifier {
+    /// Record a commit and wake every registered waiter.
+    pub fn notify(&self) {
+        self.commits.fetch_add(1, Ordering::AcqRel);
+        wake(&self.wakers);
+    }
+
+    /// Record that an operation has been taken from the writer channel, and
+    /// wake every sender waiting for room on it.
+    pub fn dequeue(&self) {
+        wake(&self.senders);
+    }
+
+    /// Record that the writer thread has exited and wake every registered
+    /// waiter and sender, so nothing waits on a commit (or a slot) which can
+    /// never happen.
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+        wake(&self.wakers);
+        wake(&self.senders);
+    }
+}
+
+#[cfg(feature = "async")]
+impl Notifier {
+    /// The number of commits observed so far.
+    pub fn commits(&self) -> u64 {
+        self.commits.load(Ordering::Acquire)
+    }
+
+    /// Whether the writer thread has exited.
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    /// Register `waker` to be woken on the next commit (or on close).
+    pub fn register(&self, waker: &Waker) {
+        register(&self.wakers, waker);
+    }
+
+    /// Register `waker` to be woken when the writer next takes an operation
+    /// from its channel (or on close).
+    pub fn register_sender(&self, waker: &Waker) {
+        register(&self.senders, waker);
+    }
+}
+
+fn lock(wakers: &Mutex<Vec<Waker>>) -> MutexGuard<'_, Vec<Waker>> {
+    match wakers.lock() {
+        Ok(wakers) => wakers,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+#[cfg(feature = "async")]
+fn register(wakers: &Mutex<Vec<Waker>>, waker: &Waker) {
+    let mut wakers = lock(wakers);
+
+    if !wakers.iter().any(|registered| registered.will_wake(waker)) {
+        wakers.push(waker.clone());
+    }
+}
+
+fn wake(wakers: &Mutex<Vec<Waker>>) {
+    let wakers = lock(wakers).drain(..).collect::<Vec<_>>();
+
+    for waker in wakers {
+        waker.wake();
+    }
+}
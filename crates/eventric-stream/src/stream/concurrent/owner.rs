@@ -1,9 +1,21 @@
 //! The [`Owner`] — holds a [`Stream`]'s dedicated writer
 //! thread and hands out [`Proxy`] clones for concurrent access.
 
-use std::thread::{
-    self,
-    JoinHandle,
+use std::{
+    fmt::{
+        self,
+        Display,
+        Formatter,
+    },
+    sync::Arc,
+    thread::{
+        self,
+        JoinHandle,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
 };
 
 use crossbeam::channel;
@@ -11,6 +23,8 @@ use error_stack::Report;
 use fancy_constructor::new;
 
 use super::{
+    health::Health,
+    notifier::Notifier,
     processor::{
         Operation,
         Processor,
@@ -23,6 +37,10 @@ use crate::{
         Reader,
         Stream,
         Writer,
+        observe::{
+            Observer,
+            Observers,
+        },
     },
 };
 
@@ -30,30 +48,118 @@ use crate::{
 // Owner
 // =================================================================================================
 
+// Builder
+
+/// Configures and spawns an [`Owner`]. Obtained from [`Owner::builder`].
+#[derive(new, Debug)]
+#[new(vis())]
+pub struct Builder {
+    #[new(default)]
+    capacity: Option<usize>,
+    #[new(default)]
+    observers: Observers,
+    #[new(default)]
+    restart: Option<bool>,
+    stream: Stream,
+}
+
+impl Builder {
+    /// Take ownership of the stream, spawning the dedicated writer thread that
+    /// serialises all writes.
+    #[must_use]
+    pub fn build(mut self) -> Owner {
+        self.stream.observe(self.observers);
+
+        let stream = self.stream.split();
+        let channel = channel::bounded::<Operation>(self.capacity.unwrap_or(128));
+        let health = Arc::new(Health::default());
+        let notifier = Arc::new(Notifier::default());
+        let restart = self.restart.unwrap_or_default();
+
+        let handle = thread::spawn({
+            let health = Arc::clone(&health);
+            let notifier = Arc::clone(&notifier);
+
+            move || Processor::new(health, notifier, channel.1, restart, stream.1).process()
+        });
+        let reader = stream.0;
+        let sender = channel.0;
+
+        Owner::new_inner(handle, health, notifier, reader, sender)
+    }
+}
+
+impl Builder {
+    /// The capacity of the bounded channel funnelling writes to the writer
+    /// thread: how many operations may queue before a write through a
+    /// [`Proxy`] waits for room (the backpressure bound), or fails through
+    /// [`Proxy::try_append`]. Defaults to `128`.
+    #[must_use]
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    /// Register an [`Observer`] on the owned stream, as
+    /// [`Builder::observer`](crate::stream::Builder::observer) does: it sees
+    /// every append and select through the owner's proxies, and additionally
+    /// the writer channel's
+    /// [`QueueDepth`](crate::stream::observe::Observation::QueueDepth) as each
+    /// operation is taken.
+    #[must_use]
+    pub fn observer<O>(mut self, observer: O) -> Self
+    where
+        O: Observer + 'static,
+    {
+        self.observers.push(Arc::new(observer));
+        self
+    }
+
+    /// Whether the writer thread recovers from a failed operation — a panic
+    /// (e.g. in the caller's event iterator) — by restarting on a fresh
+    /// `Writer`, its `next` cursor recovered from the committed events, rather
+    /// than stopping with [`Status::Failed`]. Queued operations are
+    /// unaffected. Defaults to `false`.
+    #[must_use]
+    pub fn restart(mut self, restart: bool) -> Self {
+        self.restart = Some(restart);
+        self
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Owner
+
 /// Owns a [`Stream`] across a dedicated writer thread,
 /// handing out [`Proxy`] clones for concurrent access. The unique holder of the
-/// stream's `Writer`; reclaim the underlying stream with [`Owner::into_inner`].
+/// stream's `Writer`; reclaim the underlying stream with [`Owner::into_inner`]
+/// (immediately) or [`Owner::shutdown`] (bounded).
 #[derive(new, Debug)]
 #[new(const_fn, name(new_inner), vis())]
 pub struct Owner {
     handle: JoinHandle<Result<Writer, Report<Error>>>,
+    health: Arc<Health>,
+    notifier: Arc<Notifier>,
     reader: Reader,
     sender: channel::Sender<Operation>,
 }
 
 impl Owner {
+    /// Begin configuring an owner of `stream`. Configure with
+    /// [`capacity`](Builder::capacity), [`observer`](Builder::observer), and
+    /// [`restart`](Builder::restart), and
+    /// finish with [`build`](Builder::build).
+    #[must_use]
+    pub fn builder(stream: Stream) -> Builder {
+        Builder::new(stream)
+    }
+
     /// Take ownership of `stream`, spawning the dedicated writer thread that
-    /// serialises all writes.
+    /// serialises all writes, with the default configuration.
     #[must_use]
     pub fn new(stream: Stream) -> Self {
-        let stream = stream.split();
-        let channel = channel::bounded::<Operation>(128);
-
-        let handle = thread::spawn(move || Processor::new(channel.1, stream.1).process());
-        let reader = stream.0;
-        let sender = channel.0;
-
-        Self::new_inner(handle, reader, sender)
+        Self::builder(stream).build()
     }
 }
 
@@ -62,7 +168,18 @@ impl Owner {
     /// (channelled) writes against this owner's stream.
     #[must_use]
     pub fn proxy(&self) -> Proxy {
-        Proxy::new(self.reader.clone(), self.sender.clone())
+        Proxy::new(
+            Arc::clone(&self.health),
+            Arc::clone(&self.notifier),
+            self.reader.clone(),
+            self.sender.clone(),
+        )
+    }
+
+    /// The writer thread's current [`Status`].
+    #[must_use]
+    pub fn status(&self) -> Status {
+        self.health.status()
     }
 }
 
@@ -71,16 +188,77 @@ impl Owner {
     ///
     /// # Errors
     ///
-    /// Returns an error if the writer thread cannot be signalled or joined.
+    /// Returns an error if the writer thread cannot be signalled or joined, or
+    /// if it had failed.
     pub fn into_inner(self) -> Result<Stream, Report<Error>> {
         self.sender
             .send(Operation::Exit)
             .map_err(|_| Report::new(Error).attach("owner/into_inner/send"))?;
 
+        self.join()
+    }
+
+    /// Shut the writer thread down gracefully and reclaim the underlying
+    /// [`Stream`]: every append already queued is drained (committed, and its
+    /// caller answered) before the thread stops, waiting at most `timeout` in
+    /// total.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the writer thread cannot be signalled, if it had
+    /// failed, or if the drain does not finish within `timeout` — in which case
+    /// the writer thread still finishes the drain in the background, and the
+    /// stream is closed once it has.
+    pub fn shutdown(self, timeout: Duration) -> Result<Stream, Report<Error>> {
+        let deadline = Instant::now() + timeout;
+
+        // The exit is queued behind every pending operation, so the writer thread
+        // reaches it only once they have all been processed.
+        self.sender
+            .send_deadline(Operation::Exit, deadline)
+            .map_err(|_| Report::new(Error).attach("owner/shutdown/send"))?;
+
+        if let Status::Running = self.health.wait(deadline) {
+            return Err(Report::new(Error)
+                .attach("owner/shutdown/timeout")
+                .attach(format!("drain did not finish within {timeout:?}")));
+        }
+
+        self.join()
+    }
+
+    fn join(self) -> Result<Stream, Report<Error>> {
         self.handle
             .join()
-            .map_err(|_| Report::new(Error).attach("owner/into_inner/join"))
+            .map_err(|_| Report::new(Error).attach("owner/join"))
             .flatten()
             .map(Into::into)
     }
 }
+
+// -------------------------------------------------------------------------------------------------
+
+// Status
+
+/// The lifecycle status of an [`Owner`]'s writer thread, from
+/// [`Owner::status`].
+#[derive(Clone, Debug)]
+pub enum Status {
+    /// The writer thread is processing operations.
+    Running,
+    /// The writer thread stopped on the given (unrecovered) failure — a panic
+    /// or a failed restart. Every later write through a [`Proxy`] fails.
+    Failed(Arc<Report<Error>>),
+    /// The writer thread stopped normally, on shutdown.
+    Stopped,
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Running => write!(f, "writer status: running"),
+            Self::Failed(report) => write!(f, "writer status: failed: {report:?}"),
+            Self::Stopped => write!(f, "writer status: stopped"),
+        }
+    }
+}
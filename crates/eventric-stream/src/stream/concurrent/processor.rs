@@ -1,3 +1,12 @@
+use std::{
+    any::Any,
+    panic::{
+        self,
+        AssertUnwindSafe,
+    },
+    sync::Arc,
+};
+
 use crossbeam::channel;
 use derive_more::{
     Debug,
@@ -6,15 +15,25 @@ use derive_more::{
 use error_stack::Report;
 use fancy_constructor::new;
 
+use super::{
+    health::Health,
+    notifier::Notifier,
+    owner::Status,
+    transaction::Transaction,
+};
 use crate::{
     error::Error,
     event::Event,
     stream::{
-        Position,
+        Durability,
         Writer,
+        observe::Observation,
         operate::{
             Condition,
-            append::Append as _,
+            append::{
+                Append as _,
+                AppendReceipt,
+            },
         },
     },
 };
@@ -26,20 +45,98 @@ use crate::{
 #[derive(new, Debug)]
 #[new(const_fn)]
 pub struct Processor {
+    health: Arc<Health>,
+    notifier: Arc<Notifier>,
     receiver: channel::Receiver<Operation>,
+    restart: bool,
     writer: Writer,
 }
 
 impl Processor {
-    pub fn process(mut self) -> Result<Writer, Report<Error>> {
+    pub fn process(self) -> Result<Writer, Report<Error>> {
+        let health = Arc::clone(&self.health);
+        let notifier = Arc::clone(&self.notifier);
+
+        // A panic anywhere in the loop ends the thread as a failure rather than
+        // as an opaque join error, so it is reported through the status.
+        let result = panic::catch_unwind(AssertUnwindSafe(|| self.receive()))
+            .unwrap_or_else(|payload| Err(panicked(payload.as_ref())));
+
+        let result = match result {
+            Ok(writer) => {
+                health.set(Status::Stopped);
+
+                Ok(writer)
+            }
+            Err(report) => {
+                let report = Arc::new(report);
+
+                health.set(Status::Failed(Arc::clone(&report)));
+
+                Err(Report::new(Error)
+                    .attach("processor/process/failed")
+                    .attach(report))
+            }
+        };
+
+        // Close the notifier however the loop ends, so nothing waits on a commit
+        // which can never happen.
+        notifier.close();
+
+        result
+    }
+
+    fn receive(mut self) -> Result<Writer, Report<Error>> {
         loop {
-            match self.receiver.recv() {
-                Ok(Operation::Append(append)) => self.append(append)?,
+            let operation = self.receiver.recv();
+
+            self.notifier.dequeue();
+            self.depth();
+
+            match operation {
+                Ok(Operation::Append(append)) => {
+                    if let Err(report) = self.append(append) {
+                        self.recover(report)?;
+                    }
+                }
+                Ok(Operation::Transact(transact)) => {
+                    if let Err(report) = self.transact(transact) {
+                        self.recover(report)?;
+                    }
+                }
                 Ok(Operation::Exit) => return Ok(self.writer),
                 Err(_) => return Err(Report::new(Error).attach("processor/process/receive")),
             }
         }
     }
+
+    fn depth(&self) {
+        let observers = self.writer.observers();
+
+        if !observers.is_empty() {
+            observers.observe(&Observation::QueueDepth {
+                depth: self.receiver.len(),
+                capacity: self.receiver.capacity().unwrap_or_default(),
+            });
+        }
+    }
+
+    // A failed operation (a panic) is fatal unless the processor restarts: then
+    // the writer is replaced by a fresh one over the same database, its cursor
+    // recovered from the committed events (an interrupted append never
+    // committed, so nothing is lost), and the queued operations carry on.
+    fn recover(&mut self, report: Report<Error>) -> Result<(), Report<Error>> {
+        if !self.restart {
+            return Err(report);
+        }
+
+        self.writer = self
+            .writer
+            .restart()
+            .map_err(|restart| restart.attach("processor/recover/restart"))?;
+
+        Ok(())
+    }
 }
 
 impl Processor {
@@ -51,21 +148,78 @@ impl Processor {
     where
         F: FnOnce(&mut Writer) -> Result<R, Report<Error>>,
     {
-        sender
-            .send(operation(&mut self.writer))
-            .map_err(|_| Report::new(Error).attach("processor/writer/send"))
+        // Contain a panic (e.g. from the caller's event iterator, which runs on
+        // this thread) to the operation: the caller is told, and the processor
+        // decides whether to recover.
+        //
+        // The caller may have stopped waiting for the reply (an append which
+        // timed out, or a dropped future): the operation ran all the same, so
+        // an undeliverable reply is dropped.
+        match panic::catch_unwind(AssertUnwindSafe(|| operation(&mut self.writer))) {
+            Ok(result) => {
+                sender.send(result).ok();
+
+                Ok(())
+            }
+            Err(payload) => {
+                sender
+                    .send(Err(Report::new(Error).attach("processor/writer/panic")))
+                    .ok();
+
+                Err(panicked(payload.as_ref()))
+            }
+        }
     }
 }
 
 impl Processor {
     fn append(&mut self, append: AppendOperation) -> Result<(), Report<Error>> {
+        let notifier = Arc::clone(&self.notifier);
+
         self.writer(
-            |writer| writer.append(append.events, append.condition),
+            |writer| {
+                match append.durability {
+                    Some(durability) => {
+                        writer.append_durable(append.events, append.condition, durability)
+                    }
+                    None => writer.append(append.events, append.condition),
+                }
+                .inspect(|_| notifier.notify())
+            },
             append.sender,
         )
     }
 }
 
+impl Processor {
+    fn transact(&mut self, transact: TransactOperation) -> Result<(), Report<Error>> {
+        let notifier = Arc::clone(&self.notifier);
+
+        self.writer(
+            |writer| {
+                (transact.operation)(&mut Transaction::new(&notifier, writer));
+
+                Ok(())
+            },
+            transact.sender,
+        )
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Panics
+
+fn panicked(payload: &(dyn Any + Send)) -> Report<Error> {
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(ToString::to_string)
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| String::from("(non-string panic payload)"));
+
+    Report::new(Error).attach("processor/panic").attach(message)
+}
+
 // -------------------------------------------------------------------------------------------------
 
 // Operation
@@ -73,6 +227,7 @@ impl Processor {
 #[derive(Debug, From)]
 pub enum Operation {
     Append(AppendOperation),
+    Transact(TransactOperation),
     Exit,
 }
 
@@ -82,5 +237,16 @@ pub struct AppendOperation {
     #[debug("Box<dyn Iterator<Item = Event<(), String>> + Send>")]
     events: Box<dyn Iterator<Item = Event<(), String>> + Send>,
     condition: Condition,
-    sender: oneshot::Sender<Result<Position, Report<Error>>>,
+    durability: Option<Durability>,
+    sender: oneshot::Sender<Result<AppendReceipt, Report<Error>>>,
+}
+
+// The closure of a `Proxy::transact`, which sends its result back itself (on a
+// channel of the result's type), so the reply only says that it ran.
+#[derive(new, Debug)]
+#[new(const_fn)]
+pub struct TransactOperation {
+    #[debug("Box<dyn FnOnce(&mut Transaction<'_>) + Send>")]
+    operation: Box<dyn FnOnce(&mut Transaction<'_>) + Send>,
+    sender: oneshot::Sender<Result<(), Report<Error>>>,
 }
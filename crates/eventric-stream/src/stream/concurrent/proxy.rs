@@ -1,28 +1,72 @@
 //! The [`Proxy`] — a cloneable handle that reads through a cloned `Reader` and
 //! funnels writes to the [`Owner`](super::owner::Owner)'s writer thread.
 
-use crossbeam::channel;
+use std::{
+    fmt::{
+        self,
+        Display,
+        Formatter,
+    },
+    sync::{
+        Arc,
+        Mutex,
+        MutexGuard,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use crossbeam::channel::{
+    self,
+    SendTimeoutError,
+    TrySendError,
+};
 use error_stack::Report;
 use fancy_constructor::new;
+use oneshot::{
+    RecvTimeoutError,
+    TryRecvError,
+};
 
-use super::processor::{
-    AppendOperation,
-    Operation,
+use super::{
+    health::Health,
+    notifier::Notifier,
+    processor::{
+        AppendOperation,
+        Operation,
+        TransactOperation,
+    },
+    transaction::Transaction,
 };
 use crate::{
-    error::Error,
+    error::{
+        Backpressure,
+        Error,
+        Timeout,
+    },
     event::Event,
     stream::{
-        Position,
+        Durability,
         Reader,
+        catalog::Catalog,
         operate::{
             Condition,
-            append::Append,
+            append::{
+                Append,
+                AppendReceipt,
+            },
+            explain::{
+                Analysis,
+                Plan,
+            },
             select::{
                 Select,
                 SelectIter,
             },
         },
+        statistics::Statistics,
     },
 };
 
@@ -33,10 +77,15 @@ use crate::{
 /// A cheaply-cloneable, shareable handle to an [`Owner`](super::owner::Owner)'s
 /// stream. It impls both [`Select`] (reads go straight through a cloned
 /// `Reader`) and [`Append`] (writes are funnelled over the channel to the
-/// writer thread, blocking on the reply).
+/// writer thread, blocking on the reply). Writes which must not block
+/// indefinitely go through [`try_append`](Proxy::try_append) or
+/// [`append_timeout`](Proxy::append_timeout).
 #[derive(new, Clone, Debug)]
 #[new(const_fn, vis(pub(crate)))]
 pub struct Proxy {
+    health: Arc<Health>,
+    #[cfg_attr(not(feature = "async"), allow(dead_code))]
+    notifier: Arc<Notifier>,
     reader: Reader,
     sender: channel::Sender<Operation>,
 }
@@ -47,33 +96,197 @@ impl Proxy {
         F: FnOnce(oneshot::Sender<Result<R, Report<Error>>>) -> O,
         O: Into<Operation>,
     {
-        let channel = oneshot::channel();
+        let (operation, receiver) = Self::operation(operation);
 
         self.sender
-            .send(operation(channel.0).into())
-            .map_err(|_| Report::new(Error).attach("proxy/sender/send"))?;
+            .send(operation)
+            .map_err(|_| self.stopped("proxy/sender/send"))?;
 
         // Block on the reply: the writer thread answers via the paired sender
         // after it has processed the operation. (A non-blocking `try_recv` would
         // race the worker and usually observe an empty channel.)
-        channel
-            .1
+        receiver
+            .recv()
+            .map_err(|_| self.stopped("proxy/sender/receive"))
+            .flatten()
+    }
+
+    // The operation `operation` makes with the sender of the writer thread's
+    // reply, and the receiver of that reply.
+    fn operation<F, O, R>(operation: F) -> (Operation, oneshot::Receiver<Result<R, Report<Error>>>)
+    where
+        F: FnOnce(oneshot::Sender<Result<R, Report<Error>>>) -> O,
+        O: Into<Operation>,
+    {
+        let (sender, receiver) = oneshot::channel();
+
+        (operation(sender).into(), receiver)
+    }
+
+    // The append of `events` under `condition` (persisted with `durability`, or
+    // the stream's default), to be made with the sender of its reply.
+    fn appending<E>(
+        events: E,
+        condition: Condition,
+        durability: Option<Durability>,
+    ) -> impl FnOnce(oneshot::Sender<Result<AppendReceipt, Report<Error>>>) -> AppendOperation
+    where
+        E: IntoIterator<Item = Event<(), String>>,
+        E::IntoIter: Send + 'static,
+    {
+        let events = Box::new(IntoIterator::into_iter(events));
+
+        move |sender| AppendOperation::new(events, condition, durability, sender)
+    }
+
+    // A failure to reach (or hear back from) the writer thread means it has
+    // stopped, so the writer's status rides along to say why.
+    fn stopped(&self, location: &'static str) -> Report<Error> {
+        Report::new(Error)
+            .attach(location)
+            .attach(self.health.status())
+    }
+}
+
+impl Proxy {
+    /// Append `events` as [`append`](Append::append) does, but fail at once,
+    /// with the [`Backpressure`] marker attached, if the writer thread's
+    /// channel is full rather than wait for room. Once enqueued, the append is
+    /// waited for as usual.
+    pub fn try_append<E>(
+        &mut self,
+        events: E,
+        condition: Condition,
+    ) -> Result<AppendReceipt, Report<Error>>
+    where
+        E: IntoIterator<Item = Event<(), String>>,
+        E::IntoIter: Send + 'static,
+    {
+        let (operation, receiver) = Self::operation(Self::appending(events, condition, None));
+
+        self.sender.try_send(operation).map_err(|err| match err {
+            TrySendError::Full(_) => Report::new(Error).attach(Backpressure),
+            TrySendError::Disconnected(_) => self.stopped("proxy/try_append/send"),
+        })?;
+
+        receiver
             .recv()
-            .map_err(|_| Report::new(Error).attach("proxy/sender/receive"))
+            .map_err(|_| self.stopped("proxy/try_append/receive"))
+            .flatten()
+    }
+
+    /// Append `events` as [`append`](Append::append) does, but give up waiting
+    /// after `timeout`, for room on the writer thread's channel and for its
+    /// reply together, failing with the [`Timeout`] marker attached. An append
+    /// which could not be enqueued in time carries [`Backpressure`] too, and
+    /// was not committed; one which was enqueued may still be committed (or
+    /// rejected), and carries a [`PendingAppend`] through which to wait for its
+    /// outcome.
+    pub fn append_timeout<E>(
+        &mut self,
+        events: E,
+        condition: Condition,
+        timeout: Duration,
+    ) -> Result<AppendReceipt, Report<Error>>
+    where
+        E: IntoIterator<Item = Event<(), String>>,
+        E::IntoIter: Send + 'static,
+    {
+        // A timeout too long to represent as a deadline is no timeout at all.
+        let Some(deadline) = Instant::now().checked_add(timeout) else {
+            return self.append(events, condition);
+        };
+
+        let (operation, receiver) = Self::operation(Self::appending(events, condition, None));
+
+        self.sender
+            .send_deadline(operation, deadline)
+            .map_err(|err| match err {
+                SendTimeoutError::Timeout(_) => {
+                    Report::new(Error).attach(Timeout).attach(Backpressure)
+                }
+                SendTimeoutError::Disconnected(_) => self.stopped("proxy/append_timeout/send"),
+            })?;
+
+        match receiver.recv_deadline(deadline) {
+            Ok(result) => result,
+            Err(RecvTimeoutError::Timeout) => {
+                Err(Report::new(Error)
+                    .attach(Timeout)
+                    .attach(PendingAppend::new(
+                        Arc::clone(&self.health),
+                        Mutex::new(Some(receiver)),
+                    )))
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                Err(self.stopped("proxy/append_timeout/receive"))
+            }
+        }
+    }
+}
+
+impl Proxy {
+    /// Run `operation` on the writer thread, with a [`Transaction`] through
+    /// which to select and append against the writer's current head, and return
+    /// its result. No other write is made while it runs, so a read-modify-write
+    /// which would otherwise retry on [`Conflict`](crate::error::Conflict) under
+    /// contention is made once; every other write waits for it, so it should be
+    /// short. A panic in `operation` is contained as an append's would be.
+    pub fn transact<F, R>(&self, operation: F) -> Result<R, Report<Error>>
+    where
+        F: FnOnce(&mut Transaction<'_>) -> Result<R, Report<Error>> + Send + 'static,
+        R: Send + 'static,
+    {
+        // The result comes back on a channel of its own type: the writer
+        // thread's reply says only whether `operation` ran to its end.
+        let (sender, receiver) = oneshot::channel();
+        let operation = Box::new(move |transaction: &mut Transaction<'_>| {
+            sender.send(operation(transaction)).ok();
+        });
+
+        self.sender(|sender| TransactOperation::new(operation, sender))?;
+
+        receiver
+            .try_recv()
+            .map_err(|_| Report::new(Error).attach("proxy/transact/receive"))
             .flatten()
     }
 }
 
+impl Proxy {
+    /// The stream's [`Catalog`], as
+    /// [`Reader::catalog`](crate::stream::Reader::catalog).
+    pub fn catalog(&self) -> Result<Catalog, Report<Error>> {
+        self.reader.catalog()
+    }
+
+    /// The stream's cardinality [`Statistics`], as
+    /// [`Reader::stats`](crate::stream::Reader::stats).
+    pub fn stats(&self) -> Result<Statistics, Report<Error>> {
+        self.reader.stats()
+    }
+}
+
 impl Append for Proxy {
-    fn append<E>(&mut self, events: E, condition: Condition) -> Result<Position, Report<Error>>
+    fn append<E>(&mut self, events: E, condition: Condition) -> Result<AppendReceipt, Report<Error>>
     where
         E: IntoIterator<Item = Event<(), String>>,
         E::IntoIter: Send + 'static,
     {
-        let events = IntoIterator::into_iter(events);
-        let events = Box::new(events);
+        self.sender(Self::appending(events, condition, None))
+    }
 
-        self.sender(|sender| AppendOperation::new(events, condition, sender))
+    fn append_durable<E>(
+        &mut self,
+        events: E,
+        condition: Condition,
+        durability: Durability,
+    ) -> Result<AppendReceipt, Report<Error>>
+    where
+        E: IntoIterator<Item = Event<(), String>>,
+        E::IntoIter: Send + 'static,
+    {
+        self.sender(Self::appending(events, condition, Some(durability)))
     }
 }
 
@@ -81,4 +294,140 @@ impl Select for Proxy {
     fn select(&self, condition: Condition) -> SelectIter {
         self.reader.select(condition)
     }
+
+    fn explain(&self, condition: Condition) -> Plan {
+        self.reader.explain(condition)
+    }
+
+    fn analyze(&self, condition: Condition) -> Result<Analysis, Report<Error>> {
+        self.reader.analyze(condition)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Pending Append
+
+/// An append which [`Proxy::append_timeout`] gave up waiting for after
+/// enqueueing it: the writer thread may yet commit (or reject) it. Attached to
+/// the timed-out append's report; find it with
+/// `report.downcast_ref::<PendingAppend>()` and wait for the outcome. The
+/// outcome is given once: waiting again fails.
+#[derive(new, Debug)]
+#[new(vis())]
+pub struct PendingAppend {
+    health: Arc<Health>,
+    receiver: Mutex<Option<Reply>>,
+}
+
+impl PendingAppend {
+    /// Wait for the append's outcome, as [`append`](Append::append) would have
+    /// returned it.
+    pub fn wait(&self) -> Result<AppendReceipt, Report<Error>> {
+        let mut receiver = self.receiver();
+        let receiver = receiver.take().ok_or_else(taken)?;
+
+        receiver.recv().map_err(|_| self.stopped()).flatten()
+    }
+
+    /// Wait at most `timeout` for the append's outcome: `None` if it is still
+    /// pending then.
+    pub fn wait_timeout(&self, timeout: Duration) -> Option<Result<AppendReceipt, Report<Error>>> {
+        let mut receiver = self.receiver();
+        let outcome = match receiver
+            .as_ref()
+            .map(|receiver| receiver.recv_timeout(timeout))
+        {
+            Some(Ok(result)) => result,
+            Some(Err(RecvTimeoutError::Timeout)) => return None,
+            Some(Err(RecvTimeoutError::Disconnected)) => Err(self.stopped()),
+            None => Err(taken()),
+        };
+
+        receiver.take();
+
+        Some(outcome)
+    }
+
+    /// The append's outcome if it has been decided, without waiting: `None` if
+    /// it is still pending.
+    #[must_use]
+    pub fn try_outcome(&self) -> Option<Result<AppendReceipt, Report<Error>>> {
+        let mut receiver = self.receiver();
+        let outcome = match receiver.as_ref().map(oneshot::Receiver::try_recv) {
+            Some(Ok(result)) => result,
+            Some(Err(TryRecvError::Empty)) => return None,
+            Some(Err(TryRecvError::Disconnected)) => Err(self.stopped()),
+            None => Err(taken()),
+        };
+
+        receiver.take();
+
+        Some(outcome)
+    }
+
+    fn receiver(&self) -> MutexGuard<'_, Option<Reply>> {
+        match self.receiver.lock() {
+            Ok(receiver) => receiver,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    fn stopped(&self) -> Report<Error> {
+        Report::new(Error)
+            .attach("pending_append/receive")
+            .attach(self.health.status())
+    }
+}
+
+type Reply = oneshot::Receiver<Result<AppendReceipt, Report<Error>>>;
+
+fn taken() -> Report<Error> {
+    Report::new(Error).attach("the pending append's outcome has already been taken")
+}
+
+impl Display for PendingAppend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "append enqueued: its outcome is pending")
+    }
+}
+
+#[cfg(feature = "async")]
+impl Proxy {
+    /// Subscribe to the events matching `condition`: every match already in
+    /// the stream, then each new match as the writer thread commits it (see
+    /// [`Subscription`](super::asynchronous::Subscription)).
+    #[must_use]
+    pub fn subscribe(&self, condition: Condition) -> super::asynchronous::Subscription {
+        super::asynchronous::Subscription::new(
+            condition,
+            Arc::clone(&self.notifier),
+            self.reader.clone(),
+        )
+    }
+}
+
+#[cfg(feature = "async")]
+impl super::asynchronous::AsyncAppend for Proxy {
+    fn append_async<E>(
+        &mut self,
+        events: E,
+        condition: Condition,
+    ) -> super::asynchronous::AppendFuture
+    where
+        E: IntoIterator<Item = Event<(), String>>,
+        E::IntoIter: Send + 'static,
+    {
+        let events = IntoIterator::into_iter(events);
+        let events = Box::new(events);
+        let channel = oneshot::async_channel();
+        let operation = AppendOperation::new(events, condition, None, channel.0).into();
+
+        super::asynchronous::AppendFuture::new(
+            Arc::clone(&self.notifier),
+            Some(operation),
+            channel.1,
+            self.sender.clone(),
+        )
+    }
 }
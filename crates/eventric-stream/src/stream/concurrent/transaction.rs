@@ -0,0 +1,104 @@
+//! The [`Transaction`] — the handle a closure passed to
+//! [`Proxy::transact`](super::proxy::Proxy::transact) runs with on the
+//! [`Owner`](super::owner::Owner)'s writer thread.
+
+use error_stack::Report;
+use fancy_constructor::new;
+
+use super::notifier::Notifier;
+use crate::{
+    error::Error,
+    event::Event,
+    stream::{
+        Durability,
+        Position,
+        Writer,
+        operate::{
+            Condition,
+            append::{
+                Append,
+                AppendReceipt,
+            },
+            explain::{
+                Analysis,
+                Plan,
+            },
+            select::{
+                Select,
+                SelectIter,
+            },
+        },
+    },
+};
+
+// =================================================================================================
+// Transaction
+// =================================================================================================
+
+/// Reads and writes against the writer's current head, from a closure run on
+/// the writer thread by [`Proxy::transact`](super::proxy::Proxy::transact).
+/// Nothing else is written while the closure runs, so an append conditioned on
+/// what it selected cannot conflict: a read-modify-write need not be retried.
+///
+/// Each append commits as it is made (and is seen by any later select in the
+/// same transaction); a transaction serialises its operations, it does not
+/// make them atomic.
+#[derive(new, Debug)]
+#[new(const_fn, vis(pub(crate)))]
+pub struct Transaction<'a> {
+    notifier: &'a Notifier,
+    writer: &'a mut Writer,
+}
+
+impl Transaction<'_> {
+    /// The position the next append will be made at: the number of events
+    /// committed so far.
+    #[must_use]
+    pub fn head(&self) -> Position {
+        self.writer.next()
+    }
+}
+
+impl Append for Transaction<'_> {
+    fn append<E>(&mut self, events: E, condition: Condition) -> Result<AppendReceipt, Report<Error>>
+    where
+        E: IntoIterator<Item = Event<(), String>>,
+        E::IntoIter: Send + 'static,
+    {
+        self.writer
+            .append(events, condition)
+            .inspect(|_| self.notifier.notify())
+    }
+
+    fn append_durable<E>(
+        &mut self,
+        events: E,
+        condition: Condition,
+        durability: Durability,
+    ) -> Result<AppendReceipt, Report<Error>>
+    where
+        E: IntoIterator<Item = Event<(), String>>,
+        E::IntoIter: Send + 'static,
+    {
+        self.writer
+            .append_durable(events, condition, durability)
+            .inspect(|_| self.notifier.notify())
+    }
+}
+
+impl Select for Transaction<'_> {
+    fn select(&self, condition: Condition) -> SelectIter {
+        self.writer
+            .store()
+            .select(condition)
+            .observed(self.writer.observers())
+    }
+
+    fn explain(&self, condition: Condition) -> Plan {
+        Select::explain(self.writer.store(), condition)
+    }
+
+    fn analyze(&self, condition: Condition) -> Result<Analysis, Report<Error>> {
+        Select::analyze(self.writer.store(), condition)
+    }
+}
@@ -0,0 +1,1129 @@
+//! A [`Database`] shared by several named streams: one storage backend (and so
+//! one cache, journal and background flusher) holding many independent
+//! streams, each with its own keyspaces, position cursor and `Writer`.
+
+use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+    },
+    fs::{
+        self,
+        File,
+        OpenOptions,
+        TryLockError,
+    },
+    io::{
+        ErrorKind,
+        Read as _,
+        Write as _,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+    process,
+    sync::{
+        Arc,
+        Mutex,
+        MutexGuard,
+        Weak,
+        atomic::{
+            AtomicBool,
+            Ordering,
+        },
+    },
+    time::Duration,
+};
+
+use derive_more::Debug;
+use error_stack::{
+    Report,
+    ResultExt as _,
+};
+use fancy_constructor::new;
+
+use crate::{
+    error::{
+        Error,
+        Locked,
+        Result,
+    },
+    stream::{
+        Backend,
+        Durability,
+        Position,
+        Reader,
+        Stream,
+        clock::{
+            Clock,
+            System,
+        },
+        flusher::Flusher,
+        observe::{
+            Observer,
+            Observers,
+        },
+        store::{
+            Store,
+            storage::{
+                Memory,
+                Namespaced,
+                Storage,
+            },
+        },
+    },
+    utils::validation::{
+        self,
+        NoControlCharacters,
+        NoPrecedingWhiteSpace,
+        NoTrailingWhiteSpace,
+        NotEmpty,
+    },
+};
+
+// =================================================================================================
+// Database
+// =================================================================================================
+
+// Constants
+
+static MIN_JOURNAL_SIZE: u64 = 64 * 1024 * 1024;
+
+// A stream's keyspaces are named `<name>.<keyspace>`, and fjall caps keyspace
+// names at 255 bytes: this leaves room for the longest keyspace name.
+static MAX_NAME_LEN: usize = 240;
+
+// The lock file in a database's directory (beside fjall's own files).
+static LOCK_FILE: &str = "eventric.lock";
+
+// The marker fjall writes in a database's directory, so a directory without one
+// holds no database.
+static VERSION_FILE: &str = "version";
+
+// Attached to the refusal of a read-only open of a database another process
+// holds, which fjall (holding its directory exclusively) does not allow.
+static READ_ONLY_LOCKED: &str = "a database open in another process cannot be opened here, even \
+                                 read-only: read it through that process instead (by serving it \
+                                 with eventric-server, or replicating it to a follower)";
+
+// The database directories open in this process, by canonical path, so that a
+// further open of one shares its storage rather than opening it again (which
+// fjall, holding its own lock on the directory, would refuse).
+static DIRECTORIES: Mutex<BTreeMap<PathBuf, Weak<Directory>>> = Mutex::new(BTreeMap::new());
+
+// -------------------------------------------------------------------------------------------------
+
+// Builder
+
+/// Configures and opens a [`Database`] at a given path. Obtained from
+/// [`Database::builder`].
+#[derive(new, Debug)]
+#[new(vis(pub(crate)))]
+pub struct Builder<P>
+where
+    P: AsRef<Path>,
+{
+    #[new(default)]
+    backend: Option<Backend>,
+    #[new(default)]
+    cache_size: Option<u64>,
+    #[new(default)]
+    #[debug("Clock")]
+    clock: Option<Arc<dyn Clock>>,
+    #[new(default)]
+    durability: Option<Durability>,
+    #[new(default)]
+    flush_interval: Option<Duration>,
+    #[new(default)]
+    journal_size: Option<u64>,
+    #[new(default)]
+    observers: Observers,
+    path: P,
+    #[new(default)]
+    read_only: Option<bool>,
+    #[new(default)]
+    temporary: Option<bool>,
+    #[new(default)]
+    unsafe_admin: Option<bool>,
+}
+
+impl<P> Builder<P>
+where
+    P: AsRef<Path>,
+{
+    /// Open the database (with no stream opened in it yet), locking its
+    /// directory for as long as it (or any stream opened in it) is open. A
+    /// database open in another process, or open for writing through another
+    /// `Database` in this one, is refused with the [`Locked`] marker attached.
+    ///
+    /// A database already open in this process is shared rather than opened
+    /// again, so its cache size, journal size and temporariness must be asked
+    /// for as it was opened with them: an open asking for others is refused.
+    ///
+    /// A [`read_only`](Builder::read_only) open creates nothing on disk (fjall's
+    /// own recovery of the database aside): no directory, lock file, lock
+    /// holder or keyspace. The directory must already hold a database, and
+    /// the open cannot be [`temporary`](Builder::temporary) unless it shares a
+    /// temporary database already open in this process.
+    ///
+    /// Readers are opened alongside a writer in this process only. A database
+    /// open in another process cannot be opened here at all, read-only or not:
+    /// fjall holds its directory exclusively and has no read-only mode, so
+    /// another process reads the database through the one holding it, such as
+    /// by serving it with eventric-server or replicating it to a
+    /// [`Follower`](crate::stream::replicate::Follower).
+    pub fn open(self) -> Result<Database> {
+        let read_only = self.read_only.unwrap_or_default();
+        let (lock, storage) = match self.backend.unwrap_or_default() {
+            Backend::Disk => {
+                let lock = Lock::acquire(self.path.as_ref(), self.settings(), !read_only, || {
+                    Ok(Arc::new(self.fjall()?))
+                })
+                .map_err(|report| match report.downcast_ref::<Locked>() {
+                    Some(_) if read_only => report.attach(READ_ONLY_LOCKED),
+                    _ => report,
+                })?;
+                let storage = Arc::clone(&lock.directory.storage);
+
+                (Some(Arc::new(lock)), storage)
+            }
+            Backend::Memory => (None, Arc::new(Memory::default()) as Arc<dyn Storage>),
+        };
+
+        let clock = self.clock.unwrap_or_else(|| Arc::new(System));
+        let durability = self.durability.unwrap_or_default();
+        let flusher = self
+            .flush_interval
+            .map(|interval| Arc::new(Flusher::spawn(Arc::clone(&storage), interval)));
+
+        Ok(Database::new(
+            clock,
+            durability,
+            flusher,
+            lock,
+            self.observers,
+            read_only,
+            storage,
+            self.unsafe_admin.unwrap_or_default(),
+        ))
+    }
+
+    fn settings(&self) -> Settings {
+        Settings {
+            cache_size: self.cache_size,
+            journal_size: self.journal_size,
+            temporary: self.temporary.unwrap_or_default(),
+        }
+    }
+
+    fn fjall(&self) -> Result<fjall::Database> {
+        let mut builder = fjall::Database::builder(self.path.as_ref())
+            .temporary(self.temporary.unwrap_or_default());
+
+        if let Some(cache_size) = self.cache_size {
+            builder = builder.cache_size(cache_size);
+        }
+
+        // fjall asserts the journal size minimum, so check it here to fail the
+        // open gracefully rather than panic.
+        if let Some(journal_size) = self.journal_size {
+            if journal_size < MIN_JOURNAL_SIZE {
+                return Err(Report::new(Error).attach(format!(
+                    "journal size must be at least {MIN_JOURNAL_SIZE} bytes"
+                )));
+            }
+
+            builder = builder.max_journaling_size(journal_size);
+        }
+
+        builder.open().map_err(|err| {
+            let locked = matches!(err, fjall::Error::Locked);
+            let report = Report::new(err).change_context(Error);
+
+            if locked {
+                report.attach(Locked).attach("database is locked")
+            } else {
+                report.attach("failed to open database")
+            }
+        })
+    }
+}
+
+impl<P> Builder<P>
+where
+    P: AsRef<Path>,
+{
+    /// The [`Backend`] the database is stored in. Defaults to
+    /// [`Backend::Disk`].
+    #[must_use]
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// The capacity of the database's block cache, in bytes, shared by every
+    /// stream in it. Defaults to fjall's default (32 MiB).
+    #[must_use]
+    pub fn cache_size(mut self, cache_size: u64) -> Self {
+        self.cache_size = Some(cache_size);
+        self
+    }
+
+    /// The [`Clock`] events appended to every stream in the database are
+    /// timestamped with. Defaults to the [`System`] clock.
+    #[must_use]
+    pub fn clock<C>(mut self, clock: C) -> Self
+    where
+        C: Clock + 'static,
+    {
+        self.clock = Some(Arc::new(clock));
+        self
+    }
+
+    /// How durably each append's commit is persisted before the append
+    /// returns, unless overridden per append with
+    /// [`append_durable`](crate::stream::operate::append::Append::append_durable).
+    /// Defaults to [`Durability::Buffer`].
+    #[must_use]
+    pub fn durability(mut self, durability: Durability) -> Self {
+        self.durability = Some(durability);
+        self
+    }
+
+    /// Fsync the journal in the background every `interval`, so buffered
+    /// commits become durable within one interval (the interval alternative
+    /// to [`Durability::Sync`] on every commit). Defaults to no background
+    /// flushing.
+    #[must_use]
+    pub fn flush_interval(mut self, interval: Duration) -> Self {
+        self.flush_interval = Some(interval);
+        self
+    }
+
+    /// The maximum total size of the journal, in bytes, beyond which
+    /// in-memory writes are flushed to disk so older journal files can be
+    /// dropped. At least 64 MiB; defaults to fjall's default (512 MiB).
+    #[must_use]
+    pub fn journal_size(mut self, journal_size: u64) -> Self {
+        self.journal_size = Some(journal_size);
+        self
+    }
+
+    /// Register an [`Observer`], called with an
+    /// [`Observation`](crate::stream::observe::Observation) for every append
+    /// and select through the streams opened in the database and the
+    /// handles split from them. May be called more than once; observers are
+    /// called in registration order.
+    #[must_use]
+    pub fn observer<O>(mut self, observer: O) -> Self
+    where
+        O: Observer + 'static,
+    {
+        self.observers.push(Arc::new(observer));
+        self
+    }
+
+    /// Whether the database is opened for reading only: its streams may be
+    /// opened as [`Reader`]s (with [`reader`](Database::reader)) but not for
+    /// writing, it creates nothing on disk, and it does not keep a
+    /// writer in this process out of the database. Defaults to `false`.
+    #[must_use]
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = Some(read_only);
+        self
+    }
+
+    /// Whether the database is temporary (its on-disk data is cleaned up on
+    /// drop). Defaults to `false`.
+    #[must_use]
+    pub fn temporary(mut self, temporary: bool) -> Self {
+        self.temporary = Some(temporary);
+        self
+    }
+
+    /// Whether the streams opened in the database allow the administrative
+    /// operations which destroy committed events, such as
+    /// [`Writer::truncate_after`](crate::stream::Writer::truncate_after) —
+    /// for test and staging environments, never production. Defaults to
+    /// `false`, when they are refused.
+    #[must_use]
+    pub fn unsafe_admin(mut self, unsafe_admin: bool) -> Self {
+        self.unsafe_admin = Some(unsafe_admin);
+        self
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Database
+
+/// One storage backend holding any number of named streams. Each stream opened
+/// with [`stream`](Database::stream) has its own keyspaces (prefixed with its
+/// name), position cursor and `Writer`, while the cache, journal and flusher
+/// are shared. Cheap to clone; the storage closes with the last clone and the
+/// last stream opened from it.
+#[derive(new, Clone, Debug)]
+#[new(vis())]
+pub struct Database {
+    #[debug("Clock")]
+    clock: Arc<dyn Clock>,
+    durability: Durability,
+    flusher: Option<Arc<Flusher>>,
+    lock: Option<Arc<Lock>>,
+    #[new(default)]
+    #[debug("Names")]
+    names: Arc<Mutex<BTreeSet<Option<String>>>>,
+    observers: Observers,
+    read_only: bool,
+    #[debug("Storage")]
+    storage: Arc<dyn Storage>,
+    unsafe_admin: bool,
+}
+
+impl Database {
+    /// Begin opening a database at `path`. Configure with the settings on
+    /// [`Builder`] and finish with [`open`](Builder::open).
+    pub fn builder<P>(path: P) -> Builder<P>
+    where
+        P: AsRef<Path>,
+    {
+        Builder::new(path)
+    }
+
+    /// Open the stream named `name`, creating it if it does not exist and
+    /// recovering its `next` position cursor if it does. Names must be
+    /// non-empty, free of control characters and surrounding whitespace, and
+    /// at most 240 bytes.
+    ///
+    /// A stream may be open only once at a time (its `Writer` is the only
+    /// writer): opening a name which is still open — through a `Stream`,
+    /// `Writer`, or an `Owner` — is an error, as is opening any stream in a
+    /// [`read_only`](Builder::read_only) database. Each opening advances the
+    /// stream's writer [`epoch`](Stream::epoch).
+    pub fn stream<N>(&self, name: N) -> Result<Stream>
+    where
+        N: Into<String>,
+    {
+        self.open(Some(name.into()))
+    }
+
+    /// Open the stream named `name` for reading only, as a [`Reader`]: nothing
+    /// is written to it, its writer epoch included, so any number of readers
+    /// may be opened alongside its writer (and each other). Names are
+    /// validated as for [`stream`](Database::stream).
+    pub fn reader<N>(&self, name: N) -> Result<Reader>
+    where
+        N: Into<String>,
+    {
+        self.read(Some(name.into()))
+    }
+}
+
+impl Database {
+    /// Open the stream `name`, or the unnamed stream (whose keyspaces are not
+    /// prefixed, as for a stream opened with no name) if `None`.
+    pub(crate) fn open(&self, name: Option<String>) -> Result<Stream> {
+        if self.read_only {
+            return Err(Report::new(Error).attach("database is open read-only"));
+        }
+
+        let mut claim = Claim::acquire(&self.names, name.clone(), self.lock.clone())?;
+        let storage = self.storage(name)?;
+        let store = Store::open(storage.as_ref())?;
+        let next = store.len().map(Position::new)?;
+
+        claim.epoch = store.meta.advance(storage.as_ref())?;
+
+        Ok(Stream::new(
+            Arc::new(claim),
+            Arc::clone(&self.clock),
+            self.durability,
+            self.flusher.clone(),
+            next,
+            self.observers.clone(),
+            storage,
+            store,
+            self.unsafe_admin,
+        ))
+    }
+
+    /// Open the unnamed stream as a fork of `base` at `position`, stamped by
+    /// `clock` and observed by `observers` (as the stream it was forked from
+    /// is): its store layered over `base`'s, with its `next` cursor at
+    /// `position`.
+    pub(crate) fn fork(
+        &self,
+        base: &Store,
+        position: Position,
+        clock: Arc<dyn Clock>,
+        observers: Observers,
+    ) -> Result<Stream> {
+        let mut claim = Claim::acquire(&self.names, None, self.lock.clone())?;
+        let storage = self.storage(None)?;
+        let store = base.fork(storage.as_ref(), position)?;
+
+        claim.epoch = store.meta.advance(storage.as_ref())?;
+
+        Ok(Stream::new(
+            Arc::new(claim),
+            clock,
+            self.durability,
+            self.flusher.clone(),
+            position,
+            observers,
+            storage,
+            store,
+            self.unsafe_admin,
+        ))
+    }
+
+    /// Open the stream `name` (or the unnamed stream) for reading only.
+    pub(crate) fn read(&self, name: Option<String>) -> Result<Reader> {
+        let storage = self.storage(name)?;
+        let store = Store::read(storage.as_ref())?;
+
+        Ok(Reader::new(
+            self.lock.clone(),
+            self.observers.clone(),
+            store,
+        ))
+    }
+
+    fn storage(&self, name: Option<String>) -> Result<Arc<dyn Storage>> {
+        match name {
+            Some(name) => {
+                validate(&name)?;
+
+                Ok(Arc::new(Namespaced::new(name, Arc::clone(&self.storage))))
+            }
+            None => Ok(Arc::clone(&self.storage)),
+        }
+    }
+}
+
+fn validate(name: &String) -> Result<()> {
+    validation::validate(name, "stream name", &[
+        &NotEmpty,
+        &NoControlCharacters,
+        &NoPrecedingWhiteSpace,
+        &NoTrailingWhiteSpace,
+    ])
+    .change_context(Error)?;
+
+    if name.len() > MAX_NAME_LEN {
+        return Err(
+            Report::new(Error).attach(format!("stream name must be at most {MAX_NAME_LEN} bytes"))
+        );
+    }
+
+    Ok(())
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Claim
+
+/// The claim an open stream holds on its name in a [`Database`], so the name
+/// cannot be opened a second time (with a second writer) while it is held,
+/// along with the writer epoch it was opened at and the database's lock.
+/// Released when the last handle holding it is dropped.
+#[derive(Debug)]
+pub(crate) struct Claim {
+    epoch: u64,
+    lock: Option<Arc<Lock>>,
+    #[debug("Names")]
+    names: Arc<Mutex<BTreeSet<Option<String>>>>,
+    name: Option<String>,
+}
+
+impl Claim {
+    fn acquire(
+        names: &Arc<Mutex<BTreeSet<Option<String>>>>,
+        name: Option<String>,
+        lock: Option<Arc<Lock>>,
+    ) -> Result<Self> {
+        if !guard(names).insert(name.clone()) {
+            return Err(Report::new(Error).attach(format!(
+                "stream {} is already open",
+                name.as_deref().unwrap_or("(unnamed)")
+            )));
+        }
+
+        Ok(Self {
+            epoch: 0,
+            lock,
+            names: Arc::clone(names),
+            name,
+        })
+    }
+}
+
+impl Claim {
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn lock(&self) -> Option<Arc<Lock>> {
+        self.lock.clone()
+    }
+}
+
+impl Drop for Claim {
+    fn drop(&mut self) {
+        guard(&self.names).remove(&self.name);
+    }
+}
+
+fn guard(names: &Mutex<BTreeSet<Option<String>>>) -> MutexGuard<'_, BTreeSet<Option<String>>> {
+    match names.lock() {
+        Ok(names) => names,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Lock
+
+/// A database's hold on its directory, kept while the database (or anything
+/// opened from it) is open. The directory itself is locked once per process,
+/// while any database holds it; a database opened for writing also holds its
+/// single writer's place, so no other database in this process opens it for
+/// writing too.
+#[derive(Debug)]
+pub(crate) struct Lock {
+    directory: Arc<Directory>,
+    writable: bool,
+}
+
+impl Lock {
+    fn acquire<O>(path: &Path, settings: Settings, writable: bool, open: O) -> Result<Self>
+    where
+        O: FnOnce() -> Result<Arc<dyn Storage>>,
+    {
+        if writable {
+            fs::create_dir_all(path)
+                .change_context(Error)
+                .attach_with(|| format!("failed to create {}", path.display()))?;
+        }
+
+        let path = fs::canonicalize(path)
+            .change_context(Error)
+            .attach_with(|| format!("failed to resolve {}", path.display()))?;
+
+        // The registry is released before the directory could be dropped (on
+        // a refusal below), as dropping it takes the registry's lock.
+        let directory = {
+            let mut directories = directories();
+
+            if let Some(directory) = directories.get(&path).and_then(Weak::upgrade) {
+                directory
+            } else {
+                let directory = Arc::new(Directory::lock(path.clone(), settings, writable, open)?);
+
+                directories.insert(path, Arc::downgrade(&directory));
+                directory
+            }
+        };
+
+        if let Some(setting) = directory.settings.conflict(&settings) {
+            return Err(Report::new(Error).attach(format!(
+                "{} is open in this process with a different {setting}",
+                directory.path.display()
+            )));
+        }
+
+        if writable && directory.writer.swap(true, Ordering::AcqRel) {
+            return Err(Report::new(Error).attach(Locked).attach(format!(
+                "{} is locked by process {}",
+                directory.path.display(),
+                process::id()
+            )));
+        }
+
+        let lock = Self {
+            directory,
+            writable,
+        };
+
+        if writable {
+            lock.directory.record()?;
+        }
+
+        Ok(lock)
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        if self.writable {
+            self.directory.writer.store(false, Ordering::Release);
+        }
+    }
+}
+
+/// The settings a database directory's storage is opened with, which every
+/// database sharing it in this process must ask for too.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Settings {
+    cache_size: Option<u64>,
+    journal_size: Option<u64>,
+    temporary: bool,
+}
+
+impl Settings {
+    /// The name of the first setting `other` asks for differently, if any.
+    fn conflict(&self, other: &Self) -> Option<&'static str> {
+        if self.cache_size != other.cache_size {
+            Some("cache size")
+        } else if self.journal_size != other.journal_size {
+            Some("journal size")
+        } else if self.temporary != other.temporary {
+            Some("temporariness")
+        } else {
+            None
+        }
+    }
+}
+
+/// A database directory open in this process: the exclusive lock on its lock
+/// file, held so no other process opens it too, and the storage opened in it
+/// (with the settings it was opened with), shared by every database opened at
+/// the directory here. While it is open for writing, the lock file records
+/// the holder's process id, so a refused open can say which process holds it.
+/// The OS releases the lock when the directory is closed, or however the
+/// process ends.
+///
+/// A directory opened read-only creates nothing: it locks the lock file only
+/// if a writer has left one (fjall locks the directory regardless), and it
+/// must already hold a database.
+#[derive(Debug)]
+struct Directory {
+    file: Option<File>,
+    path: PathBuf,
+    recorded: AtomicBool,
+    settings: Settings,
+    #[debug("Storage")]
+    storage: Arc<dyn Storage>,
+    writer: AtomicBool,
+}
+
+impl Directory {
+    fn lock<O>(path: PathBuf, settings: Settings, writable: bool, open: O) -> Result<Self>
+    where
+        O: FnOnce() -> Result<Arc<dyn Storage>>,
+    {
+        if !writable {
+            if !path.join(VERSION_FILE).exists() {
+                return Err(
+                    Report::new(Error).attach(format!("{} holds no database", path.display()))
+                );
+            }
+
+            // A temporary database is deleted when it closes, which a
+            // read-only open must not do to a database it opened itself.
+            if settings.temporary {
+                return Err(Report::new(Error)
+                    .attach("a read-only database is temporary only if it shares one"));
+            }
+        }
+
+        let file = match OpenOptions::new()
+            .create(writable)
+            .read(true)
+            .write(true)
+            .truncate(false)
+            .open(path.join(LOCK_FILE))
+        {
+            Ok(file) => Some(file),
+            Err(err) if !writable && err.kind() == ErrorKind::NotFound => None,
+            Err(err) => {
+                return Err(Report::new(err)
+                    .change_context(Error)
+                    .attach("failed to open lock file"));
+            }
+        };
+
+        if let Some(mut file) = file.as_ref() {
+            match file.try_lock() {
+                Ok(()) => {}
+                Err(TryLockError::WouldBlock) => {
+                    let mut holder = String::new();
+
+                    file.read_to_string(&mut holder).ok();
+
+                    let locked = match holder.trim() {
+                        "" => format!("{} is locked", path.display()),
+                        holder => format!("{} is locked by process {holder}", path.display()),
+                    };
+
+                    return Err(Report::new(Error).attach(Locked).attach(locked));
+                }
+                Err(TryLockError::Error(err)) => {
+                    return Err(Report::new(err)
+                        .change_context(Error)
+                        .attach("failed to lock lock file"));
+                }
+            }
+        }
+
+        let storage = open()?;
+
+        Ok(Self {
+            file,
+            path,
+            recorded: AtomicBool::new(false),
+            settings,
+            storage,
+            writer: AtomicBool::new(false),
+        })
+    }
+
+    /// Record this process as the lock's holder in the lock file, once a
+    /// writer opens the directory (and if not already recorded).
+    fn record(&self) -> Result<()> {
+        let Some(mut file) = self.file.as_ref() else {
+            return Ok(());
+        };
+
+        if self.recorded.swap(true, Ordering::AcqRel) {
+            return Ok(());
+        }
+
+        file.set_len(0)
+            .and_then(|()| write!(file, "{}", process::id()))
+            .change_context(Error)
+            .attach("failed to record lock holder")
+    }
+}
+
+impl Drop for Directory {
+    fn drop(&mut self) {
+        // The holder recorded is cleared before the lock is released, so a
+        // later read-only holder (which records nothing) is not named as it.
+        if let Some(file) = &self.file
+            && self.recorded.load(Ordering::Acquire)
+        {
+            file.set_len(0).ok();
+        }
+
+        let mut directories = directories();
+
+        // The directory may have been opened again (once this one could no
+        // longer be shared) before this drop took the registry's lock.
+        if directories
+            .get(&self.path)
+            .is_some_and(|directory| directory.strong_count() == 0)
+        {
+            directories.remove(&self.path);
+        }
+    }
+}
+
+fn directories() -> MutexGuard<'static, BTreeMap<PathBuf, Weak<Directory>>> {
+    match DIRECTORIES.lock() {
+        Ok(directories) => directories,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+// =================================================================================================
+// Tests
+// =================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeSet,
+        fs,
+        process,
+    };
+
+    use super::Database;
+    use crate::{
+        error::Locked,
+        event::{
+            Data,
+            Event,
+            Facets,
+            Name,
+            Tag,
+            Type,
+            Version,
+        },
+        stream::{
+            Backend,
+            Position,
+            Stream,
+            operate::{
+                Condition,
+                Selection,
+                append::Append as _,
+                select::{
+                    Select as _,
+                    Selector,
+                    TypeSelector,
+                },
+            },
+        },
+        utils::temp_path,
+    };
+
+    fn event(identifier: &str, tags: &[&str]) -> Event<(), String> {
+        let ty = Type::new(Name::new(identifier).unwrap(), Version::new(0));
+        let tags = tags
+            .iter()
+            .map(|tag| Tag::new(*tag).unwrap())
+            .collect::<BTreeSet<_>>();
+
+        Event::new(
+            Data::new(b"payload".to_vec()).unwrap(),
+            Facets::new(ty, tags),
+            (),
+        )
+    }
+
+    fn invoiced() -> Condition {
+        Condition::new().selections([Selection::new([Selector::types([TypeSelector::new(
+            "Invoiced",
+        )
+        .unwrap()])])])
+    }
+
+    // Streams in one database are independent: each has its own position
+    // cursor, sees only its own events, and is checked (DCB) only against them.
+    #[test]
+    fn named_streams_are_independent() {
+        let database = Database::builder(temp_path())
+            .temporary(true)
+            .open()
+            .unwrap();
+
+        let mut billing = database.stream("billing").unwrap();
+        let mut shipping = database.stream("shipping").unwrap();
+
+        billing
+            .append(
+                vec![event("Invoiced", &[]), event("Invoiced", &[])],
+                Condition::new(),
+            )
+            .unwrap();
+
+        let receipt = shipping
+            .append(vec![event("Invoiced", &[])], invoiced().from(Position::MIN))
+            .unwrap();
+
+        assert_eq!(receipt.last(), Position::new(0));
+        assert_eq!(billing.len(), 2);
+        assert_eq!(shipping.len(), 1);
+        assert_eq!(billing.select(invoiced()).count(), 2);
+        assert_eq!(shipping.select(Condition::new()).count(), 1);
+    }
+
+    // A named stream keeps its events across a reopen of its database, apart
+    // from the unnamed stream at the same path.
+    #[test]
+    fn named_streams_persist_across_reopen() {
+        let path = temp_path();
+
+        {
+            let mut billing = Stream::builder(&path).name("billing").open().unwrap();
+
+            billing
+                .append(vec![event("Invoiced", &[])], Condition::new())
+                .unwrap();
+        }
+
+        let database = Database::builder(&path).temporary(true).open().unwrap();
+
+        assert_eq!(database.stream("billing").unwrap().len(), 1);
+        assert_eq!(database.open(None).unwrap().len(), 0);
+    }
+
+    // A stream cannot be opened twice at once, even through a split `Writer`,
+    // but can be once every handle to it has gone; and names are validated.
+    #[test]
+    fn a_stream_is_open_at_most_once() {
+        let database = Database::builder(temp_path())
+            .backend(Backend::Memory)
+            .open()
+            .unwrap();
+
+        let (_reader, writer) = database.stream("billing").unwrap().split();
+
+        assert!(database.stream("billing").is_err());
+
+        drop(writer);
+
+        assert!(database.stream("billing").is_ok());
+        assert!(database.stream("").is_err());
+        assert!(database.stream(" billing").is_err());
+        assert!(database.stream("b".repeat(241)).is_err());
+    }
+
+    // A database's directory is locked while anything opened from it is, so
+    // it cannot be opened twice (nor by a second process); the refusal says
+    // which process holds it.
+    #[test]
+    fn a_database_is_locked_while_open() {
+        let path = temp_path();
+        let stream = Stream::builder(&path).open().unwrap();
+        let (reader, writer) = stream.split();
+
+        drop(writer);
+
+        let locked = Database::builder(&path).open().unwrap_err();
+
+        assert!(locked.downcast_ref::<Locked>().is_some());
+        assert!(format!("{locked:?}").contains(&format!("process {}", process::id())));
+
+        drop(reader);
+
+        assert!(Database::builder(&path).temporary(true).open().is_ok());
+    }
+
+    // Each opening for writing advances the stream's epoch; reading it does
+    // not, and a reader opened alongside its writer sees its appends.
+    #[test]
+    fn writer_epochs_advance_and_readers_leave_them() {
+        let path = temp_path();
+
+        for epoch in 1..=2 {
+            let stream = Stream::builder(&path).open().unwrap();
+
+            assert_eq!(stream.epoch(), epoch);
+            assert_eq!(stream.split().1.epoch(), epoch);
+        }
+
+        assert_eq!(
+            Stream::builder(&path)
+                .open_reader()
+                .unwrap()
+                .stats()
+                .unwrap()
+                .events(),
+            0
+        );
+
+        let database = Database::builder(&path).temporary(true).open().unwrap();
+        let mut stream = database.open(None).unwrap();
+        assert_eq!(stream.epoch(), 3);
+
+        let mut billing = database.stream("billing").unwrap();
+        let reader = database.reader("billing").unwrap();
+
+        billing
+            .append(vec![event("Invoiced", &[])], Condition::new())
+            .unwrap();
+        stream
+            .append(vec![event("Invoiced", &[])], Condition::new())
+            .unwrap();
+
+        assert_eq!(billing.epoch(), 1);
+        assert_eq!(reader.select(invoiced()).count(), 1);
+    }
+
+    // A read-only database opens alongside the database its writer holds (in
+    // either order), sharing its storage: its readers see the writer's later
+    // appends, it opens no stream for writing, and a second writable open is
+    // still refused. An open asking for settings other than those the shared
+    // storage was opened with is refused, rather than given them silently.
+    #[test]
+    fn read_only_databases_open_alongside_a_writer() {
+        let path = temp_path();
+        let mut stream = Stream::builder(&path).temporary(true).open().unwrap();
+
+        let database = Database::builder(&path)
+            .read_only(true)
+            .temporary(true)
+            .open()
+            .unwrap();
+        let reader = database.read(None).unwrap();
+        let other = Stream::builder(&path)
+            .temporary(true)
+            .open_reader()
+            .unwrap();
+
+        stream
+            .append(vec![event("Invoiced", &[])], Condition::new())
+            .unwrap();
+
+        assert_eq!(reader.select(invoiced()).count(), 1);
+        assert_eq!(other.select(invoiced()).count(), 1);
+        assert!(database.stream("billing").is_err());
+        assert!(
+            Database::builder(&path)
+                .temporary(true)
+                .open()
+                .unwrap_err()
+                .downcast_ref::<Locked>()
+                .is_some()
+        );
+        assert!(
+            Database::builder(&path)
+                .read_only(true)
+                .temporary(true)
+                .cache_size(1024 * 1024)
+                .open()
+                .is_err()
+        );
+
+        drop(stream);
+
+        let conflict = Stream::builder(&path).open().unwrap_err();
+
+        assert!(format!("{conflict:?}").contains("different temporariness"));
+
+        let mut stream = Stream::builder(&path).temporary(true).open().unwrap();
+
+        assert_eq!(stream.epoch(), 2);
+
+        stream
+            .append(vec![event("Invoiced", &[])], Condition::new())
+            .unwrap();
+
+        assert_eq!(reader.select(invoiced()).count(), 2);
+    }
+
+    // A read-only open creates nothing on disk: not the directory, nor a
+    // stream's keyspaces, nor a lock holder. Nor does it delete the database
+    // when dropped, as a temporary database it opened itself would be.
+    #[test]
+    fn read_only_databases_create_nothing() {
+        let path = temp_path();
+
+        assert!(Database::builder(&path).read_only(true).open().is_err());
+        assert!(!path.exists());
+
+        Stream::builder(&path).open().unwrap();
+
+        let lock = path.join("eventric.lock");
+
+        assert_eq!(fs::read_to_string(&lock).unwrap(), "");
+
+        {
+            let database = Database::builder(&path).read_only(true).open().unwrap();
+
+            assert!(database.read(None).is_ok());
+            assert!(database.reader("billing").is_err());
+            assert_eq!(fs::read_to_string(&lock).unwrap(), "");
+        }
+
+        assert!(
+            Database::builder(&path)
+                .read_only(true)
+                .temporary(true)
+                .open()
+                .is_err()
+        );
+
+        let database = fjall::Database::builder(&path)
+            .temporary(true)
+            .open()
+            .unwrap();
+
+        assert!(database.keyspace_exists("events"));
+        assert!(!database.keyspace_exists("billing.events"));
+    }
+}
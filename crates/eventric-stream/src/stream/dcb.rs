@@ -0,0 +1,396 @@
+//! A compatibility layer for the [DCB](https://dcb.events) reference API: an
+//! [`EventStore`] over a [`Stream`] with the specification's `read(query,
+//! options)` and `append(events, condition)`, in its vocabulary — string
+//! event types and tags, [`Query`]s of [`QueryItem`]s, [`ReadOptions`] and an
+//! [`AppendCondition`] of events which must not match — and with its
+//! semantics where they differ from the stream's own:
+//!
+//! - a query item matches an event whose type is any of its types (if it has
+//!   any) and which carries all of its tags (if it has any), so an item with
+//!   neither matches every event; a query with no items matches none
+//! - a read's `from` is inclusive, and when reading backwards bounds the
+//!   events from above rather than below
+//! - an append condition's `after` is exclusive: only events after that
+//!   position can fail the append, where a stream's `Condition` starts at its
+//!   position
+//!
+//! DCB events have no versions: they are appended at version `0`, and read at
+//! any version. As on the stream, event data must not be empty, and an
+//! event's tags are a set: they are read back sorted, without duplicates.
+
+use std::iter;
+
+use error_stack::Report;
+use fancy_constructor::new;
+
+use crate::{
+    error::{
+        Conflict,
+        Error,
+        Result,
+    },
+    event::{
+        self,
+        Data,
+        Facets,
+        Name,
+        Tag,
+        Type,
+        Version,
+    },
+    stream::{
+        Metadata,
+        Position,
+        Stream,
+        operate::{
+            Condition,
+            Selection,
+            append::Append as _,
+            select::{
+                Select as _,
+                Selector,
+                TypeSelector,
+            },
+        },
+    },
+};
+
+// =================================================================================================
+// DCB
+// =================================================================================================
+
+// Event Store
+
+/// The DCB reference API over a [`Stream`].
+#[derive(new, Debug)]
+#[new(const_fn)]
+pub struct EventStore {
+    stream: Stream,
+}
+
+impl EventStore {
+    /// The events matching `query`, in position order (or reverse position
+    /// order, reading backwards), as limited by `options`.
+    pub fn read(
+        &self,
+        query: &Query,
+        options: ReadOptions,
+    ) -> Result<impl Iterator<Item = Result<SequencedEvent>> + '_> {
+        let ReadOptions {
+            backwards,
+            from,
+            limit,
+        } = options;
+
+        let events: Box<dyn Iterator<Item = _>> = match (query.condition()?, backwards) {
+            (None, _) => Box::new(iter::empty()),
+            (Some(condition), false) => {
+                let condition = match from {
+                    Some(from) => condition.from(from),
+                    None => condition,
+                };
+
+                Box::new(self.stream.select(condition))
+            }
+            (Some(condition), true) => {
+                Box::new(
+                    self.stream
+                        .select(condition)
+                        .rev()
+                        .skip_while(move |event| {
+                            // Events after `from` precede it when reading backwards.
+                            match (event, from) {
+                                (Ok(event), Some(from)) => event.event.meta().position() > from,
+                                _ => false,
+                            }
+                        }),
+                )
+            }
+        };
+
+        Ok(events
+            .take(limit.unwrap_or(usize::MAX))
+            .map(|event| event.and_then(|event| self.sequenced(&event.event))))
+    }
+
+    /// Append `events`, atomically, failing with a [`Conflict`] if `condition`
+    /// is given and an event matching it exists after its `after` position.
+    /// Returns the position of the last appended event.
+    pub fn append<E>(&mut self, events: E, condition: Option<AppendCondition>) -> Result<Position>
+    where
+        E: IntoIterator<Item = Event>,
+    {
+        let events = events
+            .into_iter()
+            .map(Event::candidate)
+            .collect::<Result<Vec<_>>>()?;
+
+        let condition = match condition {
+            Some(AppendCondition {
+                fail_if_events_match,
+                after,
+            }) => {
+                let from = after.map_or(Position::MIN, |after| after + 1);
+
+                match fail_if_events_match.condition()? {
+                    // The stream takes a condition without selections as no
+                    // condition at all, so a query matching every event is
+                    // checked here: it fails if there is any event after
+                    // `after`.
+                    Some(condition) if condition.selections.is_empty() => {
+                        if self.stream.next > from {
+                            return Err(Report::new(Error).attach(Conflict));
+                        }
+
+                        Condition::new()
+                    }
+                    Some(condition) => condition.from(from),
+                    None => Condition::new(),
+                }
+            }
+            None => Condition::new(),
+        };
+
+        self.stream
+            .append(events, condition)
+            .map(|receipt| receipt.last())
+    }
+
+    /// The stream the store reads and appends.
+    #[must_use]
+    pub fn into_inner(self) -> Stream {
+        self.stream
+    }
+}
+
+impl EventStore {
+    // A read event, with its type and tags resolved to the strings they were
+    // appended with.
+    fn sequenced(&self, event: &event::Event<Metadata, u64>) -> Result<SequencedEvent> {
+        let names = &self.stream.store.names;
+        let unrecorded = || Report::new(Error).attach("event name is not recorded");
+
+        let ty = names
+            .ty(event.facets().ty().name().0)?
+            .ok_or_else(unrecorded)?;
+        let mut tags = event
+            .facets()
+            .tags()
+            .iter()
+            .map(|tag| names.tag(tag.0)?.ok_or_else(unrecorded))
+            .collect::<Result<Vec<_>>>()?;
+
+        // The stream orders tags by their hashes.
+        tags.sort();
+
+        Ok(SequencedEvent {
+            event: Event::new(ty, event.data().as_ref(), tags),
+            position: event.meta().position(),
+        })
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Events
+
+/// An event as DCB describes it: a type, data and tags, all but the data as
+/// strings.
+#[derive(new, Clone, Debug, Eq, PartialEq)]
+pub struct Event {
+    /// The event's type (the DCB `type`).
+    #[new(into)]
+    pub ty: String,
+    /// The event's data.
+    #[new(into)]
+    pub data: Vec<u8>,
+    /// The event's tags.
+    pub tags: Vec<String>,
+}
+
+impl Event {
+    fn candidate(self) -> Result<event::Event<(), String>> {
+        let ty = Type::new(Name::new(self.ty)?, Version::MIN);
+        let tags = self.tags.into_iter().map(Tag::new).collect::<Result<_>>()?;
+
+        Ok(event::Event::new(
+            Data::new(self.data)?,
+            Facets::new(ty, tags),
+            (),
+        ))
+    }
+}
+
+/// An event read from the store, with its position.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SequencedEvent {
+    /// The event.
+    pub event: Event,
+    /// The event's position (the DCB sequence position).
+    pub position: Position,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Query
+
+/// Which events a read or an append condition matches.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Query {
+    /// Every event.
+    All,
+    /// The events matching any of the items (so none, with no items).
+    Items(Vec<QueryItem>),
+}
+
+impl Query {
+    /// A query matching every event.
+    #[must_use]
+    pub fn all() -> Self {
+        Self::All
+    }
+
+    /// A query matching the events which match any of `items`.
+    pub fn from_items<I>(items: I) -> Self
+    where
+        I: IntoIterator<Item = QueryItem>,
+    {
+        Self::Items(items.into_iter().collect())
+    }
+
+    // The stream condition matching the same events, or `None` if the query
+    // matches none (which a condition cannot express).
+    fn condition(&self) -> Result<Option<Condition>> {
+        let items = match self {
+            Self::All => return Ok(Some(Condition::new())),
+            Self::Items(items) if items.is_empty() => return Ok(None),
+            Self::Items(items) => items,
+        };
+
+        if items.iter().any(QueryItem::is_empty) {
+            return Ok(Some(Condition::new()));
+        }
+
+        let selectors = items
+            .iter()
+            .map(QueryItem::selector)
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Some(
+            Condition::new().selections([Selection::new(selectors)]),
+        ))
+    }
+}
+
+/// One clause of a [`Query`]: events of any of its types, carrying all of its
+/// tags.
+#[derive(new, Clone, Debug, Default, Eq, PartialEq)]
+pub struct QueryItem {
+    #[new(default)]
+    tags: Vec<String>,
+    #[new(default)]
+    types: Vec<String>,
+}
+
+impl QueryItem {
+    /// Match only events carrying all of `tags`.
+    #[must_use]
+    pub fn tags<I, T>(mut self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.tags = tags.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Match only events of one of `types`.
+    #[must_use]
+    pub fn types<I, T>(mut self, types: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.types = types.into_iter().map(Into::into).collect();
+        self
+    }
+
+    fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.types.is_empty()
+    }
+
+    fn selector(&self) -> Result<Selector<String>> {
+        let types = self
+            .types
+            .iter()
+            .map(|ty| TypeSelector::with_versions(ty.as_str(), ..))
+            .collect::<Result<Vec<_>>>()?;
+        let tags = self
+            .tags
+            .iter()
+            .map(|tag| Tag::new(tag.as_str()))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(match (types.is_empty(), tags.is_empty()) {
+            (true, _) => Selector::tags(tags),
+            (false, true) => Selector::types(types),
+            (false, false) => Selector::types_and_tags(types, tags),
+        })
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Options & Conditions
+
+/// How a read returns the events its query matches.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ReadOptions {
+    backwards: bool,
+    from: Option<Position>,
+    limit: Option<usize>,
+}
+
+impl ReadOptions {
+    /// Read from the last event to the first.
+    #[must_use]
+    pub fn backwards(mut self, backwards: bool) -> Self {
+        self.backwards = backwards;
+        self
+    }
+
+    /// Read from `from` (inclusive): forwards, the events at or after it, and
+    /// backwards, those at or before it.
+    #[must_use]
+    pub fn from(mut self, from: Position) -> Self {
+        self.from = Some(from);
+        self
+    }
+
+    /// Read at most `limit` events.
+    #[must_use]
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+}
+
+/// The condition of an append: fail if any event matching
+/// `fail_if_events_match` exists after `after` (exclusive), or at all if no
+/// `after` is given.
+#[derive(new, Clone, Debug, Eq, PartialEq)]
+pub struct AppendCondition {
+    fail_if_events_match: Query,
+    #[new(default)]
+    after: Option<Position>,
+}
+
+impl AppendCondition {
+    /// Fail only if a matching event exists after `after` (the position of
+    /// the last event the decision was made on).
+    #[must_use]
+    pub fn after(mut self, after: Position) -> Self {
+        self.after = Some(after);
+        self
+    }
+}
@@ -0,0 +1,66 @@
+use std::{
+    sync::Arc,
+    thread::{
+        self,
+        JoinHandle,
+    },
+    time::Duration,
+};
+
+use crossbeam::channel::{
+    self,
+    RecvTimeoutError,
+};
+use derive_more::Debug;
+
+use crate::stream::store::storage::Storage;
+
+// =================================================================================================
+// Flusher
+// =================================================================================================
+
+/// The background half of an interval durability policy: a thread which
+/// fsyncs the storage's journal every `interval`, so commits buffered to the
+/// OS become durable within one interval. It stops (and is joined) when the
+/// `Flusher` is dropped, which happens with the last `Stream`/`Writer` sharing
+/// it.
+#[derive(Debug)]
+pub struct Flusher {
+    #[debug("JoinHandle")]
+    handle: Option<JoinHandle<()>>,
+    #[debug("Sender")]
+    stop: Option<channel::Sender<()>>,
+}
+
+impl Flusher {
+    pub fn spawn(storage: Arc<dyn Storage>, interval: Duration) -> Self {
+        let (stop, stopped) = channel::bounded::<()>(0);
+
+        let handle = thread::spawn(move || {
+            // A failed persist is retried on the next tick: the commits it covers
+            // are still in the journal, only not yet durable.
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                storage.persist().ok();
+            }
+
+            // Make everything committed before the stop durable too.
+            storage.persist().ok();
+        });
+
+        Self {
+            handle: Some(handle),
+            stop: Some(stop),
+        }
+    }
+}
+
+impl Drop for Flusher {
+    fn drop(&mut self) {
+        // Disconnecting the stop channel wakes the thread immediately.
+        drop(self.stop.take());
+
+        if let Some(handle) = self.handle.take() {
+            handle.join().ok();
+        }
+    }
+}
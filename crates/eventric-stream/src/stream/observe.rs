@@ -0,0 +1,529 @@
+//! Pluggable observability: the [`Observer`] trait, registered on a stream
+//! [`Builder`](crate::stream::Builder) or an [`Owner`] [`Builder`], and the
+//! structured [`Observation`]s it is called with. With the `tracing` feature,
+//! [`Tracing`] is an `Observer` which emits observations as `tracing` spans and
+//! events.
+//!
+//! [`Owner`]: crate::stream::concurrent::owner::Owner
+//! [`Builder`]: crate::stream::concurrent::owner::Builder
+
+#[cfg(feature = "tracing")]
+use std::{
+    collections::BTreeMap,
+    sync::{
+        Mutex,
+        MutexGuard,
+    },
+};
+use std::{
+    fmt::{
+        self,
+        Formatter,
+    },
+    sync::{
+        Arc,
+        atomic::{
+            AtomicU64,
+            Ordering,
+        },
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use crate::stream::operate::explain::Counters;
+
+// =================================================================================================
+// Observe
+// =================================================================================================
+
+// Observer
+
+/// A hook called synchronously with an [`Observation`] as stream operations
+/// happen. It runs on the operating thread (the writer thread, for appends
+/// through an `Owner`), so it should be cheap — hand anything expensive off
+/// elsewhere. Implemented for every `Fn(&Observation) + Send + Sync`.
+pub trait Observer: Send + Sync {
+    /// Called with each observation.
+    fn observe(&self, observation: &Observation);
+}
+
+impl<F> Observer for F
+where
+    F: Fn(&Observation) + Send + Sync,
+{
+    fn observe(&self, observation: &Observation) {
+        self(observation);
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Observation
+
+/// A structured record of one stream operation (or of the writer channel's
+/// state), passed to every registered [`Observer`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Observation {
+    /// An append committed.
+    AppendCommitted {
+        /// The number of events appended.
+        count: u64,
+        /// The total payload (`Data`) size of the appended events, in bytes.
+        bytes: u64,
+        /// The time taken by the append, condition check and commit included.
+        duration: Duration,
+    },
+    /// An append was rejected by its condition (a DCB conflict).
+    AppendConflicted {
+        /// The time taken by the condition check.
+        duration: Duration,
+    },
+    /// A select started.
+    SelectStarted {
+        /// The select's identifier, unique within the process, which its
+        /// [`SelectFinished`](Self::SelectFinished) carries too.
+        id: u64,
+        /// The number of selections in the query's condition.
+        selections: usize,
+    },
+    /// A select finished: its iterator was dropped, exhausted or not.
+    SelectFinished {
+        /// The select's identifier, as its
+        /// [`SelectStarted`](Self::SelectStarted) carried.
+        id: u64,
+        /// The number of candidates examined: the index postings read, and the
+        /// events read from the store (including those a filtered scan then
+        /// dropped).
+        scanned: u64,
+        /// The number of matching events yielded to the caller.
+        yielded: u64,
+        /// The time from the start of the select until it finished.
+        duration: Duration,
+    },
+    /// Indexing the events of a [`Bulk`] import dropped unfinished failed.
+    /// They are indexed when the stream is next opened for writing; until
+    /// then, conditions and selects answered from the indices do not see
+    /// them.
+    ///
+    /// [`Bulk`]: crate::stream::Bulk
+    IndexFailed {
+        /// The failure, as its report's debug output.
+        error: String,
+    },
+    /// The writer thread took an operation from an `Owner`'s channel.
+    QueueDepth {
+        /// The number of operations still queued behind it.
+        depth: usize,
+        /// The channel's capacity.
+        capacity: usize,
+    },
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Observers
+
+/// The set of [`Observer`]s registered on a stream, shared by its handles.
+#[derive(Clone, Default)]
+pub(crate) struct Observers(Vec<Arc<dyn Observer>>);
+
+impl Observers {
+    pub fn push(&mut self, observer: Arc<dyn Observer>) {
+        self.0.push(observer);
+    }
+
+    pub fn extend(&mut self, observers: Self) {
+        self.0.extend(observers.0);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn observe(&self, observation: &Observation) {
+        for observer in &self.0 {
+            observer.observe(observation);
+        }
+    }
+}
+
+impl fmt::Debug for Observers {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Observers({})", self.0.len())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Scan
+
+/// The running counts of an observed select, reported as
+/// [`Observation::SelectFinished`] when dropped along with its iterator. A
+/// select over a local store counts what its instrumented iterator read (the
+/// index postings, and the events, matching or not); any other counts each
+/// event it is handed.
+#[derive(Debug)]
+pub(crate) struct Scan {
+    counters: Option<Arc<Counters>>,
+    id: u64,
+    observers: Observers,
+    read: u64,
+    started: Instant,
+    yielded: u64,
+}
+
+impl Scan {
+    pub fn start(observers: Observers, selections: usize, counters: Option<Arc<Counters>>) -> Self {
+        let id = SELECTS.fetch_add(1, Ordering::Relaxed);
+
+        observers.observe(&Observation::SelectStarted { id, selections });
+
+        Self {
+            counters,
+            id,
+            observers,
+            read: 0,
+            started: Instant::now(),
+            yielded: 0,
+        }
+    }
+
+    pub fn read(&mut self, yielded: bool) {
+        self.read += 1;
+        self.yielded += u64::from(yielded);
+    }
+}
+
+impl Drop for Scan {
+    fn drop(&mut self) {
+        let scanned = self.counters.as_ref().map_or(self.read, |counters| {
+            counters.postings() + counters.events()
+        });
+
+        self.observers.observe(&Observation::SelectFinished {
+            id: self.id,
+            scanned,
+            yielded: self.yielded,
+            duration: self.started.elapsed(),
+        });
+    }
+}
+
+// Scan Constants
+
+static SELECTS: AtomicU64 = AtomicU64::new(0);
+
+// -------------------------------------------------------------------------------------------------
+
+// Tracing
+
+/// An [`Observer`] which emits [`Observation`]s through `tracing` (at
+/// `DEBUG`, or `WARN` for a failure, target `eventric_stream`), carrying their
+/// fields.
+///
+/// A select is a `select` span, opened when it starts and closed when it
+/// finishes, with an event at each end. Every other observation is made once
+/// its operation has finished, so is an event named for it, any duration it
+/// carries a field rather than the extent of a span.
+#[cfg(feature = "tracing")]
+#[derive(Debug, Default)]
+pub struct Tracing {
+    spans: Mutex<BTreeMap<u64, tracing::Span>>,
+}
+
+#[cfg(feature = "tracing")]
+impl Tracing {
+    fn spans(&self) -> MutexGuard<'_, BTreeMap<u64, tracing::Span>> {
+        match self.spans.lock() {
+            Ok(spans) => spans,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl Observer for Tracing {
+    fn observe(&self, observation: &Observation) {
+        match observation {
+            Observation::AppendCommitted {
+                count,
+                bytes,
+                duration,
+            } => tracing::debug!(count, bytes, ?duration, "append_committed"),
+            Observation::AppendConflicted { duration } => {
+                tracing::debug!(?duration, "append_conflicted");
+            }
+            Observation::SelectStarted { id, selections } => {
+                let span = tracing::debug_span!(
+                    "select",
+                    id,
+                    selections,
+                    scanned = tracing::field::Empty,
+                    yielded = tracing::field::Empty,
+                );
+
+                span.in_scope(|| tracing::debug!("select_started"));
+                self.spans().insert(*id, span);
+            }
+            Observation::SelectFinished {
+                id,
+                scanned,
+                yielded,
+                duration,
+            } => {
+                // A select started before this observer was registered has no
+                // span to close.
+                let span = self.spans().remove(id).unwrap_or_else(tracing::Span::none);
+
+                span.record("scanned", scanned);
+                span.record("yielded", yielded);
+                span.in_scope(|| tracing::debug!(?duration, "select_finished"));
+            }
+            Observation::IndexFailed { error } => tracing::warn!(error, "index_failed"),
+            Observation::QueueDepth { depth, capacity } => {
+                tracing::debug!(depth, capacity, "queue_depth");
+            }
+        }
+    }
+}
+
+// =================================================================================================
+// Tests
+// =================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeSet,
+        sync::{
+            Arc,
+            Mutex,
+        },
+    };
+
+    use super::Observation;
+    #[cfg(feature = "tracing")]
+    use super::{
+        Observer as _,
+        Tracing,
+    };
+    use crate::{
+        error::Conflict,
+        event::{
+            Data,
+            Event,
+            Facets,
+            Name,
+            Tag,
+            Type,
+            Version,
+        },
+        stream::{
+            Position,
+            Stream,
+            operate::{
+                Condition,
+                Selection,
+                append::Append as _,
+                select::{
+                    Select as _,
+                    Selector,
+                    TypeSelector,
+                },
+            },
+        },
+        utils::temp_path,
+    };
+
+    fn event(identifier: &str, data: &[u8], tags: &[&str]) -> Event<(), String> {
+        let ty = Type::new(Name::new(identifier).unwrap(), Version::new(0));
+        let tags = tags
+            .iter()
+            .map(|tag| Tag::new(*tag).unwrap())
+            .collect::<BTreeSet<_>>();
+
+        Event::new(Data::new(data.to_vec()).unwrap(), Facets::new(ty, tags), ())
+    }
+
+    fn enrolled() -> Condition {
+        Condition::new().selections([Selection::new([Selector::types([TypeSelector::new(
+            "Enrolled",
+        )
+        .unwrap()])])])
+    }
+
+    // Appends report their count and payload bytes when committed, and a
+    // rejected append reports the conflict instead.
+    #[test]
+    fn appends_are_observed() {
+        let observations = Arc::new(Mutex::new(Vec::new()));
+        let mut stream = Stream::builder(temp_path())
+            .temporary(true)
+            .observer({
+                let observations = Arc::clone(&observations);
+
+                move |observation: &Observation| {
+                    observations.lock().unwrap().push(observation.clone());
+                }
+            })
+            .open()
+            .unwrap();
+
+        stream
+            .append(
+                vec![
+                    event("Enrolled", b"abc", &[]),
+                    event("Enrolled", b"de", &[]),
+                ],
+                Condition::new(),
+            )
+            .unwrap();
+
+        let report = stream
+            .append(
+                vec![event("Enrolled", b"f", &[])],
+                enrolled().from(Position::MIN),
+            )
+            .unwrap_err();
+
+        assert!(report.downcast_ref::<Conflict>().is_some());
+
+        let observations = observations.lock().unwrap();
+
+        assert_eq!(observations.len(), 2);
+        assert!(matches!(observations[0], Observation::AppendCommitted {
+            count: 2,
+            bytes: 5,
+            ..
+        }));
+        assert!(matches!(
+            observations[1],
+            Observation::AppendConflicted { .. }
+        ));
+    }
+
+    // A select reports its start, then (once its iterator is dropped) how many
+    // candidates it examined and yielded — on a filtered scan, every event it
+    // read, on the index path the postings and the events they led to —
+    // including a select split off to a `Reader`, and one abandoned part way
+    // through.
+    #[test]
+    fn selects_are_observed() {
+        let observations = Arc::new(Mutex::new(Vec::new()));
+        let mut stream = Stream::builder(temp_path())
+            .temporary(true)
+            .observer({
+                let observations = Arc::clone(&observations);
+
+                move |observation: &Observation| {
+                    if !matches!(observation, Observation::AppendCommitted { .. }) {
+                        observations.lock().unwrap().push(observation.clone());
+                    }
+                }
+            })
+            .open()
+            .unwrap();
+
+        stream
+            .append(
+                vec![
+                    event("Enrolled", b"a", &["student:1"]),
+                    event("Dropped", b"b", &["student:1"]),
+                    event("Enrolled", b"c", &["student:2"]),
+                ],
+                Condition::new(),
+            )
+            .unwrap();
+
+        // Enough other events that one student's are worth the index path.
+        stream
+            .append(
+                (0..5).map(|_| event("Paid", b"d", &[])).collect::<Vec<_>>(),
+                Condition::new(),
+            )
+            .unwrap();
+
+        let student = Condition::new()
+            .selections([Selection::new([Selector::tags([
+                Tag::new("student:2").unwrap()
+            ])])]);
+
+        let (reader, _writer) = stream.split();
+
+        assert_eq!(reader.select(enrolled()).count(), 2);
+        assert_eq!(reader.select(student).count(), 1);
+        assert!(reader.select(Condition::new()).next().is_some());
+
+        let observations = observations.lock().unwrap();
+
+        assert_eq!(observations.len(), 6);
+        assert!(matches!(observations[0], Observation::SelectStarted {
+            selections: 1,
+            ..
+        }));
+        assert!(matches!(observations[1], Observation::SelectFinished {
+            scanned: 8,
+            yielded: 2,
+            ..
+        }));
+        assert!(matches!(observations[3], Observation::SelectFinished {
+            scanned: 2,
+            yielded: 1,
+            ..
+        }));
+        assert!(matches!(observations[4], Observation::SelectStarted {
+            selections: 0,
+            ..
+        }));
+        assert!(matches!(observations[5], Observation::SelectFinished {
+            scanned: 1,
+            yielded: 1,
+            ..
+        }));
+
+        let ids = observations
+            .iter()
+            .map(|observation| match observation {
+                Observation::SelectStarted { id, .. } | Observation::SelectFinished { id, .. } => {
+                    *id
+                }
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(ids[0], ids[1]);
+        assert_eq!(ids[2], ids[3]);
+        assert_eq!(ids[4], ids[5]);
+        assert_ne!(ids[0], ids[2]);
+    }
+
+    // The tracing observer holds a select's span only from its start to its
+    // finish.
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn tracing_closes_a_select_span_when_it_finishes() {
+        let tracing = Arc::new(Tracing::default());
+        let stream = Stream::builder(temp_path())
+            .temporary(true)
+            .observer({
+                let tracing = Arc::clone(&tracing);
+
+                move |observation: &Observation| tracing.observe(observation)
+            })
+            .open()
+            .unwrap();
+
+        let mut select = stream.select(enrolled());
+
+        assert!(select.next().is_none());
+        assert_eq!(tracing.spans().len(), 1);
+
+        drop(select);
+
+        assert!(tracing.spans().is_empty());
+    }
+}
@@ -1,11 +1,16 @@
-//! The stream's operations vocabulary, split across two submodules plus the
+//! The stream's operations vocabulary, split across four submodules plus the
 //! shared [`Condition`]/[`Selection`] types defined here: [`append`] holds the
-//! [`Append`](append::Append) operation, and [`select`] holds the
+//! [`Append`](append::Append) operation, [`select`] holds the
 //! [`Select`](select::Select) query along with its selector and mask types
 //! ([`Selector`], [`TypeSelector`](select::TypeSelector),
-//! [`Mask`](select::Mask), [`EventAndMask`](select::EventAndMask), …).
+//! [`Mask`](select::Mask), [`EventAndMask`](select::EventAndMask), …),
+//! [`explain`] holds the query [`Plan`](explain::Plan) and
+//! [`Analysis`](explain::Analysis) types, and [`query`] holds the textual
+//! [`Query`](query::Query) form of a condition.
 
 pub mod append;
+pub mod explain;
+pub mod query;
 pub mod select;
 
 use self::select::Selector;
@@ -18,14 +23,16 @@ use crate::stream::Position;
 // Condition
 
 /// A query (and, later, append concurrency) condition: an optional lower
-/// position bound plus zero or more [`Selection`]s to match.
+/// position bound, an optional batch, plus zero or more [`Selection`]s to
+/// match.
 ///
 /// Each [`Selection`] is one mask unit. A matched event carries a
 /// [`Mask`](select::Mask) recording which selections it satisfied, in the order
 /// they were supplied. With no selections the condition matches
-/// the whole stream (a full scan).
-#[derive(Debug, Default)]
+/// the whole stream (a full scan), or the whole batch.
+#[derive(Clone, Debug, Default)]
 pub struct Condition {
+    pub(crate) batch: Option<Position>,
     pub(crate) position: Option<Position>,
     pub(crate) selections: Vec<Selection>,
 }
@@ -44,6 +51,16 @@ impl Condition {
         self
     }
 
+    /// Restrict the condition to the batch holding the event at `position`:
+    /// the events appended with it, in one append (see
+    /// [`Metadata::batch`](crate::stream::Metadata::batch)). Nothing matches
+    /// if there is no event at `position`.
+    #[must_use]
+    pub fn batch(mut self, position: Position) -> Self {
+        self.batch = Some(position);
+        self
+    }
+
     /// Set the selections to match. Each is one mask unit (see [`Condition`]).
     #[must_use]
     pub fn selections<I>(mut self, selections: I) -> Self
@@ -62,7 +79,7 @@ impl Condition {
 /// One mask unit: a set of [`Selector`]s combined with OR. An event matches the
 /// selection if it matches any of its selectors. String type-names and tags are
 /// hashed when the selection is built.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Selection {
     pub(crate) selectors: Vec<Selector<u64>>,
 }
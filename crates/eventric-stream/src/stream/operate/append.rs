@@ -1,9 +1,13 @@
 //! Appending events to the stream under an optimistic-concurrency (DCB)
-//! `Condition`.
+//! `Condition`, and the [`AppendReceipt`] of what an append committed.
+
+use std::{
+    ops::RangeInclusive,
+    time::Instant,
+};
 
 use error_stack::Report;
 use fancy_constructor::new;
-use fjall::OwnedWriteBatch as Batch;
 
 use crate::{
     error::{
@@ -13,9 +17,20 @@ use crate::{
     },
     event::Event,
     stream::{
+        Durability,
+        Metadata,
         Position,
+        Timestamp,
+        clock::Clock,
+        observe::{
+            Observation,
+            Observers,
+        },
         operate::Condition,
-        store::Store,
+        store::{
+            Store,
+            storage::Batch,
+        },
     },
 };
 
@@ -26,12 +41,92 @@ use crate::{
 /// Appends candidate events to the stream, subject to a `Condition`.
 pub trait Append {
     /// Appends `events`, rejecting with a `Conflict` if `condition`'s DCB
-    /// concurrency check fails, and returns the `Position` of the last appended
-    /// event.
-    fn append<E>(&mut self, events: E, condition: Condition) -> Result<Position>
+    /// concurrency check fails, and returns the [`AppendReceipt`] of the
+    /// appended events.
+    fn append<E>(&mut self, events: E, condition: Condition) -> Result<AppendReceipt>
     where
         E: IntoIterator<Item = Event<(), String>>,
         E::IntoIter: Send + 'static;
+
+    /// Appends `events` as [`append`](Append::append) does, but persists the
+    /// commit with `durability` rather than the stream's configured default
+    /// (e.g. fsync an audit event on a stream which otherwise only buffers).
+    ///
+    /// The default ignores `durability` and appends as `append` does, for an
+    /// implementor which cannot choose how each commit is persisted.
+    fn append_durable<E>(
+        &mut self,
+        events: E,
+        condition: Condition,
+        durability: Durability,
+    ) -> Result<AppendReceipt>
+    where
+        E: IntoIterator<Item = Event<(), String>>,
+        E::IntoIter: Send + 'static,
+    {
+        let _ = durability;
+
+        self.append(events, condition)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Append Receipt
+
+/// What an append committed: the positions of its events (`first..=last`),
+/// the timestamp each was committed with, and the batch they were committed in
+/// (see [`Metadata::batch`]). An append commits at least one event.
+#[derive(new, Clone, Debug, Eq, PartialEq)]
+#[new(vis(pub(crate)))]
+pub struct AppendReceipt {
+    batch: Position,
+    first: Position,
+    timestamps: Vec<Timestamp>,
+}
+
+impl AppendReceipt {
+    /// The position of the first appended event.
+    #[must_use]
+    pub fn first(&self) -> Position {
+        self.first
+    }
+
+    /// The position of the last appended event.
+    #[must_use]
+    pub fn last(&self) -> Position {
+        self.first + (self.timestamps.len() as u64 - 1)
+    }
+
+    /// The positions of the appended events.
+    #[must_use]
+    pub fn positions(&self) -> RangeInclusive<Position> {
+        self.first()..=self.last()
+    }
+
+    /// The batch the events were committed in, identified (as in each event's
+    /// [`Metadata`]) by the position of the batch's first event.
+    #[must_use]
+    pub fn batch(&self) -> Position {
+        self.batch
+    }
+
+    /// The timestamps the appended events were committed with, in position
+    /// order.
+    #[must_use]
+    pub fn timestamps(&self) -> &[Timestamp] {
+        &self.timestamps
+    }
+
+    /// The [`Metadata`] of each appended event, in position order: as a select
+    /// would read it back.
+    pub fn metadata(&self) -> impl Iterator<Item = Metadata> + '_ {
+        (self.first.0..)
+            .zip(&self.timestamps)
+            .map(|(position, timestamp)| {
+                Metadata::new(Position::new(position), *timestamp, self.batch)
+            })
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -39,14 +134,17 @@ pub trait Append {
 // Appender
 
 /// The shared append worker behind [`Stream`](crate::stream::Stream) and
-/// [`Writer`](crate::stream::Writer): a batch source, the `next`-position
-/// cursor, and the `Store`. Both handles construct one and delegate to its
-/// `append`, so the DCB check and the insert live in a single place.
+/// [`Writer`](crate::stream::Writer): a batch source, the `Clock`, the
+/// `next`-position cursor, the registered `Observers`, and the `Store`. Both
+/// handles construct one and delegate to its `append`, so the DCB check, the
+/// insert, and their observation live in a single place.
 #[derive(new)]
 #[new(vis(pub(crate)))]
 pub(crate) struct Appender<'a, B> {
     batch: &'a mut B,
+    clock: &'a dyn Clock,
     next: &'a mut Position,
+    observers: &'a Observers,
     store: &'a Store,
 }
 
@@ -54,30 +152,77 @@ impl<B> Appender<'_, B>
 where
     B: FnMut() -> Batch,
 {
-    pub(crate) fn append<E>(&mut self, events: E, condition: Condition) -> Result<Position>
+    pub(crate) fn append<E>(&mut self, events: E, condition: Condition) -> Result<AppendReceipt>
     where
         E: IntoIterator<Item = Event<(), String>>,
-        E::IntoIter: Send + 'static,
     {
+        let started = Instant::now();
         let Condition {
+            batch,
             position,
             selections,
         } = condition;
 
         // Optimistic-concurrency (DCB) check: reject the append if any event
-        // matching `selections` already exists at or after `position`. Empty
-        // selections means no condition, so the append is unconditional. The
-        // window starting at or after the head can never conflict, so skip the
-        // index scan in that case.
-        let conflict = match position {
-            Some(from) if from >= *self.next => false,
-            _ => self.store.matches(&selections, position)?,
+        // matching `selections` already exists at or after `position` (and in
+        // `batch`, if given). Empty selections means no condition, so the
+        // append is unconditional. The window starting at or after the head can
+        // never conflict, so skip the index scan in that case.
+        let conflict = match (position, batch) {
+            (Some(from), _) if from >= *self.next => false,
+            (_, Some(batch)) => self.store.matches_batch(&selections, batch, position)?,
+            (_, None) => self.store.matches(&selections, position)?,
         };
 
         if conflict {
+            self.observers.observe(&Observation::AppendConflicted {
+                duration: started.elapsed(),
+            });
+
             return Err(Report::new(Error).attach(Conflict));
         }
 
-        self.store.insert(self.batch, events, self.next)
+        let from = *self.next;
+        let mut bytes = 0;
+        let events = events.into_iter().inspect(|event| {
+            bytes += event.data().as_ref().len() as u64;
+        });
+
+        let receipt = self
+            .store
+            .insert(self.batch, self.clock, events, self.next)?;
+
+        self.observers.observe(&Observation::AppendCommitted {
+            count: self.next.0 - from.0,
+            bytes,
+            duration: started.elapsed(),
+        });
+
+        Ok(receipt)
+    }
+
+    /// Append `events` with their own timestamps, unconditionally, deferring
+    /// their postings unless `index` (see
+    /// [`Writer::import`](crate::stream::Writer::import)).
+    pub(crate) fn import<E>(&mut self, events: E, index: bool) -> Result<AppendReceipt>
+    where
+        E: IntoIterator<Item = (Timestamp, Event<(), String>)>,
+    {
+        let started = Instant::now();
+        let from = *self.next;
+        let mut bytes = 0;
+        let events = events.into_iter().inspect(|(_, event)| {
+            bytes += event.data().as_ref().len() as u64;
+        });
+
+        let receipt = self.store.import(self.batch, events, self.next, index)?;
+
+        self.observers.observe(&Observation::AppendCommitted {
+            count: self.next.0 - from.0,
+            bytes,
+            duration: started.elapsed(),
+        });
+
+        Ok(receipt)
     }
 }
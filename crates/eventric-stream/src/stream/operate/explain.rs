@@ -0,0 +1,486 @@
+//! Query plan explanation: the [`Plan`] a [`Condition`] is lowered to (from
+//! [`Select::explain`]), and the [`Analysis`] of an instrumented run of it
+//! (from [`Select::analyze`]).
+//!
+//! [`Condition`]: crate::stream::operate::Condition
+//! [`Select::explain`]: crate::stream::operate::select::Select::explain
+//! [`Select::analyze`]: crate::stream::operate::select::Select::analyze
+
+use std::{
+    fmt::{
+        self,
+        Display,
+        Formatter,
+    },
+    ops::Range,
+    sync::atomic::{
+        AtomicU64,
+        Ordering,
+    },
+    time::Duration,
+};
+
+use crate::{
+    event::Version,
+    stream::Position,
+};
+
+// =================================================================================================
+// Explain
+// =================================================================================================
+
+// Plan
+
+/// How a query is executed: the access path its `Condition` was lowered to.
+/// Renders as an indented tree with `Display`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Plan {
+    /// A full scan of the events keyspace (a condition with no selections),
+    /// from `from` onwards.
+    Events {
+        /// The query's lower position bound, if any.
+        from: Option<Position>,
+    },
+    /// A full scan of the events keyspace, from `from` onwards, filtering each
+    /// event against the selections: chosen over the index path when the
+    /// cardinalities estimate the selections match much of the stream.
+    Filter {
+        /// The query's lower position bound, if any.
+        from: Option<Position>,
+    },
+    /// A scan of the index keyspace, resolving each matching position to its
+    /// event, from `from` onwards.
+    Indices {
+        /// The query's lower position bound, if any.
+        from: Option<Position>,
+        /// The root of the index iterator tree.
+        root: Node,
+    },
+    /// A scan of the events of one batch (a condition restricted with
+    /// [`Condition::batch`](crate::stream::operate::Condition::batch)), from
+    /// `from` onwards, filtering each event against the selections if any.
+    Batch {
+        /// The query's lower position bound, if any.
+        from: Option<Position>,
+        /// The position of an event in the batch.
+        position: Position,
+    },
+}
+
+impl Plan {
+    /// The number of `Intersection` nodes in the index iterator tree.
+    #[must_use]
+    pub fn intersections(&self) -> usize {
+        self.count(|node| matches!(node, Node::Intersection(_)))
+    }
+
+    /// The number of `Union` nodes in the index iterator tree.
+    #[must_use]
+    pub fn unions(&self) -> usize {
+        self.count(|node| matches!(node, Node::Union(_)))
+    }
+
+    fn count(&self, f: impl Fn(&Node) -> bool + Copy) -> usize {
+        match self {
+            Self::Events { .. } | Self::Filter { .. } | Self::Batch { .. } => 0,
+            Self::Indices { root, .. } => root.count(f),
+        }
+    }
+}
+
+impl Display for Plan {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (path, from, root) = match self {
+            Self::Events { from } => ("Events", from, None),
+            Self::Filter { from } => ("Filter", from, None),
+            Self::Indices { from, root } => ("Indices", from, Some(root)),
+            Self::Batch { from, position } => {
+                write!(f, "Batch (of {}", position.0)?;
+
+                return match from {
+                    Some(from) => writeln!(f, ", from {})", from.0),
+                    None => writeln!(f, ")"),
+                };
+            }
+        };
+
+        match from {
+            Some(from) => writeln!(f, "{path} (from {})", from.0)?,
+            None => writeln!(f, "{path}")?,
+        }
+
+        root.map_or(Ok(()), |root| root.fmt_indented(f, 1))
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Node
+
+/// One node of the index iterator tree a [`Plan::Indices`] executes.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Node {
+    /// The positions present in every child (AND), found by leapfrogging the
+    /// children with seeks.
+    Intersection(Vec<Node>),
+    /// The positions present in any child (OR), merged in order.
+    Union(Vec<Node>),
+    /// The postings of one tag.
+    Tag {
+        /// The tag's stable hash.
+        hash: u64,
+    },
+    /// The postings of one type name, filtered to a version range.
+    Type {
+        /// The type name's stable hash.
+        hash: u64,
+        /// The (half-open) range of versions matched.
+        versions: Range<Version>,
+    },
+}
+
+impl Node {
+    fn count(&self, f: impl Fn(&Self) -> bool + Copy) -> usize {
+        let children = match self {
+            Self::Intersection(nodes) | Self::Union(nodes) => {
+                nodes.iter().map(|node| node.count(f)).sum()
+            }
+            Self::Tag { .. } | Self::Type { .. } => 0,
+        };
+
+        usize::from(f(self)) + children
+    }
+
+    fn fmt_indented(&self, f: &mut Formatter<'_>, depth: usize) -> fmt::Result {
+        let indent = depth * 2;
+
+        match self {
+            Self::Intersection(nodes) | Self::Union(nodes) => {
+                let name = match self {
+                    Self::Intersection(_) => "Intersection",
+                    _ => "Union",
+                };
+
+                writeln!(f, "{:indent$}{name}", "")?;
+
+                for node in nodes {
+                    node.fmt_indented(f, depth + 1)?;
+                }
+
+                Ok(())
+            }
+            Self::Tag { hash } => writeln!(f, "{:indent$}Tag {hash:#018x}", ""),
+            Self::Type { hash, versions } => writeln!(
+                f,
+                "{:indent$}Type {hash:#018x} v{}..v{}",
+                "", versions.start.0, versions.end.0
+            ),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Analysis
+
+/// The result of an instrumented run of a query: its [`Plan`], and what
+/// executing it to completion cost.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Analysis {
+    /// The plan which was executed.
+    pub plan: Plan,
+    /// The index postings read (including those a type's version range then
+    /// filtered out). Zero for a [`Plan::Events`] scan.
+    pub postings: u64,
+    /// The seeks performed on index scans (each one LSM re-range), as the
+    /// intersections leapfrogged their children.
+    pub seeks: u64,
+    /// The events read (and decoded) from the events keyspace.
+    pub events: u64,
+    /// The wall-clock time taken to run the query to completion.
+    pub duration: Duration,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Counters
+
+/// The shared counters an instrumented store iterator (its index iterator
+/// tree, and its reads of events) increments.
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    events: AtomicU64,
+    postings: AtomicU64,
+    seeks: AtomicU64,
+}
+
+impl Counters {
+    pub fn event(&self) {
+        self.events.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn events(&self) -> u64 {
+        self.events.load(Ordering::Relaxed)
+    }
+
+    pub fn posting(&self) {
+        self.postings.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn postings(&self) -> u64 {
+        self.postings.load(Ordering::Relaxed)
+    }
+
+    pub fn seek(&self) {
+        self.seeks.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn seeks(&self) -> u64 {
+        self.seeks.load(Ordering::Relaxed)
+    }
+}
+
+// =================================================================================================
+// Tests
+// =================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::{
+        Node,
+        Plan,
+    };
+    use crate::{
+        event::{
+            Data,
+            Event,
+            Facets,
+            Name,
+            Tag,
+            Type,
+            Version,
+        },
+        stream::{
+            Position,
+            Stream,
+            operate::{
+                Condition,
+                Selection,
+                append::Append as _,
+                select::{
+                    Select,
+                    SelectIter,
+                    Selector,
+                    TypeSelector,
+                },
+            },
+        },
+        utils::temp_path,
+    };
+
+    fn stream() -> Stream {
+        Stream::builder(temp_path()).temporary(true).open().unwrap()
+    }
+
+    fn event(identifier: &str, tags: &[&str]) -> Event<(), String> {
+        let ty = Type::new(Name::new(identifier).unwrap(), Version::new(0));
+        let tags = tags
+            .iter()
+            .map(|tag| Tag::new(*tag).unwrap())
+            .collect::<BTreeSet<_>>();
+
+        Event::new(
+            Data::new(b"payload".to_vec()).unwrap(),
+            Facets::new(ty, tags),
+            (),
+        )
+    }
+
+    fn name(identifier: &str) -> u64 {
+        Name::<u64>::from(Name::new(identifier).unwrap()).0
+    }
+
+    fn tag(tag: &str) -> u64 {
+        Tag::<u64>::from(Tag::new(tag).unwrap()).0
+    }
+
+    // A condition with no selections is lowered to the full events scan.
+    #[test]
+    fn no_selections_explain_as_an_events_scan() {
+        let plan = stream().explain(Condition::new().from(Position::new(3)));
+
+        assert_eq!(plan, Plan::Events {
+            from: Some(Position::new(3))
+        });
+        assert_eq!(plan.intersections(), 0);
+        assert_eq!(plan.unions(), 0);
+        assert_eq!(plan.to_string(), "Events (from 3)\n");
+    }
+
+    // A tagged selector is lowered to the intersection of its types (a union)
+    // with its tags (an intersection), under the union of all selectors.
+    #[test]
+    fn tagged_selector_explains_as_an_intersection() {
+        let condition = Condition::new().selections([Selection::new([Selector::types_and_tags(
+            [
+                TypeSelector::new("Enrolled").unwrap(),
+                TypeSelector::new("Dropped").unwrap(),
+            ],
+            [Tag::new("course:1").unwrap()],
+        )])]);
+
+        let plan = stream().explain(condition);
+        let versions = Version::MIN..Version::MAX;
+
+        assert_eq!(plan, Plan::Indices {
+            from: None,
+            root: Node::Union(vec![Node::Intersection(vec![
+                Node::Union(vec![
+                    Node::Type {
+                        hash: name("Dropped"),
+                        versions: versions.clone(),
+                    },
+                    Node::Type {
+                        hash: name("Enrolled"),
+                        versions,
+                    },
+                ]),
+                Node::Intersection(vec![Node::Tag {
+                    hash: tag("course:1"),
+                }]),
+            ])]),
+        });
+        assert_eq!(plan.intersections(), 2);
+        assert_eq!(plan.unions(), 2);
+        assert_eq!(plan.to_string().lines().count(), 8);
+    }
+
+    // An analyzed run counts what the leapfrog read: a rare tag lets the
+    // intersection seek past the common type's postings rather than read them.
+    #[test]
+    fn analyze_counts_postings_seeks_and_events() {
+        let mut stream = stream();
+
+        stream
+            .append(
+                (0..100).map(|i| match i {
+                    10 | 90 => event("Enrolled", &["course:1"]),
+                    _ => event("Enrolled", &["course:2"]),
+                }),
+                Condition::new(),
+            )
+            .unwrap();
+
+        let analysis = stream
+            .analyze(
+                Condition::new().selections([Selection::new([Selector::types_and_tags(
+                    [TypeSelector::new("Enrolled").unwrap()],
+                    [Tag::new("course:1").unwrap()],
+                )])]),
+            )
+            .unwrap();
+
+        assert_eq!(analysis.events, 2);
+        assert!(analysis.seeks > 0);
+        assert!(analysis.postings < 100);
+
+        let analysis = stream.analyze(Condition::new()).unwrap();
+
+        assert_eq!(analysis.plan, Plan::Events { from: None });
+        assert_eq!(analysis.events, 100);
+        assert_eq!(analysis.postings, 0);
+    }
+
+    // The cardinalities order an intersection rarest child first, and send a
+    // selection matching much of the stream down the filtered full scan.
+    #[test]
+    fn cardinalities_shape_the_plan() {
+        let mut stream = stream();
+
+        stream
+            .append(
+                (0..100).map(|i| match i {
+                    10 => event("Enrolled", &["course:1"]),
+                    _ => event("Dropped", &["course:2"]),
+                }),
+                Condition::new(),
+            )
+            .unwrap();
+
+        let rare = Condition::new().selections([Selection::new([Selector::types_and_tags(
+            [TypeSelector::new("Dropped").unwrap()],
+            [Tag::new("course:1").unwrap()],
+        )])]);
+
+        assert_eq!(stream.explain(rare), Plan::Indices {
+            from: None,
+            root: Node::Union(vec![Node::Intersection(vec![
+                Node::Intersection(vec![Node::Tag {
+                    hash: tag("course:1"),
+                }]),
+                Node::Union(vec![Node::Type {
+                    hash: name("Dropped"),
+                    versions: Version::MIN..Version::MAX,
+                }]),
+            ])]),
+        });
+
+        let broad =
+            Condition::new().selections([Selection::new([Selector::types([TypeSelector::new(
+                "Dropped",
+            )
+            .unwrap()])])]);
+
+        assert_eq!(stream.explain(broad), Plan::Filter { from: None });
+    }
+
+    // An implementor which only selects is explained as a full scan, and
+    // analyzed by counting what its select yields.
+    #[test]
+    fn select_only_implementors_explain_and_analyze_by_default() {
+        struct Selecting(Stream);
+
+        impl Select for Selecting {
+            fn select(&self, condition: Condition) -> SelectIter {
+                self.0.select(condition)
+            }
+        }
+
+        let mut stream = stream();
+
+        stream
+            .append(
+                (0..10).map(|i| match i {
+                    3 => event("Enrolled", &["course:1"]),
+                    _ => event("Dropped", &["course:2"]),
+                }),
+                Condition::new(),
+            )
+            .unwrap();
+
+        let selecting = Selecting(stream);
+        let rare = Condition::new()
+            .selections([Selection::new([Selector::tags([
+                Tag::new("course:1").unwrap()
+            ])])]);
+
+        assert_eq!(selecting.explain(Condition::new()), Plan::Events {
+            from: None
+        });
+        assert_eq!(
+            selecting.explain(Condition::new().batch(Position::new(3))),
+            Plan::Batch {
+                from: None,
+                position: Position::new(3)
+            }
+        );
+
+        let analysis = selecting.analyze(rare).unwrap();
+
+        assert_eq!(analysis.plan, Plan::Filter { from: None });
+        assert_eq!(analysis.events, 1);
+        assert_eq!(analysis.postings, 0);
+    }
+}
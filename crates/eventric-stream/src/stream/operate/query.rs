@@ -0,0 +1,821 @@
+//! The textual form of a [`Condition`]: a [`Query`] parses from (and displays
+//! as) a small grammar, for conditions given in configuration, at an
+//! operations console or on a command line, and converts into the
+//! [`Condition`] it describes.
+//!
+//! ```text
+//! query     = [clause *(";" clause)]
+//! clause    = "from" position | "batch" position | [label "="] selection
+//! selection = selector *("|" selector)
+//! selector  = factor *("&" factor)
+//! factor    = "tag:" value | type | "(" type *("|" type) ")"
+//! type      = value ["@" versions]
+//! versions  = version [".." [version]] | ".." version
+//! ```
+//!
+//! Each selection is one mask unit, in the order given; a label names it, so
+//! its index in a [`Mask`](crate::stream::operate::select::Mask) can be found
+//! with [`Query::index`]. A selection matches an event matching any of its
+//! selectors, and a selector an event of any of its types (one type, or a
+//! parenthesised group) carrying all of its tags. A type's versions default
+//! to all of them, and a single version selects that version alone; ranges
+//! are half-open, as in Rust. A `batch` clause restricts the query to the
+//! batch holding the event at its position (see
+//! [`Condition::batch`]). A value is a run of characters other than
+//! whitespace and `;|&()@="`, or a string in double quotes (with `\"` and
+//! `\\` escapes) for one which needs them; a label is an identifier. An empty
+//! query matches every event.
+//!
+//! For example, `sel0 = (Enrolled@1.. | Dropped) & tag:course:1; from 100`
+//! matches the events from position 100 which are `Enrolled` events at version
+//! 1 or later, or `Dropped` events, tagged `course:1`; `batch 7` matches every
+//! event appended along with the event at position 7.
+//!
+//! A query which cannot be parsed fails with the [`Span`] of its input at
+//! fault attached, along with a description pointing at it.
+
+use std::{
+    collections::BTreeSet,
+    fmt::{
+        self,
+        Display,
+        Formatter,
+    },
+    ops::Range,
+    str::FromStr,
+};
+
+use error_stack::{
+    Report,
+    ResultExt as _,
+};
+
+use crate::{
+    error::{
+        Error,
+        Result,
+    },
+    event::{
+        Tag,
+        Version,
+    },
+    stream::{
+        Position,
+        operate::{
+            Condition,
+            Selection,
+            select::{
+                Selector,
+                TypeSelector,
+            },
+        },
+    },
+};
+
+// =================================================================================================
+// Query
+// =================================================================================================
+
+/// A [`Condition`] in its textual form (see the [module](self) for the
+/// grammar): its selections, each with an optional label, as selectors of
+/// type names and tags, and its `from` and `batch` positions. Displays in the
+/// same grammar, so a displayed query parses back to an equal one.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Query {
+    batch: Option<Position>,
+    from: Option<Position>,
+    selections: Vec<(Option<String>, Vec<Selector<String>>)>,
+}
+
+impl Query {
+    /// A query matching every event.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse `query` (see the [module](self) for the grammar).
+    pub fn parse(query: &str) -> Result<Self> {
+        Parser::new(query).query()
+    }
+
+    /// Restrict the query to events at or after `position`.
+    #[must_use]
+    pub fn from(mut self, position: Position) -> Self {
+        self.from = Some(position);
+        self
+    }
+
+    /// Restrict the query to the batch holding the event at `position` (see
+    /// [`Condition::batch`]).
+    #[must_use]
+    pub fn batch(mut self, position: Position) -> Self {
+        self.batch = Some(position);
+        self
+    }
+
+    /// Add a selection of `selectors` (combined with OR), as the next mask
+    /// unit.
+    #[must_use]
+    pub fn selection<I>(mut self, selectors: I) -> Self
+    where
+        I: IntoIterator<Item = Selector<String>>,
+    {
+        self.selections
+            .push((None, selectors.into_iter().collect()));
+        self
+    }
+
+    /// Add a selection of `selectors` (combined with OR) labelled `label`, as
+    /// the next mask unit. The label must be an identifier, not already given
+    /// to another selection.
+    pub fn labelled<L, I>(mut self, label: L, selectors: I) -> Result<Self>
+    where
+        L: Into<String>,
+        I: IntoIterator<Item = Selector<String>>,
+    {
+        let label = label.into();
+
+        if !is_label(&label) {
+            return Err(Report::new(Error).attach(format!("`{label}` is not a valid label")));
+        }
+
+        if self.index(&label).is_some() {
+            return Err(Report::new(Error).attach(format!("`{label}` labels another selection")));
+        }
+
+        self.selections
+            .push((Some(label), selectors.into_iter().collect()));
+
+        Ok(self)
+    }
+
+    /// The index, in a matched event's mask, of the selection labelled
+    /// `label`.
+    #[must_use]
+    pub fn index(&self, label: &str) -> Option<usize> {
+        self.selections
+            .iter()
+            .position(|(other, _)| other.as_deref() == Some(label))
+    }
+}
+
+impl From<Query> for Condition {
+    fn from(query: Query) -> Self {
+        let condition = Self::new().selections(
+            query
+                .selections
+                .into_iter()
+                .map(|(_, selectors)| Selection::new(selectors)),
+        );
+
+        let condition = match query.from {
+            Some(from) => condition.from(from),
+            None => condition,
+        };
+
+        match query.batch {
+            Some(batch) => condition.batch(batch),
+            None => condition,
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = Report<Error>;
+
+    fn from_str(query: &str) -> Result<Self> {
+        Self::parse(query)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Display
+
+impl Display for Query {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut clauses = 0;
+
+        for (label, selectors) in &self.selections {
+            if clauses > 0 {
+                f.write_str("; ")?;
+            }
+
+            if let Some(label) = label {
+                write!(f, "{label} = ")?;
+            }
+
+            for (index, selector) in selectors.iter().enumerate() {
+                if index > 0 {
+                    f.write_str(" | ")?;
+                }
+
+                fmt_selector(selector, f)?;
+            }
+
+            clauses += 1;
+        }
+
+        for (keyword, position) in [("from", self.from), ("batch", self.batch)] {
+            if let Some(position) = position {
+                if clauses > 0 {
+                    f.write_str("; ")?;
+                }
+
+                write!(f, "{keyword} {}", position.0)?;
+                clauses += 1;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn fmt_selector(Selector(types, tags): &Selector<String>, f: &mut Formatter<'_>) -> fmt::Result {
+    let tags = tags.iter().flatten();
+
+    match types.len() {
+        0 => {}
+        1 => types.iter().try_for_each(|ty| fmt_type(ty, f))?,
+        _ => {
+            f.write_str("(")?;
+
+            for (index, ty) in types.iter().enumerate() {
+                if index > 0 {
+                    f.write_str(" | ")?;
+                }
+
+                fmt_type(ty, f)?;
+            }
+
+            f.write_str(")")?;
+        }
+    }
+
+    for (index, tag) in tags.enumerate() {
+        if index > 0 || !types.is_empty() {
+            f.write_str(" & ")?;
+        }
+
+        f.write_str("tag:")?;
+        fmt_value(&tag.0, f)?;
+    }
+
+    Ok(())
+}
+
+fn fmt_type(
+    TypeSelector(name, versions): &TypeSelector<String>,
+    f: &mut Formatter<'_>,
+) -> fmt::Result {
+    // A name which reads as a tag is quoted, so that it parses back as a type.
+    if name.0.starts_with("tag:") {
+        write!(f, "\"{}\"", escape(&name.0))?;
+    } else {
+        fmt_value(&name.0, f)?;
+    }
+
+    let Range { start, end } = versions;
+
+    match (*start, *end) {
+        (Version::MIN, Version::MAX) => Ok(()),
+        (start, Version::MAX) => write!(f, "@{}..", start.0),
+        (start, end) if start.0.checked_add(1) == Some(end.0) => write!(f, "@{}", start.0),
+        (Version::MIN, end) => write!(f, "@..{}", end.0),
+        (start, end) => write!(f, "@{}..{}", start.0, end.0),
+    }
+}
+
+fn fmt_value(value: &str, f: &mut Formatter<'_>) -> fmt::Result {
+    if !value.is_empty() && !value.contains(is_reserved) {
+        f.write_str(value)
+    } else {
+        write!(f, "\"{}\"", escape(value))
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Span
+
+/// The span of a query's input (as a range of byte offsets) at which it could
+/// not be parsed, attached to the parse's error report. Find it with
+/// `report.downcast_ref::<Span>()`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Span(pub Range<usize>);
+
+impl Display for Span {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "query span {}..{}", self.0.start, self.0.end)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Parser
+
+struct Parser<'a> {
+    input: &'a str,
+    offset: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { input, offset: 0 }
+    }
+
+    fn query(&mut self) -> Result<Query> {
+        let mut query = Query::new();
+
+        if self.peek().is_none() {
+            return Ok(query);
+        }
+
+        loop {
+            self.peek();
+
+            let start = self.offset;
+
+            if self.keyword("from") {
+                let from = Position::new(self.number()?);
+
+                if query.from.replace(from).is_some() {
+                    return Err(self.error(start..self.offset, "`from` is given more than once"));
+                }
+            } else if self.keyword("batch") {
+                let batch = Position::new(self.number()?);
+
+                if query.batch.replace(batch).is_some() {
+                    return Err(self.error(start..self.offset, "`batch` is given more than once"));
+                }
+            } else {
+                let label = self.label();
+                let selectors = self.selection()?;
+
+                query = match label {
+                    Some((label, span)) => query
+                        .labelled(label, selectors)
+                        .attach_with(|| Span(span.clone()))
+                        .attach_with(|| self.describe(&span, "duplicate label"))?,
+                    None => query.selection(selectors),
+                };
+            }
+
+            if self.peek().is_none() {
+                break;
+            }
+
+            self.expect(';')?;
+        }
+
+        Ok(query)
+    }
+
+    // A label and its `=`, if the next clause starts with one.
+    fn label(&mut self) -> Option<(String, Range<usize>)> {
+        let start = self.offset;
+        let len = self
+            .rest()
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(self.rest().len());
+        let label = &self.input[start..start + len];
+
+        self.offset += len;
+
+        if is_label(label) && self.eat('=') {
+            return Some((label.to_owned(), start..start + len));
+        }
+
+        self.offset = start;
+
+        None
+    }
+
+    fn selection(&mut self) -> Result<Vec<Selector<String>>> {
+        let mut selectors = vec![self.selector()?];
+
+        while self.eat('|') {
+            selectors.push(self.selector()?);
+        }
+
+        Ok(selectors)
+    }
+
+    fn selector(&mut self) -> Result<Selector<String>> {
+        let mut types = None;
+        let mut tags = Vec::new();
+
+        loop {
+            self.peek();
+
+            let start = self.offset;
+
+            match self.factor()? {
+                Factor::Tag(tag) => tags.push(tag),
+                Factor::Types(group) => {
+                    if types.replace(group).is_some() {
+                        return Err(self.error(
+                            start..self.offset,
+                            "a selector has one type or group of types: combine them as `(A | B)`",
+                        ));
+                    }
+                }
+            }
+
+            if !self.eat('&') {
+                break;
+            }
+        }
+
+        Ok(match (types, tags.is_empty()) {
+            (None, _) => Selector::tags(tags),
+            (Some(types), true) => Selector::types(types),
+            (Some(types), false) => Selector::types_and_tags(types, tags),
+        })
+    }
+
+    fn factor(&mut self) -> Result<Factor> {
+        if self.eat('(') {
+            let mut group = BTreeSet::from([self.ty()?]);
+
+            while self.eat('|') {
+                group.insert(self.ty()?);
+            }
+
+            self.expect(')')?;
+
+            return Ok(Factor::Types(group));
+        }
+
+        self.peek();
+
+        if self.literal("tag:") {
+            let start = self.offset;
+            let tag = self.value("a tag")?;
+            let span = start..self.offset;
+
+            return Tag::new(tag)
+                .attach_with(|| Span(span.clone()))
+                .attach_with(|| self.describe(&span, "invalid tag"))
+                .map(Factor::Tag);
+        }
+
+        self.ty().map(|ty| Factor::Types(BTreeSet::from([ty])))
+    }
+
+    fn ty(&mut self) -> Result<TypeSelector<String>> {
+        self.peek();
+
+        let start = self.offset;
+        let name = self.value("a type name")?;
+        let span = start..self.offset;
+        let versions = if self.literal("@") {
+            self.versions()?
+        } else {
+            Version::MIN..Version::MAX
+        };
+
+        TypeSelector::with_versions(name, versions)
+            .attach_with(|| Span(span.clone()))
+            .attach_with(|| self.describe(&span, "invalid type name"))
+    }
+
+    fn versions(&mut self) -> Result<Range<Version>> {
+        if self.literal("..") {
+            return Ok(Version::MIN..self.version()?);
+        }
+
+        let start = self.offset;
+        let from = self.version()?;
+
+        if !self.literal("..") {
+            let to = from.0.checked_add(1).ok_or_else(|| {
+                self.error(start..self.offset, "the maximum version cannot be selected")
+            })?;
+
+            return Ok(from..Version::new(to));
+        }
+
+        if self.rest().starts_with(|c: char| c.is_ascii_digit()) {
+            Ok(from..self.version()?)
+        } else {
+            Ok(from..Version::MAX)
+        }
+    }
+
+    fn version(&mut self) -> Result<Version> {
+        self.number().map(Version::new)
+    }
+}
+
+impl Parser<'_> {
+    // Skip whitespace, returning the next character.
+    fn peek(&mut self) -> Option<char> {
+        let rest = self.rest();
+
+        self.offset += rest.len() - rest.trim_start().len();
+        self.rest().chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let eaten = self.peek() == Some(c);
+
+        if eaten {
+            self.offset += c.len_utf8();
+        }
+
+        eaten
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("expected `{c}`")))
+        }
+    }
+
+    // A literal at the current offset, without skipping whitespace.
+    fn literal(&mut self, literal: &str) -> bool {
+        let matched = self.rest().starts_with(literal);
+
+        if matched {
+            self.offset += literal.len();
+        }
+
+        matched
+    }
+
+    // The keyword `keyword`, as a whole word followed by a number (so a type
+    // of the same name can still be selected without quoting).
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let matched = self.rest().strip_prefix(keyword).is_some_and(|rest| {
+            let number = rest.trim_start();
+
+            number.len() < rest.len() && number.starts_with(|c: char| c.is_ascii_digit())
+        });
+
+        if matched {
+            self.offset += keyword.len();
+        }
+
+        matched
+    }
+
+    fn number<T>(&mut self) -> Result<T>
+    where
+        T: FromStr,
+    {
+        self.peek();
+
+        let start = self.offset;
+        let digits = self
+            .rest()
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(self.rest().len());
+
+        self.offset += digits;
+
+        match self.input[start..self.offset].parse() {
+            Ok(number) => Ok(number),
+            Err(_) if digits == 0 => Err(self.unexpected("expected a number")),
+            Err(_) => Err(self.error(start..self.offset, "number out of range")),
+        }
+    }
+
+    fn value(&mut self, expected: &str) -> Result<String> {
+        if self.rest().starts_with('"') {
+            return self.quoted();
+        }
+
+        let len = self.rest().find(is_reserved).unwrap_or(self.rest().len());
+
+        if len == 0 {
+            return Err(self.unexpected(&format!("expected {expected}")));
+        }
+
+        let value = self.rest()[..len].to_owned();
+
+        self.offset += len;
+
+        Ok(value)
+    }
+
+    fn quoted(&mut self) -> Result<String> {
+        let start = self.offset;
+        let mut value = String::new();
+        let mut chars = self.rest().char_indices().skip(1);
+
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.offset += index + 1;
+
+                    return Ok(value);
+                }
+                '\\' => {
+                    if let Some((_, c @ ('"' | '\\'))) = chars.next() {
+                        value.push(c);
+                    } else {
+                        let escape = start + index;
+
+                        return Err(self.error(escape..escape + 1, "expected `\\\"` or `\\\\`"));
+                    }
+                }
+                c => value.push(c),
+            }
+        }
+
+        Err(self.error(start..self.input.len(), "unterminated string"))
+    }
+
+    fn rest(&self) -> &str {
+        &self.input[self.offset..]
+    }
+
+    // The input, with the span marked beneath it.
+    fn describe(&self, span: &Range<usize>, message: &str) -> String {
+        let indent = self.input[..span.start].chars().count();
+        let width = self.input[span.clone()].chars().count().max(1);
+
+        format!(
+            "{message}:\n  {}\n  {}{}",
+            self.input,
+            " ".repeat(indent),
+            "^".repeat(width)
+        )
+    }
+
+    // An error at the next character (or the end of the input).
+    fn unexpected(&self, message: &str) -> Report<Error> {
+        let end = self
+            .rest()
+            .chars()
+            .next()
+            .map_or(self.offset, |c| self.offset + c.len_utf8());
+
+        self.error(self.offset..end, message)
+    }
+
+    fn error(&self, span: Range<usize>, message: &str) -> Report<Error> {
+        let description = self.describe(&span, message);
+
+        Report::new(Error).attach(Span(span)).attach(description)
+    }
+}
+
+enum Factor {
+    Tag(Tag<String>),
+    Types(BTreeSet<TypeSelector<String>>),
+}
+
+// Whether `c` cannot appear in a value unquoted.
+fn is_reserved(c: char) -> bool {
+    c.is_whitespace() || ";|&()@=\"".contains(c)
+}
+
+fn is_label(label: &str) -> bool {
+    label.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+// =================================================================================================
+// Tests
+// =================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Query,
+        Span,
+    };
+    use crate::{
+        event::{
+            Tag,
+            Version,
+        },
+        stream::{
+            Position,
+            operate::select::{
+                Selector,
+                TypeSelector,
+            },
+        },
+    };
+
+    fn ty(name: &str, from: u8, to: u8) -> TypeSelector<String> {
+        TypeSelector::with_versions(name, Version::new(from)..Version::new(to)).unwrap()
+    }
+
+    fn tag(tag: &str) -> Tag<String> {
+        Tag::new(tag).unwrap()
+    }
+
+    fn parse(query: &str) -> Query {
+        Query::parse(query).unwrap()
+    }
+
+    #[test]
+    fn parses_the_empty_query_as_every_event() {
+        assert_eq!(parse(""), Query::new());
+        assert_eq!(parse("  "), Query::new());
+    }
+
+    #[test]
+    fn parses_types_tags_versions_and_labels() {
+        assert_eq!(
+            parse("sel0 = (Enrolled@1.. | Dropped) & tag:course:1; from 100"),
+            Query::new()
+                .labelled("sel0", [Selector::types_and_tags(
+                    [ty("Enrolled", 1, 255), ty("Dropped", 0, 255)],
+                    [tag("course:1")],
+                )])
+                .unwrap()
+                .from(Position::new(100)),
+        );
+        assert_eq!(
+            parse("A@2 | B@..3 & tag:x & tag:y | C@1..4; tag:z"),
+            Query::new()
+                .selection([
+                    Selector::types([ty("A", 2, 3)]),
+                    Selector::types_and_tags([ty("B", 0, 3)], [tag("x"), tag("y")]),
+                    Selector::types([ty("C", 1, 4)]),
+                ])
+                .selection([Selector::tags([tag("z")])]),
+        );
+    }
+
+    #[test]
+    fn parses_quoted_values_and_names_like_keywords() {
+        assert_eq!(
+            parse(r#""Course Renamed" & tag:"title:a \"b\"" | from | "tag:x""#),
+            Query::new().selection([
+                Selector::types_and_tags([ty("Course Renamed", 0, 255)], [tag("title:a \"b\"")]),
+                Selector::types([ty("from", 0, 255)]),
+                Selector::types([ty("tag:x", 0, 255)]),
+            ]),
+        );
+    }
+
+    #[test]
+    fn finds_labelled_selections_by_index() {
+        let query = parse("first = A; B; third = tag:c");
+
+        assert_eq!(query.index("first"), Some(0));
+        assert_eq!(query.index("third"), Some(2));
+        assert_eq!(query.index("second"), None);
+    }
+
+    #[test]
+    fn displays_queries_which_parse_back() {
+        for query in [
+            "",
+            "from 7",
+            "batch 3",
+            "A; from 2; batch 3",
+            "A",
+            "sel0 = (Dropped | Enrolled@1..) & tag:course:1; from 100",
+            "A@2 | B@..3 & tag:x & tag:y | C@1..4; tag:z",
+            r#""Course Renamed" & tag:"title:a \"b\"" | from | "tag:x" | "a=b""#,
+        ] {
+            assert_eq!(parse(query).to_string(), query);
+            assert_eq!(parse(&parse(query).to_string()), parse(query));
+        }
+
+        assert_eq!(
+            parse(" A@0..  &tag:x ;  from 3 ").to_string(),
+            "A & tag:x; from 3"
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_queries_with_the_span_at_fault() {
+        for (query, span) in [
+            ("Opened &", 8..8),
+            ("Opened & Closed", 9..15),
+            ("(Opened | Closed", 16..16),
+            ("Opened@x", 7..8),
+            ("Opened@255", 7..10),
+            ("Opened@256", 7..10),
+            ("Opened; from 1; from 2", 16..22),
+            ("batch 1; batch 2", 9..16),
+            ("tag:\"open", 4..9),
+            ("tag:\" open\"", 4..11),
+            ("Opened Closed", 7..8),
+            ("a = A; a = B", 7..8),
+        ] {
+            let report = Query::parse(query).expect_err(query);
+
+            assert_eq!(
+                report.downcast_ref::<Span>(),
+                Some(&Span(span)),
+                "{query}: {report:?}"
+            );
+        }
+    }
+}
@@ -5,6 +5,12 @@
 use std::{
     cmp::Ordering,
     collections::BTreeSet,
+    fmt::{
+        self,
+        Debug,
+        Formatter,
+    },
+    iter,
     ops::{
         Index,
         Range,
@@ -12,7 +18,11 @@ use std::{
         RangeFull,
         RangeTo,
     },
-    sync::SyncView,
+    sync::{
+        Arc,
+        SyncView,
+    },
+    time::Instant,
 };
 
 use derive_more::{
@@ -32,9 +42,18 @@ use crate::{
     },
     stream::{
         Metadata,
+        observe::{
+            Observers,
+            Scan,
+        },
         operate::{
             Condition,
             Selection,
+            explain::{
+                Analysis,
+                Counters,
+                Plan,
+            },
         },
         store::{
             Store,
@@ -52,19 +71,85 @@ pub trait Select {
     /// Run `condition` as a query, yielding each matching event paired with the
     /// [`Mask`] of which selections it satisfied.
     fn select(&self, condition: Condition) -> SelectIter;
+
+    /// The [`Plan`] `condition` is lowered to — the full-scan `Events` path,
+    /// or the `Indices` iterator tree with the type and tag hashes at each
+    /// leaf — without running it.
+    ///
+    /// The default describes the full scan (filtered, if `condition` has
+    /// selections) of an implementor with no indices to plan over.
+    fn explain(&self, condition: Condition) -> Plan {
+        let from = condition.position;
+
+        match condition.batch {
+            Some(position) => Plan::Batch { from, position },
+            None if condition.selections.is_empty() => Plan::Events { from },
+            None => Plan::Filter { from },
+        }
+    }
+
+    /// Run `condition` as [`select`](Select::select) would, to completion and
+    /// discarding the events, counting the index postings touched, the seeks
+    /// performed, and the events materialised.
+    ///
+    /// The default runs `select`, counting the events it yields, against the
+    /// plan from [`explain`](Select::explain).
+    fn analyze(&self, condition: Condition) -> Result<Analysis> {
+        let started = Instant::now();
+        let plan = self.explain(condition.clone());
+        let mut events = 0;
+
+        for event in self.select(condition) {
+            event?;
+            events += 1;
+        }
+
+        Ok(Analysis {
+            plan,
+            postings: 0,
+            seeks: 0,
+            events,
+            duration: started.elapsed(),
+        })
+    }
 }
 
 impl Select for Store {
+    fn explain(&self, condition: Condition) -> Plan {
+        match condition.batch {
+            Some(position) => Plan::Batch {
+                from: condition.position,
+                position,
+            },
+            None => Store::explain(self, &condition.selections, condition.position),
+        }
+    }
+
+    fn analyze(&self, condition: Condition) -> Result<Analysis> {
+        match condition.batch {
+            Some(batch) => self.analyze_batch(&condition.selections, batch, condition.position),
+            None => Store::analyze(self, &condition.selections, condition.position),
+        }
+    }
+
     fn select(&self, condition: Condition) -> SelectIter {
         let Condition {
+            batch,
             position,
             selections,
         } = condition;
 
         // The store iterates the coarse union of every selector across every
         // selection (the candidate set); the per-selection mask is then computed
-        // for each candidate by `SelectIter`.
-        let iter = self.iterate(&selections, position);
+        // for each candidate by `SelectIter`. Finding a batch's bounds reads
+        // the stream, so may fail before any event is yielded.
+        let iter = match batch {
+            Some(batch) => match self.batch(&selections, batch, position) {
+                Ok(iter) => iter,
+                Err(err) => return SelectIter::from_masked(iter::once(Err(err))),
+            },
+            None => self.iterate(&selections, position),
+        };
 
         SelectIter::new(iter, selections)
     }
@@ -78,24 +163,69 @@ impl Select for Store {
 /// with its per-selection [`Mask`].
 #[derive(Debug)]
 pub struct SelectIter {
-    iter: SyncView<StoreIter>,
+    iter: SyncView<Source>,
+    scan: Option<Scan>,
     selections: Vec<Selection>,
 }
 
 impl SelectIter {
     pub(crate) fn new(iter: StoreIter, selections: Vec<Selection>) -> Self {
         Self {
-            iter: SyncView::new(iter),
+            iter: SyncView::new(Source::Store(iter)),
+            scan: None,
             selections,
         }
     }
-}
 
-impl DoubleEndedIterator for SelectIter {
-    fn next_back(&mut self) -> Option<Self::Item> {
-        let event = self.iter.as_mut().next_back()?;
+    /// A select over events which arrive already masked from a source other
+    /// than a local store, such as the client of a remote stream. The events
+    /// are yielded as `events` yields them.
+    pub fn from_masked<I>(events: I) -> Self
+    where
+        I: DoubleEndedIterator<Item = Result<EventAndMask>> + Send + 'static,
+    {
+        Self {
+            iter: SyncView::new(Source::Masked(Box::new(events))),
+            scan: None,
+            selections: Vec::new(),
+        }
+    }
+
+    /// Report this select to `observers`: started now, and finished (with its
+    /// scanned and yielded counts) when the iterator is dropped. Unobserved
+    /// selects skip the bookkeeping entirely.
+    pub(crate) fn observed(mut self, observers: &Observers) -> Self {
+        if !observers.is_empty() {
+            let counters = match self.iter.as_mut() {
+                Source::Store(iter) => {
+                    let counters = Arc::new(Counters::default());
+
+                    iter.instrument(&counters);
+
+                    Some(counters)
+                }
+                Source::Masked(_) => None,
+            };
+
+            self.scan = Some(Scan::start(
+                observers.clone(),
+                self.selections.len(),
+                counters,
+            ));
+        }
+
+        self
+    }
+
+    fn masked(
+        &mut self,
+        event: Option<Result<Event<Metadata, u64>>>,
+    ) -> Option<Result<EventAndMask>> {
+        if let (Some(scan), Some(event)) = (&mut self.scan, &event) {
+            scan.read(event.is_ok());
+        }
 
-        Some(event.map(|event| {
+        Some(event?.map(|event| {
             let mask = mask(&self.selections, &event);
 
             EventAndMask::new(event, mask)
@@ -103,17 +233,43 @@ impl DoubleEndedIterator for SelectIter {
     }
 }
 
+impl DoubleEndedIterator for SelectIter {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let event = match self.iter.as_mut() {
+            Source::Store(iter) => iter.next_back(),
+            Source::Masked(iter) => return iter.next_back(),
+        };
+
+        self.masked(event)
+    }
+}
+
 impl Iterator for SelectIter {
     type Item = Result<EventAndMask>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let event = self.iter.as_mut().next()?;
+        let event = match self.iter.as_mut() {
+            Source::Store(iter) => iter.next(),
+            Source::Masked(iter) => return iter.next(),
+        };
 
-        Some(event.map(|event| {
-            let mask = mask(&self.selections, &event);
+        self.masked(event)
+    }
+}
 
-            EventAndMask::new(event, mask)
-        }))
+// Where a select's events come from: a local store, whose events are masked
+// as they are read, or a source which masks its own.
+enum Source {
+    Store(StoreIter),
+    Masked(Box<dyn DoubleEndedIterator<Item = Result<EventAndMask>> + Send>),
+}
+
+impl Debug for Source {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Store(iter) => f.debug_tuple("Store").field(iter).finish(),
+            Self::Masked(_) => f.debug_tuple("Masked").finish_non_exhaustive(),
+        }
     }
 }
 
@@ -151,44 +307,58 @@ impl Index<usize> for Mask {
     }
 }
 
-// Compute, for a queried event, which of `selections` it satisfies. A selection
-// matches if any of its selectors matches; a selector matches when the event's
-// type-name equals one of the selector's type-names with the event's version in
-// that type's range, AND (if the selector carries tags) all those tags are
-// present on the event. This mirrors the index-side matching, re-checked here
-// on the hashed (`u64`) representation to recover which selection(s) hit.
+// Compute, for a queried event, which of `selections` it satisfies (see
+// `selected`).
 fn mask(selections: &[Selection], event: &Event<Metadata, u64>) -> Mask {
-    let name = event.facets().ty().name();
-    let version = event.facets().ty().version();
-    let tags = event.facets().tags();
-
     Mask::new(
         selections
             .iter()
-            .map(|selection| {
-                selection
-                    .selectors
-                    .iter()
-                    .any(|Selector(types, selector_tags)| {
-                        types
-                            .iter()
-                            .any(|ty| &ty.0 == name && ty.1.contains(&version))
-                            && selector_tags
-                                .as_ref()
-                                .is_none_or(|required| required.is_subset(tags))
-                    })
-            })
+            .map(|selection| selected(selection, event))
             .collect(),
     )
 }
 
+// Whether `selection` matches a queried event. A selection matches if any of
+// its selectors matches; a selector matches when the event's type-name equals
+// one of the selector's type-names with the event's version in that type's
+// range, AND (if the selector carries tags) all those tags are present on the
+// event. This mirrors the index-side matching, re-checked here on the hashed
+// (`u64`) representation to recover which selection(s) hit (and to filter a
+// full scan).
+pub(crate) fn selected(selection: &Selection, event: &Event<Metadata, u64>) -> bool {
+    let name = event.facets().ty().name();
+    let version = event.facets().ty().version();
+    let tags = event.facets().tags();
+
+    selection
+        .selectors
+        .iter()
+        .any(|Selector(types, selector_tags)| {
+            // A selector without types selects by its tags alone, if it has
+            // any.
+            let typed = if types.is_empty() {
+                selector_tags.as_ref().is_some_and(|tags| !tags.is_empty())
+            } else {
+                types
+                    .iter()
+                    .any(|ty| &ty.0 == name && ty.1.contains(&version))
+            };
+
+            typed
+                && selector_tags
+                    .as_ref()
+                    .is_none_or(|required| required.is_subset(tags))
+        })
+}
+
 // -------------------------------------------------------------------------------------------------
 
 // Selector
 
 /// A single match clause: an event matches when its type is any of `types` AND
-/// (if present) it carries all of `tags`.
-#[derive(Debug)]
+/// (if present) it carries all of `tags`. A selector with tags but no types
+/// matches events of any type carrying the tags.
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Selector<T>(
     pub(crate) BTreeSet<TypeSelector<T>>,
     pub(crate) Option<BTreeSet<Tag<T>>>,
@@ -204,6 +374,15 @@ impl Selector<String> {
         Self(types.into_iter().collect(), None)
     }
 
+    /// A selector matching events of any type which carry all of `tags` (and
+    /// no events, if `tags` is empty).
+    pub fn tags<J>(tags: J) -> Self
+    where
+        J: IntoIterator<Item = Tag<String>>,
+    {
+        Self(BTreeSet::new(), Some(tags.into_iter().collect()))
+    }
+
     /// A selector matching events whose type is any of `types` AND which carry
     /// all of `tags`.
     pub fn types_and_tags<I, J>(types: I, tags: J) -> Self
@@ -239,7 +418,7 @@ selector_from!(String, u64);
 // Type Selector
 
 /// A type-name plus the range of versions to match.
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct TypeSelector<T>(pub(crate) Name<T>, pub(crate) Range<Version>);
 
 impl TypeSelector<String> {
@@ -0,0 +1,752 @@
+//! Read replicas by log shipping: a [`Shipper`] reads the events committed to
+//! a leader stream and transmits them, in position order, as [`Shipment`]s
+//! over a pluggable [`transport`]; a [`Follower`] applies them to a stream in
+//! its own database, preserving their original positions and timestamps, and
+//! tracks how far it is behind the leader as a [`Watermark`]. The follower
+//! serves selects but rejects appends, until it is promoted.
+
+pub mod transport;
+
+use std::{
+    collections::BTreeMap,
+    result,
+    sync::{
+        Arc,
+        atomic::{
+            AtomicU64,
+            Ordering,
+        },
+    },
+};
+
+use bytes::{
+    Buf as _,
+    BufMut as _,
+    TryGetError,
+};
+use derive_more::Debug;
+use error_stack::{
+    Report,
+    ResultExt as _,
+};
+use fancy_constructor::new;
+
+use crate::{
+    error::{
+        Error,
+        Result,
+    },
+    event::{
+        Data,
+        Event,
+        Facets,
+        Name,
+        Tag,
+        Type,
+        Version,
+    },
+    stream::{
+        Metadata,
+        Position,
+        Reader,
+        Stream,
+        Timestamp,
+        operate::{
+            Condition,
+            append::{
+                Append,
+                AppendReceipt,
+            },
+            explain::{
+                Analysis,
+                Plan,
+            },
+            select::{
+                Select,
+                SelectIter,
+            },
+        },
+        replicate::transport::{
+            Receive,
+            Transmit,
+        },
+    },
+};
+
+// =================================================================================================
+// Replicate
+// =================================================================================================
+
+// Constants
+
+// The most events a `Shipper` puts in one shipment by default.
+static SHIPMENT_LEN: usize = 1024;
+
+// -------------------------------------------------------------------------------------------------
+
+// Shipment
+
+/// A run of consecutive events committed to a leader stream, as shipped to a
+/// [`Follower`]: the events themselves (with their positions and timestamps),
+/// the string forms of their type names and tags (so the follower's catalog
+/// can name them), and the leader's length when they were shipped.
+#[derive(new, Clone, Debug)]
+#[new(vis())]
+pub struct Shipment {
+    events: Vec<Event<Metadata, u64>>,
+    head: Position,
+    tags: BTreeMap<u64, String>,
+    types: BTreeMap<u64, String>,
+}
+
+impl Shipment {
+    /// The shipped events, in position order.
+    #[must_use]
+    pub fn events(&self) -> &[Event<Metadata, u64>] {
+        &self.events
+    }
+
+    /// The leader's `next` position (its length) when the shipment was made.
+    #[must_use]
+    pub fn head(&self) -> Position {
+        self.head
+    }
+}
+
+impl Shipment {
+    /// Encode the shipment in its wire format, for transports which carry
+    /// bytes. All integers are big-endian:
+    ///
+    /// - the head position (`u64`)
+    /// - the event count (`u32`), then per event: position (`u64`), timestamp
+    ///   (`u64`), batch (`u64`, its top bit set if the event's payload has
+    ///   been redacted), type name hash (`u64`), version (`u8`), tag count
+    ///   (`u8`), tag hashes (`u64` each), data length (`u32`) and data
+    /// - the type name count (`u32`), then per name: hash (`u64`), length
+    ///   (`u32`) and UTF-8 bytes
+    /// - the tag count and tags, as the type names
+    #[must_use]
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+
+        bytes.put_u64(self.head.0);
+        bytes.put_u32(len(self.events.len()));
+
+        for event in &self.events {
+            let ty = event.facets().ty();
+            let tags = event.facets().tags();
+
+            bytes.put_u64(event.meta().position().0);
+            bytes.put_u64(event.meta().timestamp().0);
+            bytes.put_u64(event.meta().marked_batch());
+            bytes.put_u64(ty.name().0);
+            bytes.put_u8(ty.version().0);
+            bytes.put_u8(
+                u8::try_from(tags.len()).expect("tag count > u8::MAX (rejected at append)"),
+            );
+
+            for tag in tags {
+                bytes.put_u64(tag.0);
+            }
+
+            bytes.put_u32(len(event.data().as_ref().len()));
+            bytes.put_slice(event.data().as_ref());
+        }
+
+        for names in [&self.types, &self.tags] {
+            bytes.put_u32(len(names.len()));
+
+            for (hash, name) in names {
+                bytes.put_u64(*hash);
+                bytes.put_u32(len(name.len()));
+                bytes.put_slice(name.as_bytes());
+            }
+        }
+
+        bytes
+    }
+
+    /// Decode a shipment from its wire format (see
+    /// [`encode`](Shipment::encode)), rejecting truncated or trailing bytes.
+    pub fn decode(mut bytes: &[u8]) -> Result<Self> {
+        let bytes = &mut bytes;
+        let head = Position::new(get(bytes, |bytes| bytes.try_get_u64())?);
+        let count = get(bytes, |bytes| bytes.try_get_u32())?;
+        let mut events = Vec::new();
+
+        for _ in 0..count {
+            let position = Position::new(get(bytes, |bytes| bytes.try_get_u64())?);
+            let timestamp = Timestamp::new(get(bytes, |bytes| bytes.try_get_u64())?);
+            let batch = get(bytes, |bytes| bytes.try_get_u64())?;
+            let name = Name(get(bytes, |bytes| bytes.try_get_u64())?);
+            let version = Version(get(bytes, |bytes| bytes.try_get_u8())?);
+            let tags = (0..get(bytes, |bytes| bytes.try_get_u8())?)
+                .map(|_| get(bytes, |bytes| bytes.try_get_u64()).map(Tag))
+                .collect::<Result<_>>()?;
+            let data = Data::new(slice(bytes)?)?;
+            let facets = Facets::new(Type::new(name, version), tags);
+
+            events.push(Event::new(
+                data,
+                facets,
+                Metadata::marked(position, timestamp, batch),
+            ));
+        }
+
+        let types = names(bytes)?;
+        let tags = names(bytes)?;
+
+        if !bytes.is_empty() {
+            return Err(Report::new(Error).attach("shipment has trailing bytes"));
+        }
+
+        Ok(Self::new(events, head, tags, types))
+    }
+}
+
+fn len(len: usize) -> u32 {
+    u32::try_from(len).expect("shipment length > u32::MAX")
+}
+
+fn get<T, F>(bytes: &mut &[u8], get: F) -> Result<T>
+where
+    F: FnOnce(&mut &[u8]) -> result::Result<T, TryGetError>,
+{
+    get(bytes)
+        .change_context(Error)
+        .attach("shipment is truncated")
+}
+
+fn slice(bytes: &mut &[u8]) -> Result<Vec<u8>> {
+    let len = get(bytes, |bytes| bytes.try_get_u32())? as usize;
+
+    if bytes.len() < len {
+        return Err(Report::new(Error).attach("shipment is truncated"));
+    }
+
+    let (slice, rest) = bytes.split_at(len);
+
+    *bytes = rest;
+
+    Ok(slice.to_vec())
+}
+
+fn names(bytes: &mut &[u8]) -> Result<BTreeMap<u64, String>> {
+    (0..get(bytes, |bytes| bytes.try_get_u32())?)
+        .map(|_| {
+            let hash = get(bytes, |bytes| bytes.try_get_u64())?;
+            let name = String::from_utf8(slice(bytes)?)
+                .change_context(Error)
+                .attach("shipped name is not UTF-8")?;
+
+            Ok((hash, name))
+        })
+        .collect()
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Shipper
+
+/// The leader side of replication: ships the events committed to a stream,
+/// read through a [`Reader`] of it, to a follower over a [`Transmit`]
+/// transport. Each [`ship`](Shipper::ship) sends everything committed since
+/// the last, so call it after appends (or on an interval) to keep the
+/// follower current.
+#[derive(new, Debug)]
+pub struct Shipper<T> {
+    #[new(val(Position::MIN))]
+    cursor: Position,
+    #[new(val(SHIPMENT_LEN))]
+    limit: usize,
+    reader: Reader,
+    transmitter: T,
+}
+
+impl<T> Shipper<T> {
+    /// Ship from `position` rather than from the start of the stream: from the
+    /// follower's length, to resume a follower which already has the events
+    /// before it.
+    #[must_use]
+    pub fn from(mut self, position: Position) -> Self {
+        self.cursor = position;
+        self
+    }
+
+    /// The most events to put in one shipment. Defaults to 1024.
+    #[must_use]
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit.max(1);
+        self
+    }
+}
+
+impl<T> Shipper<T>
+where
+    T: Transmit,
+{
+    /// Transmit every event committed since the last shipment, in position
+    /// order, returning the number of events shipped. If transmission fails,
+    /// the events not yet shipped are shipped by the next call.
+    pub fn ship(&mut self) -> Result<u64> {
+        let store = &self.reader.store;
+        let mut shipped = 0;
+
+        loop {
+            let events = store
+                .events
+                .iterate(Some(self.cursor))
+                .take(self.limit)
+                .collect::<Result<Vec<_>>>()?;
+
+            let Some(last) = events.last() else {
+                return Ok(shipped);
+            };
+
+            let cursor = last.meta().position() + 1;
+            let count = events.len() as u64;
+            let (types, tags) = store.names.resolve(&events)?;
+            let head = store.len().map(Position::new)?;
+
+            self.transmitter
+                .transmit(Shipment::new(events, head, tags, types))?;
+
+            self.cursor = cursor;
+            shipped += count;
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Follower
+
+/// A read replica of a leader stream: applies the [`Shipment`]s received from
+/// a [`Shipper`] to a stream of its own (opened in its own database), so its
+/// events keep the positions and timestamps they were committed with on the
+/// leader. Selects run against the replica; appends are rejected, as the
+/// leader is the only writer — until the follower is
+/// [`promote`](Follower::promote)d.
+#[derive(Debug)]
+pub struct Follower {
+    stream: Stream,
+    watermark: Watermark,
+}
+
+impl Follower {
+    /// Follow into `stream`, resuming after the events it already holds (so a
+    /// follower reopened on the same stream continues where it stopped).
+    #[must_use]
+    pub fn new(stream: Stream) -> Self {
+        let watermark = Watermark::default();
+
+        watermark.0.applied.store(stream.len(), Ordering::Release);
+        watermark.0.head.store(stream.len(), Ordering::Release);
+
+        Self { stream, watermark }
+    }
+
+    /// Whether the replica holds no events.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.stream.is_empty()
+    }
+
+    /// The number of events applied to the replica.
+    #[must_use]
+    pub fn len(&self) -> u64 {
+        self.stream.len()
+    }
+
+    /// A cloneable, read-only [`Reader`] of the replica, which sees each
+    /// shipment as it is applied.
+    #[must_use]
+    pub fn reader(&self) -> Reader {
+        Reader::new(
+            self.stream.claim.lock(),
+            self.stream.observers.clone(),
+            self.stream.store.clone(),
+        )
+    }
+
+    /// The replica's [`Watermark`], a live view of its replication lag which
+    /// can be held (and read from other threads) while the follower runs.
+    #[must_use]
+    pub fn watermark(&self) -> Watermark {
+        self.watermark.clone()
+    }
+
+    /// Stop following and take the replica as a writable [`Stream`] (to fail
+    /// over to a standby once the leader is gone).
+    #[must_use]
+    pub fn promote(self) -> Stream {
+        self.stream
+    }
+}
+
+impl Follower {
+    /// Apply `shipment` to the replica, in one atomic commit. Events the
+    /// replica already holds are skipped, so a shipment may be re-applied;
+    /// events which would leave a gap after the replica's last are an error.
+    pub fn apply(&mut self, shipment: Shipment) -> Result<()> {
+        let Shipment {
+            events,
+            head,
+            tags,
+            types,
+        } = shipment;
+
+        let next = self.stream.next;
+        let events = events
+            .into_iter()
+            .filter(|event| event.meta().position() >= next)
+            .collect::<Vec<_>>();
+
+        let timestamp = events.last().map(|event| event.meta().timestamp());
+        let batch = self.stream.storage.batch(self.stream.durability);
+
+        self.stream
+            .store
+            .replicate(batch, events, (types, tags), &mut self.stream.next)
+            .attach("failed to apply shipment")?;
+
+        self.watermark.advance(self.stream.next, head, timestamp);
+
+        Ok(())
+    }
+
+    /// Apply every shipment received from `receiver` until the transport is
+    /// closed (returning `Ok`) or a receive or apply fails.
+    pub fn follow<R>(&mut self, receiver: &mut R) -> Result<()>
+    where
+        R: Receive,
+    {
+        while let Some(shipment) = receiver.receive()? {
+            self.apply(shipment)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Append for Follower {
+    fn append<E>(&mut self, _: E, _: Condition) -> Result<AppendReceipt>
+    where
+        E: IntoIterator<Item = Event<(), String>>,
+        E::IntoIter: Send + 'static,
+    {
+        Err(Report::new(Error).attach("cannot append to a follower: append to its leader"))
+    }
+}
+
+impl Select for Follower {
+    fn select(&self, condition: Condition) -> SelectIter {
+        self.stream.select(condition)
+    }
+
+    fn explain(&self, condition: Condition) -> Plan {
+        self.stream.explain(condition)
+    }
+
+    fn analyze(&self, condition: Condition) -> Result<Analysis> {
+        self.stream.analyze(condition)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Watermark
+
+/// How far a [`Follower`] has replicated: the events it has applied, the
+/// leader's length as of the latest shipment, and the timestamp of the latest
+/// event applied. Cheap to clone; every clone reads the follower's current
+/// marks.
+#[derive(Clone, Debug, Default)]
+pub struct Watermark(#[debug("Marks")] Arc<Marks>);
+
+#[derive(Debug, Default)]
+struct Marks {
+    applied: AtomicU64,
+    head: AtomicU64,
+    timestamp: AtomicU64,
+}
+
+impl Watermark {
+    /// The number of events applied to the replica.
+    #[must_use]
+    pub fn applied(&self) -> u64 {
+        self.0.applied.load(Ordering::Acquire)
+    }
+
+    /// The leader's length as of the latest shipment applied.
+    #[must_use]
+    pub fn head(&self) -> u64 {
+        self.0.head.load(Ordering::Acquire)
+    }
+
+    /// The number of events the replica is known to be behind the leader.
+    /// Known only as of the latest shipment: appends to the leader since then
+    /// are not counted until they are shipped.
+    #[must_use]
+    pub fn lag(&self) -> u64 {
+        self.head().saturating_sub(self.applied())
+    }
+
+    /// The leader timestamp of the latest event applied by the follower, if
+    /// it has applied any (compare with the current time for the lag in
+    /// time, bearing in mind the leader's clock decides whether timestamps
+    /// are monotonic).
+    #[must_use]
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        match self.0.timestamp.load(Ordering::Acquire) {
+            0 => None,
+            nanos => Some(Timestamp::new(nanos)),
+        }
+    }
+
+    fn advance(&self, applied: Position, head: Position, timestamp: Option<Timestamp>) {
+        if let Some(timestamp) = timestamp {
+            self.0.timestamp.store(timestamp.0, Ordering::Release);
+        }
+
+        self.0
+            .head
+            .fetch_max(head.0.max(applied.0), Ordering::AcqRel);
+        self.0.applied.store(applied.0, Ordering::Release);
+    }
+}
+
+// =================================================================================================
+// Tests
+// =================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::{
+        Follower,
+        Shipment,
+        Shipper,
+        transport::{
+            Receive as _,
+            channel,
+        },
+    };
+    use crate::{
+        event::{
+            Data,
+            Event,
+            Facets,
+            Name,
+            Tag,
+            Type,
+            Version,
+        },
+        stream::{
+            Backend,
+            Position,
+            Stream,
+            operate::{
+                Condition,
+                append::Append as _,
+                select::Select as _,
+            },
+        },
+        utils::temp_path,
+    };
+
+    fn stream() -> Stream {
+        Stream::builder(temp_path())
+            .backend(Backend::Memory)
+            .open()
+            .unwrap()
+    }
+
+    fn event(identifier: &str, tags: &[&str]) -> Event<(), String> {
+        let ty = Type::new(Name::new(identifier).unwrap(), Version::new(0));
+        let tags = tags
+            .iter()
+            .map(|tag| Tag::new(*tag).unwrap())
+            .collect::<BTreeSet<_>>();
+
+        Event::new(
+            Data::new(b"payload".to_vec()).unwrap(),
+            Facets::new(ty, tags),
+            (),
+        )
+    }
+
+    // Shipped events land on the follower at their leader positions, with
+    // their leader timestamps and names, and the watermark tracks the lag
+    // shipment by shipment.
+    #[test]
+    fn follower_replicates_positions_and_timestamps() {
+        let (leader, mut writer) = stream().split();
+        let (transmitter, mut receiver) = channel::channel(8);
+        let mut shipper = Shipper::new(leader.clone(), transmitter).limit(2);
+        let mut follower = Follower::new(stream());
+        let watermark = follower.watermark();
+
+        writer
+            .append(
+                vec![
+                    event("Invoiced", &["invoice:1"]),
+                    event("Paid", &["invoice:1"]),
+                    event("Invoiced", &["invoice:2"]),
+                ],
+                Condition::new(),
+            )
+            .unwrap();
+
+        assert_eq!(shipper.ship().unwrap(), 3);
+
+        follower
+            .apply(receiver.receive().unwrap().unwrap())
+            .unwrap();
+
+        assert_eq!(
+            (watermark.applied(), watermark.head(), watermark.lag()),
+            (2, 3, 1)
+        );
+
+        follower
+            .apply(receiver.receive().unwrap().unwrap())
+            .unwrap();
+
+        assert_eq!((watermark.applied(), watermark.lag()), (3, 0));
+        assert_eq!(shipper.ship().unwrap(), 0);
+
+        let committed = leader
+            .select(Condition::new())
+            .map(|selected| *selected.unwrap().event.meta())
+            .collect::<Vec<_>>();
+        let applied = follower
+            .reader()
+            .select(Condition::new())
+            .map(|selected| *selected.unwrap().event.meta())
+            .collect::<Vec<_>>();
+
+        assert_eq!(applied, committed);
+        assert_eq!(watermark.timestamp(), Some(committed[2].timestamp()));
+        assert_eq!(
+            follower.reader().catalog().unwrap().types.len(),
+            leader.catalog().unwrap().types.len(),
+        );
+        assert!(
+            follower
+                .reader()
+                .catalog()
+                .unwrap()
+                .tags
+                .iter()
+                .all(|entry| entry.tag.is_some())
+        );
+    }
+
+    // A follower rejects appends, skips events it already holds, and rejects
+    // a shipment which would leave a gap.
+    #[test]
+    fn follower_rejects_appends_and_gaps() {
+        let (leader, mut writer) = stream().split();
+        let (transmitter, mut receiver) = channel::channel(8);
+        let mut follower = Follower::new(stream());
+
+        writer
+            .append(
+                vec![event("Invoiced", &[]), event("Paid", &[])],
+                Condition::new(),
+            )
+            .unwrap();
+
+        Shipper::new(leader.clone(), transmitter.clone())
+            .ship()
+            .unwrap();
+        Shipper::new(leader.clone(), transmitter.clone())
+            .from(Position::new(1))
+            .ship()
+            .unwrap();
+
+        let shipment = receiver.receive().unwrap().unwrap();
+        let gap = receiver.receive().unwrap().unwrap();
+
+        assert!(follower.apply(gap).is_err());
+
+        follower.apply(shipment.clone()).unwrap();
+        follower.apply(shipment).unwrap();
+
+        assert_eq!(follower.len(), 2);
+        assert!(
+            follower
+                .append(vec![event("Invoiced", &[])], Condition::new())
+                .is_err()
+        );
+
+        let mut stream = follower.promote();
+
+        assert!(
+            stream
+                .append(vec![event("Invoiced", &[])], Condition::new())
+                .is_ok()
+        );
+    }
+
+    // A redacted event is shipped with its redaction mark, through the wire
+    // encoding too, so it reads as redacted on the follower.
+    #[test]
+    fn redacted_events_replicate_as_redacted() {
+        let (leader, mut writer) = stream().split();
+        let (transmitter, mut receiver) = channel::channel(8);
+        let mut follower = Follower::new(stream());
+
+        writer
+            .append(
+                vec![event("Invoiced", &[]), event("Paid", &[])],
+                Condition::new(),
+            )
+            .unwrap();
+        writer
+            .redact(Position::new(0), Data::new("void").unwrap(), "scrubbed")
+            .unwrap();
+
+        Shipper::new(leader, transmitter).ship().unwrap();
+
+        let bytes = receiver.receive().unwrap().unwrap().encode();
+
+        follower.apply(Shipment::decode(&bytes).unwrap()).unwrap();
+
+        let redacted = follower
+            .reader()
+            .select(Condition::new())
+            .map(|selected| selected.unwrap().event.meta().redacted())
+            .collect::<Vec<_>>();
+
+        assert_eq!(redacted, [true, false]);
+    }
+
+    // A shipment survives its wire encoding, and truncated bytes are
+    // rejected rather than misread.
+    #[test]
+    fn shipments_round_trip_their_encoding() {
+        let (leader, mut writer) = stream().split();
+        let (transmitter, mut receiver) = channel::channel(8);
+
+        writer
+            .append(
+                vec![event("Invoiced", &["invoice:1", "customer:1"])],
+                Condition::new(),
+            )
+            .unwrap();
+
+        Shipper::new(leader, transmitter).ship().unwrap();
+
+        let bytes = receiver.receive().unwrap().unwrap().encode();
+        let shipment = Shipment::decode(&bytes).unwrap();
+
+        assert_eq!(shipment.encode(), bytes);
+        assert_eq!(shipment.events().len(), 1);
+        assert_eq!(shipment.head(), Position::new(1));
+        assert!(Shipment::decode(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Shipment::decode(&[bytes.as_slice(), &[0]].concat()).is_err());
+    }
+}
@@ -0,0 +1,45 @@
+//! The pluggable carrier of [`Shipment`]s from a [`Shipper`] to a
+//! [`Follower`]: the [`Transmit`] and [`Receive`] traits, with an in-process
+//! [`channel`] and a [`socket`] transport over any byte stream (a local
+//! socket, say).
+//!
+//! [`Shipper`]: crate::stream::replicate::Shipper
+//! [`Follower`]: crate::stream::replicate::Follower
+
+pub mod channel;
+pub mod socket;
+
+use crate::{
+    error::Result,
+    stream::replicate::Shipment,
+};
+
+// =================================================================================================
+// Transport
+// =================================================================================================
+
+// Transmit
+
+/// The leader end of a transport, which a [`Shipper`] transmits each
+/// shipment through, in position order.
+///
+/// [`Shipper`]: crate::stream::replicate::Shipper
+pub trait Transmit {
+    /// Transmit `shipment` to the follower, failing if it cannot be delivered
+    /// (as when the follower end has gone).
+    fn transmit(&mut self, shipment: Shipment) -> Result<()>;
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Receive
+
+/// The follower end of a transport, which a [`Follower`] receives shipments
+/// from, in the order they were transmitted.
+///
+/// [`Follower`]: crate::stream::replicate::Follower
+pub trait Receive {
+    /// Block until the next shipment arrives, returning it, or `None` once
+    /// the leader end has closed.
+    fn receive(&mut self) -> Result<Option<Shipment>>;
+}
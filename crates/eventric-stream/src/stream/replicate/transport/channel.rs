@@ -0,0 +1,68 @@
+//! The in-process transport: a bounded channel carrying [`Shipment`]s between
+//! threads, with no encoding.
+
+use crossbeam::channel;
+use error_stack::ResultExt as _;
+
+use crate::{
+    error::{
+        Error,
+        Result,
+    },
+    stream::replicate::{
+        Shipment,
+        transport::{
+            Receive,
+            Transmit,
+        },
+    },
+};
+
+// =================================================================================================
+// Channel
+// =================================================================================================
+
+/// A channel holding up to `capacity` shipments in flight, as its
+/// [`Transmitter`] and [`Receiver`] ends. A transmit blocks while the channel
+/// is full, so a slow follower applies backpressure to its shipper.
+#[must_use]
+pub fn channel(capacity: usize) -> (Transmitter, Receiver) {
+    let (sender, receiver) = channel::bounded(capacity);
+
+    (Transmitter(sender), Receiver(receiver))
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Transmitter
+
+/// The leader end of a [`channel()`]. Cloneable; the channel closes when every
+/// clone has been dropped.
+#[derive(Clone, Debug)]
+pub struct Transmitter(channel::Sender<Shipment>);
+
+impl Transmit for Transmitter {
+    fn transmit(&mut self, shipment: Shipment) -> Result<()> {
+        self.0
+            .send(shipment)
+            .change_context(Error)
+            .attach("failed to transmit shipment: receiver disconnected")
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Receiver
+
+/// The follower end of a [`channel()`].
+#[derive(Debug)]
+pub struct Receiver(channel::Receiver<Shipment>);
+
+impl Receive for Receiver {
+    fn receive(&mut self) -> Result<Option<Shipment>> {
+        match self.0.recv() {
+            Ok(shipment) => Ok(Some(shipment)),
+            Err(channel::RecvError) => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,202 @@
+//! The socket transport: [`Shipment`]s in their wire format (see
+//! [`Shipment::encode`]), each framed by a big-endian `u32` length, over any
+//! byte stream — a `UnixStream` for a local socket, or a `TcpStream`.
+
+use std::io::{
+    ErrorKind,
+    Read,
+    Write,
+};
+
+use error_stack::{
+    Report,
+    ResultExt as _,
+};
+use fancy_constructor::new;
+
+use crate::{
+    error::{
+        Error,
+        Result,
+    },
+    stream::replicate::{
+        Shipment,
+        transport::{
+            Receive,
+            Transmit,
+        },
+    },
+};
+
+// =================================================================================================
+// Socket
+// =================================================================================================
+
+// Constants
+
+// The largest frame a receiver accepts, so a corrupt length cannot make it
+// allocate without bound.
+static MAX_FRAME_LEN: u32 = 1 << 30;
+
+// -------------------------------------------------------------------------------------------------
+
+// Transmitter
+
+/// The leader end of a socket transport, writing framed shipments to `S`.
+#[derive(new, Debug)]
+#[new(const_fn)]
+pub struct Transmitter<S> {
+    stream: S,
+}
+
+impl<S> Transmit for Transmitter<S>
+where
+    S: Write,
+{
+    fn transmit(&mut self, shipment: Shipment) -> Result<()> {
+        let frame = shipment.encode();
+        let len = u32::try_from(frame.len())
+            .ok()
+            .filter(|len| *len <= MAX_FRAME_LEN)
+            .ok_or_else(|| Report::new(Error).attach("shipment exceeds the maximum frame size"))?;
+
+        self.stream
+            .write_all(&len.to_be_bytes())
+            .and_then(|()| self.stream.write_all(&frame))
+            .and_then(|()| self.stream.flush())
+            .change_context(Error)
+            .attach("failed to transmit shipment")
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Receiver
+
+/// The follower end of a socket transport, reading framed shipments from `S`.
+/// The transport is closed when `S` ends between frames.
+#[derive(new, Debug)]
+#[new(const_fn)]
+pub struct Receiver<S> {
+    stream: S,
+}
+
+impl<S> Receive for Receiver<S>
+where
+    S: Read,
+{
+    fn receive(&mut self) -> Result<Option<Shipment>> {
+        let mut len = [0; size_of::<u32>()];
+
+        match self.stream.read_exact(&mut len) {
+            Ok(()) => {}
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => {
+                return Err(err)
+                    .change_context(Error)
+                    .attach("failed to receive shipment length");
+            }
+        }
+
+        let len = u32::from_be_bytes(len);
+
+        if len > MAX_FRAME_LEN {
+            return Err(Report::new(Error).attach("shipment exceeds the maximum frame size"));
+        }
+
+        let mut frame = vec![0; len as usize];
+
+        self.stream
+            .read_exact(&mut frame)
+            .change_context(Error)
+            .attach("failed to receive shipment")?;
+
+        Shipment::decode(&frame).map(Some)
+    }
+}
+
+// =================================================================================================
+// Tests
+// =================================================================================================
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{
+        collections::BTreeSet,
+        os::unix::net::UnixStream,
+        thread,
+    };
+
+    use super::{
+        Receiver,
+        Transmitter,
+    };
+    use crate::{
+        event::{
+            Data,
+            Event,
+            Facets,
+            Name,
+            Type,
+            Version,
+        },
+        stream::{
+            Backend,
+            Stream,
+            operate::{
+                Condition,
+                append::Append as _,
+            },
+            replicate::{
+                Follower,
+                Shipper,
+            },
+        },
+        utils::temp_path,
+    };
+
+    fn stream() -> Stream {
+        Stream::builder(temp_path())
+            .backend(Backend::Memory)
+            .open()
+            .unwrap()
+    }
+
+    // Shipments cross a local socket to a follower on another thread, which
+    // stops following cleanly when the leader end is closed.
+    #[test]
+    fn shipments_cross_a_local_socket() {
+        let (leading, following) = UnixStream::pair().unwrap();
+        let (reader, mut writer) = stream().split();
+        let mut shipper = Shipper::new(reader, Transmitter::new(leading)).limit(3);
+
+        let follower = thread::spawn(move || {
+            let mut follower = Follower::new(stream());
+
+            follower.follow(&mut Receiver::new(following)).unwrap();
+            follower
+        });
+
+        for _ in 0..4 {
+            let ty = Type::new(Name::new("Invoiced").unwrap(), Version::new(0));
+            let event = Event::new(
+                Data::new(b"payload".to_vec()).unwrap(),
+                Facets::new(ty, BTreeSet::new()),
+                (),
+            );
+
+            writer
+                .append(vec![event.clone(), event], Condition::new())
+                .unwrap();
+        }
+
+        assert_eq!(shipper.ship().unwrap(), 8);
+
+        drop(shipper);
+
+        let follower = follower.join().unwrap();
+
+        assert_eq!(follower.len(), 8);
+        assert_eq!(follower.watermark().lag(), 0);
+    }
+}
@@ -0,0 +1,63 @@
+//! Cardinality [`Statistics`]: how many events a stream holds, and how many
+//! carry each event type name and each tag, from
+//! [`Reader::stats`](crate::stream::Reader::stats). The same counts order the
+//! query's index intersections and choose between the index path and a
+//! filtered full scan.
+
+use std::collections::BTreeMap;
+
+use fancy_constructor::new;
+
+use crate::event::{
+    Name,
+    Tag,
+};
+
+// =================================================================================================
+// Statistics
+// =================================================================================================
+
+/// A snapshot of a stream's cardinalities. Type names and tags are held as
+/// their stable hashes (as stored); look one up by its string form with
+/// [`ty`](Statistics::ty) and [`tag`](Statistics::tag).
+#[derive(new, Clone, Debug, Eq, PartialEq)]
+#[new(const_fn, vis(pub(crate)))]
+pub struct Statistics {
+    events: u64,
+    tags: BTreeMap<u64, u64>,
+    types: BTreeMap<u64, u64>,
+}
+
+impl Statistics {
+    /// The number of events in the stream.
+    #[must_use]
+    pub fn events(&self) -> u64 {
+        self.events
+    }
+
+    /// The number of events carrying `tag`.
+    #[must_use]
+    pub fn tag(&self, tag: &Tag<String>) -> u64 {
+        let tag: Tag<u64> = tag.clone().into();
+
+        self.tags.get(&tag.0).copied().unwrap_or_default()
+    }
+
+    /// Every tag (as its hash) carried by any event, with its count.
+    pub fn tags(&self) -> impl Iterator<Item = (u64, u64)> {
+        self.tags.iter().map(|(hash, count)| (*hash, *count))
+    }
+
+    /// The number of events whose type name is `name` (of any version).
+    #[must_use]
+    pub fn ty(&self, name: &Name<String>) -> u64 {
+        let name: Name<u64> = name.clone().into();
+
+        self.types.get(&name.0).copied().unwrap_or_default()
+    }
+
+    /// Every type name (as its hash) of any event, with its count.
+    pub fn types(&self) -> impl Iterator<Item = (u64, u64)> {
+        self.types.iter().map(|(hash, count)| (*hash, *count))
+    }
+}
@@ -1,32 +1,85 @@
+mod audit;
+mod cardinalities;
 mod events;
+mod forks;
 mod indices;
+mod meta;
+mod names;
+pub(crate) mod storage;
+
+use std::{
+    collections::BTreeMap,
+    ops::Range,
+    sync::Arc,
+    time::Instant,
+};
 
 use error_stack::{
     Report,
     ResultExt as _,
 };
 use fancy_constructor::new;
-use fjall::{
-    Database,
-    OwnedWriteBatch as Batch,
-};
 
 use crate::{
     error::{
         Error,
         Result,
     },
-    event::Event,
+    event::{
+        Data,
+        Event,
+        Version,
+    },
     stream::{
+        Durability,
         Metadata,
         Position,
         Timestamp,
-        operate::Selection,
+        audit::Redaction,
+        catalog::{
+            Catalog,
+            TagEntry,
+            TypeEntry,
+        },
+        clock::Clock,
+        operate::{
+            Selection,
+            append::AppendReceipt,
+            explain::{
+                Analysis,
+                Counters,
+                Plan,
+            },
+            select,
+        },
+        statistics::Statistics,
         store::{
+            audit::Audit,
+            cardinalities::{
+                Cardinalities,
+                Deltas,
+            },
             events::EventsIter,
+            forks::{
+                Forks,
+                Pin,
+            },
             indices::IndicesIter,
+            meta::{
+                FORMAT,
+                Meta,
+            },
+            names::{
+                Names,
+                Pending,
+            },
+            storage::{
+                Batch,
+                Storage,
+            },
         },
     },
+    utils::hashing,
 };
 
 // =================================================================================================
@@ -39,6 +92,27 @@ static HASH_LEN: usize = size_of::<u64>();
 static ID_LEN: usize = size_of::<u8>();
 static POSITION_LEN: usize = size_of::<u64>();
 
+// A selection is run as a filtered full scan, rather than through the indices,
+// once its estimated candidates reach this fraction (1/n) of the stream: a
+// sequential scan of every event then reads less than a posting read and a
+// point lookup per candidate.
+static SCAN_DIVISOR: u64 = 4;
+
+// Deferred postings are written this many events to a batch, bounding the
+// memory an index run holds at once.
+static INDEX_CHUNK: u64 = 16 * 1024;
+
+// The keyspaces of a stream, every one created when it is first opened for
+// writing.
+static KEYSPACES: [&str; 6] = [
+    "audit",
+    "cardinalities",
+    "events",
+    "indices",
+    "meta",
+    "names",
+];
+
 // -------------------------------------------------------------------------------------------------
 
 // Store
@@ -46,16 +120,195 @@ static POSITION_LEN: usize = size_of::<u64>();
 #[derive(new, Clone, Debug)]
 #[new(const_fn, vis())]
 pub struct Store {
+    pub(crate) audit: Audit,
+    pub(crate) cardinalities: Cardinalities,
     pub(crate) events: Events,
+    forks: Forks,
     pub(crate) indices: Indices,
+    pub(crate) meta: Meta,
+    pub(crate) names: Names,
+    #[allow(dead_code)]
+    pin: Option<Arc<Pin>>,
+}
+
+impl Store {
+    pub fn open(storage: &dyn Storage) -> Result<Self> {
+        let store = Self::load(storage)?;
+
+        store.upgrade(storage)?;
+        store.recover(storage)?;
+
+        Ok(store)
+    }
+
+    /// Rewrite the records of a stream stored in an earlier format in the
+    /// current [`FORMAT`], as opening it for writing does: its events, to carry
+    /// their batch, and its timestamp postings, to be keyed by position. Each
+    /// event is taken as a batch of its own, as nothing recorded which events
+    /// were appended together. The records are rewritten a chunk at a time,
+    /// each chunk committed with how far the upgrade has got, so an interrupted
+    /// upgrade resumes where it stopped and nothing is rewritten twice.
+    fn upgrade(&self, storage: &dyn Storage) -> Result<()> {
+        match self.meta.format()? {
+            Some(format) if format == FORMAT => return Ok(()),
+            Some(format) => return Err(Self::unreadable(format)),
+            None => {}
+        }
+
+        let end = Position::new(self.events.len()?);
+        let mut start = self.meta.upgraded()?.unwrap_or(Position::MIN);
+
+        loop {
+            let chunk = (start + INDEX_CHUNK).min(end);
+            let mut write = storage.batch(Durability::Buffer);
+
+            for meta in self.events.upgrade(&mut write, start..chunk)? {
+                self.indices.upgrade(&mut write, &meta);
+            }
+
+            if chunk == end {
+                self.meta.format_current(&mut write);
+            } else {
+                self.meta.upgrade(&mut write, chunk);
+            }
+
+            write
+                .commit()
+                .change_context(Error)
+                .attach("failed to commit upgraded events")?;
+
+            if chunk == end {
+                return Ok(());
+            }
+
+            start = chunk;
+        }
+    }
+
+    /// Bring the store's derived state up to date with its events, as opening
+    /// it for writing does: build the counts of a stream written before they
+    /// were maintained, and index any events imported without their postings.
+    pub fn recover(&self, storage: &dyn Storage) -> Result<()> {
+        // A stream written before the cardinalities were maintained has events
+        // but no counts: build them once, from the indices.
+        if self.cardinalities.is_empty()? && self.events.len()? > 0 {
+            let (types, tags) = self.indices.count()?;
+            let mut batch = storage.batch(Durability::Buffer);
+
+            self.cardinalities.rebuild(&mut batch, &types, &tags);
+
+            batch
+                .commit()
+                .change_context(Error)
+                .attach("failed to commit rebuilt cardinalities")?;
+        }
+
+        // An import which deferred its postings and did not finish indexing
+        // them (or was interrupted) is indexed now.
+        self.index(&mut || storage.batch(Durability::Buffer))
+    }
+
+    // Open the store without writing to it, for reading only, so a stream
+    // never opened for writing (with no keyspaces) is refused rather than
+    // created. (A stream whose cardinalities were never built is read without
+    // them until it is next opened for writing. A stream stored in an earlier
+    // format is refused, as its records would be misread, until opening it for
+    // writing upgrades it.)
+    pub fn read(storage: &dyn Storage) -> Result<Self> {
+        if !storage.exists("events") {
+            return Err(Report::new(Error).attach("stream does not exist"));
+        }
+
+        if !KEYSPACES.iter().all(|name| storage.exists(name)) {
+            return Err(Self::earlier());
+        }
+
+        let store = Self::load(storage)?;
+
+        match store.meta.format()? {
+            Some(format) if format == FORMAT => Ok(store),
+            None if store.events.len()? == 0 => Ok(store),
+            None => Err(Self::earlier()),
+            Some(format) => Err(Self::unreadable(format)),
+        }
+    }
+
+    fn earlier() -> Report<Error> {
+        Report::new(Error).attach(
+            "stream was written by an earlier version: open it for writing once to upgrade it",
+        )
+    }
+
+    fn unreadable(format: u64) -> Report<Error> {
+        Report::new(Error).attach(format!(
+            "stream is stored in format {format}, which this version (format {FORMAT}) does not \
+             read",
+        ))
+    }
+
+    fn load(storage: &dyn Storage) -> Result<Self> {
+        let audit = Audit::open(storage)?;
+        let cardinalities = Cardinalities::open(storage)?;
+        let events = Events::open(storage)?;
+        let indices = Indices::open(storage)?;
+        let meta = Meta::open(storage)?;
+        let names = Names::open(storage)?;
+
+        Ok(Self::new(
+            audit,
+            cardinalities,
+            events,
+            Forks::default(),
+            indices,
+            meta,
+            names,
+            None,
+        ))
+    }
 }
 
 impl Store {
-    pub fn open(database: &Database) -> Result<Self> {
-        let events = Events::open(database)?;
-        let indices = Indices::open(database)?;
+    /// A store forked from this one at `position` (no later than its length),
+    /// in `storage`: its events, postings, names and audit trail read through
+    /// to this store's up to `position`, and everything written to it goes to
+    /// `storage` — so nothing is copied. Only the counts are written at once,
+    /// as this store's less those of its events past `position`. While the
+    /// fork (or any clone of it) is open, this store refuses to redact or
+    /// truncate the events it reads.
+    pub fn fork(&self, storage: &dyn Storage, position: Position) -> Result<Self> {
+        let pin = Arc::new(self.forks.pin(position));
+        let mut past = Deltas::default();
+
+        for event in self.events.iterate(Some(position)) {
+            let Event(data, facets, _) = event?;
+
+            past.add(&Event::new(data, facets, ()));
+        }
+
+        let mut batch = storage.batch(Durability::Buffer);
+
+        let audit = self.audit.fork(storage, position)?;
+        let cardinalities = self.cardinalities.fork(storage, &mut batch, &past)?;
+        let events = self.events.fork(storage, position)?;
+        let indices = self.indices.fork(storage, position)?;
+        let meta = Meta::open(storage)?;
+        let names = self.names.fork(storage)?;
 
-        Ok(Self::new(events, indices))
+        batch
+            .commit()
+            .change_context(Error)
+            .attach("failed to commit forked cardinalities")?;
+
+        Ok(Self::new(
+            audit,
+            cardinalities,
+            events,
+            Forks::default(),
+            indices,
+            meta,
+            names,
+            Some(pin),
+        ))
     }
 }
 
@@ -63,19 +316,103 @@ impl Store {
     pub fn len(&self) -> Result<u64> {
         self.events.len()
     }
+
+    pub fn statistics(&self) -> Result<Statistics> {
+        let events = self.len()?;
+        let (types, tags) = self.cardinalities.all()?;
+
+        Ok(Statistics::new(events, tags, types))
+    }
 }
 
 impl Store {
-    pub fn insert<B, E>(&self, batch: &mut B, events: E, next: &mut Position) -> Result<Position>
+    pub fn insert<B, E>(
+        &self,
+        batch: &mut B,
+        clock: &dyn Clock,
+        events: E,
+        next: &mut Position,
+    ) -> Result<AppendReceipt>
     where
         B: FnMut() -> Batch,
         E: IntoIterator<Item = Event<(), String>>,
-        E::IntoIter: Send + 'static,
     {
-        let mut batch = batch();
+        self.write(
+            batch,
+            events,
+            next,
+            true,
+            clock.reads_last(),
+            |last, event| clock.now(last).map(|timestamp| (timestamp, event)),
+        )
+    }
+
+    /// Insert `events` stamped with their own timestamps rather than by a
+    /// clock. Unless `index`, their postings (and counts) are deferred: the
+    /// events are recorded as unindexed, to be indexed by [`Store::index`].
+    pub fn import<B, E>(
+        &self,
+        batch: &mut B,
+        events: E,
+        next: &mut Position,
+        index: bool,
+    ) -> Result<AppendReceipt>
+    where
+        B: FnMut() -> Batch,
+        E: IntoIterator<Item = (Timestamp, Event<(), String>)>,
+    {
+        self.write(batch, events, next, index, false, |_, event| Ok(event))
+    }
+
+    fn write<B, E, S>(
+        &self,
+        batch: &mut B,
+        events: E,
+        next: &mut Position,
+        index: bool,
+        read_last: bool,
+        mut stamp: S,
+    ) -> Result<AppendReceipt>
+    where
+        B: FnMut() -> Batch,
+        E: IntoIterator,
+        S: FnMut(Option<Timestamp>, E::Item) -> Result<(Timestamp, Event<(), String>)>,
+    {
+        // Deferred events must stay contiguous, so any indexed since the last
+        // deferral would be indexed twice: index the earlier ones first.
+        let unindexed = match self.meta.unindexed()? {
+            Some(unindexed) if !index && unindexed.end != *next => {
+                self.index(batch)?;
+
+                None
+            }
+            unindexed => unindexed,
+        };
+
+        let mut write = batch();
+        let mut deltas = Deltas::default();
+        let mut pending = Pending::default();
         let mut position = *next;
+        let mut timestamps = Vec::new();
+        let first = *next;
+
+        // The clock is told the timestamp of the event before each one, so
+        // the stream's last event is read for the first, if it is to be used.
+        let mut last = match first {
+            Position::MIN => None,
+            _ if !read_last => None,
+            first => self
+                .events
+                .get(first - 1)?
+                .map(|event| event.meta().timestamp()),
+        };
 
         for event in events {
+            let (timestamp, event) =
+                stamp(last, event).attach("failed to create timestamped metadata")?;
+
+            pending.add(&event);
+
             let event: Event<(), u64> = event.into();
 
             // The events keyspace prefixes the tag list with a `u8` count, so an
@@ -85,31 +422,340 @@ impl Store {
                 return Err(Report::new(Error).attach("event exceeds the maximum of 255 tags"));
             }
 
-            let meta = Timestamp::now()
-                .map(|timestamp| Metadata::new(position, timestamp))
-                .attach("failed to create timestamped metadata")?;
+            let meta = Metadata::new(position, timestamp, first);
+
+            self.events.insert(&mut write, &event, &meta);
+
+            if index {
+                self.indices.insert(&mut write, &event, &meta);
+                deltas.add(&event);
+            }
+
+            timestamps.push(timestamp);
+            last = Some(timestamp);
+            position += 1;
+        }
+
+        // Appending zero events has no "last position" to return, so treat it
+        // as a usage error rather than committing an empty batch.
+        if position == *next {
+            return Err(Report::new(Error).attach("cannot append zero events"));
+        }
+
+        if index {
+            self.cardinalities.insert(&mut write, deltas)?;
+        } else {
+            let start = unindexed.map_or(first, |unindexed| unindexed.start);
+
+            self.meta.defer(&mut write, start..position);
+        }
+
+        self.names.insert(&mut write, pending)?;
+
+        write
+            .commit()
+            .change_context(Error)
+            .attach("failed to commit append batch")?;
+
+        *next = position;
+
+        Ok(AppendReceipt::new(first, first, timestamps))
+    }
+
+    /// Index the events imported without their postings, if any: write their
+    /// postings and counts a chunk at a time, each chunk committed with the
+    /// range still to be indexed, so an interrupted run resumes where it
+    /// stopped and nothing is counted twice.
+    pub fn index<B>(&self, batch: &mut B) -> Result<()>
+    where
+        B: FnMut() -> Batch,
+    {
+        let Some(Range { mut start, end }) = self.meta.unindexed()? else {
+            return Ok(());
+        };
+
+        loop {
+            let chunk = (start + INDEX_CHUNK).min(end);
+            let mut write = batch();
+            let mut deltas = Deltas::default();
+
+            for event in self.events.range(start..chunk) {
+                let Event(data, facets, meta) = event?;
+                let event = Event::new(data, facets, ());
+
+                self.indices.insert(&mut write, &event, &meta);
+                deltas.add(&event);
+            }
+
+            self.cardinalities.insert(&mut write, deltas)?;
+            self.meta.defer(&mut write, chunk..end);
+
+            write
+                .commit()
+                .change_context(Error)
+                .attach("failed to commit deferred postings")?;
+
+            if chunk == end {
+                return Ok(());
+            }
+
+            start = chunk;
+        }
+    }
+
+    /// Insert `events` persisted by another stream (a replication leader),
+    /// keeping their positions and timestamps, with the names resolved for
+    /// them there as (`types`, `tags`). The events must continue this stream
+    /// exactly: their positions must run on from `next` without a gap.
+    pub fn replicate(
+        &self,
+        mut batch: Batch,
+        events: Vec<Event<Metadata, u64>>,
+        (types, tags): (BTreeMap<u64, String>, BTreeMap<u64, String>),
+        next: &mut Position,
+    ) -> Result<()> {
+        let mut deltas = Deltas::default();
+        let mut position = *next;
+
+        for Event(data, facets, meta) in events {
+            if meta.position() != position {
+                return Err(Report::new(Error).attach(format!(
+                    "replicated event at position {} does not follow position {}",
+                    meta.position().0,
+                    position.0,
+                )));
+            }
+
+            let event = Event::new(data, facets, ());
 
             self.events.insert(&mut batch, &event, &meta);
             self.indices.insert(&mut batch, &event, &meta);
 
+            deltas.add(&event);
             position += 1;
         }
 
-        // Appending zero events has no "last position" to return (and would
-        // underflow `*next - 1` on an empty stream), so treat it as a usage
-        // error rather than committing an empty batch.
         if position == *next {
-            return Err(Report::new(Error).attach("cannot append zero events"));
+            return Ok(());
         }
 
+        self.cardinalities.insert(&mut batch, deltas)?;
+        self.names
+            .insert(&mut batch, Pending::resolved(types, tags))?;
+
         batch
             .commit()
             .change_context(Error)
-            .attach("failed to commit append batch")?;
+            .attach("failed to commit replicated batch")?;
 
         *next = position;
 
-        Ok(*next - 1)
+        Ok(())
+    }
+}
+
+impl Store {
+    /// Replace the payload of the event at `position` with `replacement`,
+    /// marking it redacted, and record the redaction — the hash of the
+    /// payload replaced, `reason`, and the `clock`'s time — in the audit
+    /// keyspace, in one batch. Nothing else about the event changes, so its
+    /// postings, counts and any condition decided on it stand.
+    pub fn redact(
+        &self,
+        mut batch: Batch,
+        clock: &dyn Clock,
+        position: Position,
+        replacement: Data,
+        reason: String,
+    ) -> Result<Redaction> {
+        if let Some(fence) = self.forks.fence()
+            && position < fence
+        {
+            return Err(Report::new(Error).attach(format!(
+                "cannot redact position {} while a fork made at position {} reads it",
+                position.0, fence.0
+            )));
+        }
+
+        let Some(Event(data, facets, mut meta)) = self.events.get(position)? else {
+            return Err(
+                Report::new(Error).attach(format!("no event at position {} to redact", position.0))
+            );
+        };
+
+        let redaction = Redaction::new(hashing::hash(&data), reason, clock.now(None)?);
+
+        meta.3 = true;
+
+        self.events
+            .insert(&mut batch, &Event::new(replacement, facets, ()), &meta);
+        self.audit.insert(&mut batch, position, &redaction)?;
+
+        batch
+            .commit()
+            .change_context(Error)
+            .attach("failed to commit redaction")?;
+
+        Ok(redaction)
+    }
+
+    /// The redactions of the event at `position`, oldest first.
+    pub fn redactions(&self, position: Position) -> Result<Vec<Redaction>> {
+        self.audit.get(position)
+    }
+
+    /// Remove every event after `position` — with its postings, its counts
+    /// and its redactions — in one batch, and move `next` back to follow
+    /// `position`. Each backend commits a batch whole, and a select which has
+    /// read a posting whose event has since gone skips it, so no reader sees
+    /// part of a truncation.
+    pub fn truncate<B>(&self, batch: &mut B, position: Position, next: &mut Position) -> Result<()>
+    where
+        B: FnMut() -> Batch,
+    {
+        let Some(start) = position.0.checked_add(1).map(Position::new) else {
+            return Ok(());
+        };
+
+        if start >= *next {
+            return Ok(());
+        }
+
+        if let Some(fork) = self.events.fork_position()
+            && start < fork
+        {
+            return Err(Report::new(Error).attach(format!(
+                "cannot truncate a fork before position {}, where it was forked",
+                fork.0
+            )));
+        }
+
+        if let Some(fence) = self.forks.fence()
+            && start < fence
+        {
+            return Err(Report::new(Error).attach(format!(
+                "cannot truncate before position {} while a fork made there reads it",
+                fence.0
+            )));
+        }
+
+        // Counts must match the postings removed, so any events imported
+        // without their postings are indexed first.
+        self.index(batch)?;
+
+        let mut write = batch();
+        let mut deltas = Deltas::default();
+
+        for event in self.events.range(start..*next) {
+            let Event(data, facets, meta) = event?;
+            let event = Event::new(data, facets, ());
+
+            self.indices.remove(&mut write, &event, &meta);
+            deltas.add(&event);
+        }
+
+        for position in start.0..next.0 {
+            self.events.remove(&mut write, Position::new(position));
+            self.audit.remove(&mut write, Position::new(position))?;
+        }
+
+        self.cardinalities.remove(&mut write, deltas)?;
+
+        write
+            .commit()
+            .change_context(Error)
+            .attach("failed to commit truncation")?;
+
+        *next = start;
+
+        Ok(())
+    }
+}
+
+impl Store {
+    /// Build the catalog of type names and tags from a full scan of the
+    /// indices, resolving their string names and the metadata of the first and
+    /// last event carrying each.
+    pub fn catalog(&self) -> Result<Catalog> {
+        let mut types = BTreeMap::<u64, (Seen, BTreeMap<Version, u64>)>::new();
+        let mut tags = BTreeMap::<u64, Seen>::new();
+
+        for posting in self.indices.type_postings() {
+            let (hash, position, version) = posting?;
+            let (seen, versions) = types
+                .entry(hash)
+                .or_insert_with(|| (Seen::new(position), BTreeMap::new()));
+
+            seen.add(position);
+            *versions.entry(version).or_default() += 1;
+        }
+
+        for posting in self.indices.tag_postings() {
+            let (hash, position) = posting?;
+
+            tags.entry(hash)
+                .or_insert_with(|| Seen::new(position))
+                .add(position);
+        }
+
+        let types = types
+            .into_iter()
+            .map(|(hash, (seen, versions))| {
+                Ok(TypeEntry {
+                    hash,
+                    name: self.names.ty(hash)?,
+                    count: seen.count,
+                    versions,
+                    first: self.metadata(seen.first)?,
+                    last: self.metadata(seen.last)?,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        let tags = tags
+            .into_iter()
+            .map(|(hash, seen)| {
+                Ok(TagEntry {
+                    hash,
+                    tag: self.names.tag(hash)?,
+                    count: seen.count,
+                    first: self.metadata(seen.first)?,
+                    last: self.metadata(seen.last)?,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Catalog { tags, types })
+    }
+
+    fn metadata(&self, position: Position) -> Result<Metadata> {
+        self.events
+            .get(position)?
+            .map(|event| *event.meta())
+            .ok_or_else(|| Report::new(Error).attach("index posting has no event"))
+    }
+}
+
+// The postings of one hash, in position order: how many, and the first and
+// last position.
+struct Seen {
+    count: u64,
+    first: Position,
+    last: Position,
+}
+
+impl Seen {
+    fn new(position: Position) -> Self {
+        Self {
+            count: 0,
+            first: position,
+            last: position,
+        }
+    }
+
+    fn add(&mut self, position: Position) {
+        self.count += 1;
+        self.last = position;
     }
 }
 
@@ -123,16 +769,138 @@ impl Store {
                 .iter()
                 .flat_map(|selection| selection.selectors.iter()),
             from,
+            &self.cardinalities,
         )
     }
 
     pub fn iterate(&self, selections: &[Selection], from: Option<Position>) -> StoreIter {
         if selections.is_empty() {
             StoreIter::Events(self.events.iterate(from))
+        } else if self.scannable(selections) {
+            StoreIter::Filter(FilterIter::new(
+                self.events.iterate(from),
+                selections.to_vec(),
+            ))
         } else {
-            StoreIter::Indices(self.events.clone(), self.positions(selections, from))
+            StoreIter::Indices(self.events.clone(), self.positions(selections, from), None)
         }
     }
+
+    // Whether `selections` are estimated to match enough of the stream that a
+    // filtered full scan is cheaper than the index path. The estimate is of
+    // the whole stream, so it takes no account of a lower position bound.
+    fn scannable(&self, selections: &[Selection]) -> bool {
+        let Ok(len) = self.len() else {
+            return false;
+        };
+
+        let estimate = selections
+            .iter()
+            .flat_map(|selection| selection.selectors.iter())
+            .fold(0, |estimate: u64, selector| {
+                estimate.saturating_add(self.cardinalities.estimate(selector))
+            });
+
+        len > 0 && estimate >= len / SCAN_DIVISOR
+    }
+}
+
+impl Store {
+    /// The events of the batch holding the event at `position`, at or after
+    /// `from`, filtered to those matching `selections` (if any). A batch is
+    /// contiguous, so it is read as a range of the events keyspace rather than
+    /// through the indices.
+    pub fn batch(
+        &self,
+        selections: &[Selection],
+        position: Position,
+        from: Option<Position>,
+    ) -> Result<StoreIter> {
+        let batch = self.bounds(position)?;
+        let start = from.map_or(batch.start, |from| from.max(batch.start));
+        let events = self.events.range(start..batch.end.max(start));
+
+        if selections.is_empty() {
+            Ok(StoreIter::Events(events))
+        } else {
+            Ok(StoreIter::Filter(FilterIter::new(
+                events,
+                selections.to_vec(),
+            )))
+        }
+    }
+
+    // The positions of the batch holding the event at `position`, from its
+    // first event to just past its last (empty if there is no such event). The
+    // batch ends at the first later event which does not share its first
+    // position.
+    fn bounds(&self, position: Position) -> Result<Range<Position>> {
+        let Some(event) = self.events.get(position)? else {
+            return Ok(position..position);
+        };
+
+        let first = event.meta().batch();
+        let mut end = position + 1;
+
+        for event in self.events.iterate(Some(end)) {
+            if event?.meta().batch() != first {
+                break;
+            }
+
+            end += 1;
+        }
+
+        Ok(first..end)
+    }
+}
+
+impl Store {
+    /// The plan `iterate` executes for `selections` from `from`.
+    pub fn explain(&self, selections: &[Selection], from: Option<Position>) -> Plan {
+        self.iterate(selections, from).plan(from)
+    }
+
+    /// Run the plan for `selections` from `from` to completion, counting the
+    /// postings, seeks, and events it reads. The events are discarded.
+    pub fn analyze(&self, selections: &[Selection], from: Option<Position>) -> Result<Analysis> {
+        let started = Instant::now();
+        let iter = self.iterate(selections, from);
+        let plan = iter.plan(from);
+
+        Self::measure(started, iter, plan)
+    }
+
+    /// Run the read of the batch holding the event at `position` (see
+    /// [`batch`](Store::batch)) to completion, as [`analyze`](Store::analyze).
+    pub fn analyze_batch(
+        &self,
+        selections: &[Selection],
+        position: Position,
+        from: Option<Position>,
+    ) -> Result<Analysis> {
+        let started = Instant::now();
+        let iter = self.batch(selections, position, from)?;
+
+        Self::measure(started, iter, Plan::Batch { from, position })
+    }
+
+    fn measure(started: Instant, mut iter: StoreIter, plan: Plan) -> Result<Analysis> {
+        let counters = Arc::new(Counters::default());
+
+        iter.instrument(&counters);
+
+        for event in iter {
+            event?;
+        }
+
+        Ok(Analysis {
+            plan,
+            postings: counters.postings(),
+            seeks: counters.seeks(),
+            events: counters.events(),
+            duration: started.elapsed(),
+        })
+    }
 }
 
 impl Store {
@@ -146,6 +914,25 @@ impl Store {
             None => Ok(false),
         }
     }
+
+    /// Whether any event of the batch holding the event at `position` (see
+    /// [`batch`](Store::batch)) matches `selections`. Empty `selections` is
+    /// vacuously `false`, as for [`matches`](Store::matches).
+    pub fn matches_batch(
+        &self,
+        selections: &[Selection],
+        position: Position,
+        from: Option<Position>,
+    ) -> Result<bool> {
+        if selections.is_empty() {
+            return Ok(false);
+        }
+
+        match self.batch(selections, position, from)?.next() {
+            Some(result) => result.map(|_| true),
+            None => Ok(false),
+        }
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -155,18 +942,58 @@ impl Store {
 #[derive(Debug)]
 pub enum StoreIter {
     Events(EventsIter),
-    Indices(Events, IndicesIter),
+    Filter(FilterIter),
+    Indices(Events, IndicesIter, Option<Arc<Counters>>),
 }
 
 impl StoreIter {
-    fn next_map(events: &Events, position: Result<Position>) -> Option<<Self as Iterator>::Item> {
-        match position {
-            Ok(position) => match events.get(position) {
-                Ok(Some(event)) => Some(Ok(event)),
-                Ok(None) => None,
-                Err(err) => Some(Err(err)),
+    fn plan(&self, from: Option<Position>) -> Plan {
+        match self {
+            Self::Events(_) => Plan::Events { from },
+            Self::Filter(_) => Plan::Filter { from },
+            Self::Indices(_, iter, _) => Plan::Indices {
+                from,
+                root: iter.node(),
             },
-            Err(err) => Some(Err(err)),
+        }
+    }
+
+    /// Count the postings, seeks and events this iterator reads into
+    /// `counters`.
+    pub(crate) fn instrument(&mut self, counters: &Arc<Counters>) {
+        match self {
+            Self::Events(iter) => iter.instrument(counters),
+            Self::Filter(iter) => iter.iter.instrument(counters),
+            Self::Indices(_, iter, instrument) => {
+                iter.instrument(counters);
+                *instrument = Some(Arc::clone(counters));
+            }
+        }
+    }
+
+    // The event at each position `next` yields, skipping any whose event is
+    // gone (truncated since its posting was read).
+    fn next_map<F>(
+        events: &Events,
+        counters: Option<&Counters>,
+        mut next: F,
+    ) -> Option<<Self as Iterator>::Item>
+    where
+        F: FnMut() -> Option<Result<Position>>,
+    {
+        loop {
+            let event = match next()? {
+                Ok(position) => events.get(position).transpose(),
+                Err(err) => Some(Err(err)),
+            };
+
+            if event.is_some() {
+                if let Some(counters) = counters {
+                    counters.event();
+                }
+
+                return event;
+            }
         }
     }
 }
@@ -175,9 +1002,10 @@ impl DoubleEndedIterator for StoreIter {
     fn next_back(&mut self) -> Option<Self::Item> {
         match self {
             Self::Events(iter) => iter.next_back(),
-            Self::Indices(events, iter) => iter
-                .next_back()
-                .and_then(|position| Self::next_map(events, position)),
+            Self::Filter(iter) => iter.next_back(),
+            Self::Indices(events, iter, counters) => {
+                Self::next_map(events, counters.as_deref(), || iter.next_back())
+            }
         }
     }
 }
@@ -188,9 +1016,64 @@ impl Iterator for StoreIter {
     fn next(&mut self) -> Option<Self::Item> {
         match self {
             Self::Events(iter) => iter.next(),
-            Self::Indices(events, iter) => iter
-                .next()
-                .and_then(|position| Self::next_map(events, position)),
+            Self::Filter(iter) => iter.next(),
+            Self::Indices(events, iter, counters) => {
+                Self::next_map(events, counters.as_deref(), || iter.next())
+            }
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Filter Iterator
+
+/// A full scan of the events, yielding only those matching any selector of
+/// `selections` (read errors are yielded as they occur).
+#[derive(new, Debug)]
+#[new(const_fn)]
+pub struct FilterIter {
+    iter: EventsIter,
+    selections: Vec<Selection>,
+}
+
+impl FilterIter {
+    fn filter(&mut self, event: <Self as Iterator>::Item) -> Option<<Self as Iterator>::Item> {
+        match &event {
+            Ok(event) if !self.selected(event) => None,
+            _ => Some(event),
+        }
+    }
+
+    fn selected(&self, event: &Event<Metadata, u64>) -> bool {
+        self.selections
+            .iter()
+            .any(|selection| select::selected(selection, event))
+    }
+}
+
+impl DoubleEndedIterator for FilterIter {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            let event = self.iter.next_back()?;
+
+            if let Some(event) = self.filter(event) {
+                return Some(event);
+            }
+        }
+    }
+}
+
+impl Iterator for FilterIter {
+    type Item = Result<Event<Metadata, u64>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let event = self.iter.next()?;
+
+            if let Some(event) = self.filter(event) {
+                return Some(event);
+            }
         }
     }
 }
@@ -212,9 +1095,16 @@ pub use self::{
 mod tests {
     use std::collections::BTreeSet;
 
-    use fjall::Database;
+    use fjall::{
+        Database,
+        KeyspaceCreateOptions,
+    };
 
-    use super::Store;
+    use super::{
+        FORMAT,
+        Store,
+        storage::Batch,
+    };
     use crate::{
         event::{
             Data,
@@ -227,8 +1117,11 @@ mod tests {
         },
         stream::{
             Position,
+            Timestamp,
+            clock::System,
             operate::{
                 Selection,
+                explain::Plan,
                 select::{
                     Selector,
                     TypeSelector,
@@ -285,11 +1178,16 @@ mod tests {
         ];
 
         let mut next = Position::new(0);
-        let last = store
-            .insert(&mut || database.batch(), events, &mut next)
+        let receipt = store
+            .insert(
+                &mut || Batch::Fjall(database.batch()),
+                &System,
+                events,
+                &mut next,
+            )
             .unwrap();
 
-        assert_eq!(last, Position::new(2));
+        assert_eq!(receipt.last(), Position::new(2));
         assert_eq!(next, Position::new(3));
 
         let read = store
@@ -335,7 +1233,12 @@ mod tests {
 
         let mut next = Position::new(0);
         store
-            .insert(&mut || database.batch(), events, &mut next)
+            .insert(
+                &mut || Batch::Fjall(database.batch()),
+                &System,
+                events,
+                &mut next,
+            )
             .unwrap();
 
         let selection = Selection::new([Selector::types_and_tags(
@@ -373,7 +1276,12 @@ mod tests {
 
         let mut next = Position::new(0);
         store
-            .insert(&mut || database.batch(), events, &mut next)
+            .insert(
+                &mut || Batch::Fjall(database.batch()),
+                &System,
+                events,
+                &mut next,
+            )
             .unwrap();
 
         let selection = Selection::new([Selector::types([TypeSelector::with_versions(
@@ -408,9 +1316,350 @@ mod tests {
         let event = Event::new(Data::new(b"x".to_vec()).unwrap(), Facets::new(ty, tags), ());
 
         let mut next = Position::new(0);
-        let result = store.insert(&mut || database.batch(), vec![event], &mut next);
+        let result = store.insert(
+            &mut || Batch::Fjall(database.batch()),
+            &System,
+            vec![event],
+            &mut next,
+        );
 
         assert!(result.is_err());
         assert_eq!(next, Position::new(0)); // nothing committed
     }
+
+    // Every committed append adds its postings to the per-type and per-tag
+    // counts, in the same batch.
+    #[test]
+    fn cardinalities_follow_appends() {
+        let database = Database::builder(temp_path())
+            .temporary(true)
+            .open()
+            .unwrap();
+        let store = Store::open(&database).unwrap();
+
+        let mut next = Position::new(0);
+
+        store
+            .insert(
+                &mut || Batch::Fjall(database.batch()),
+                &System,
+                vec![
+                    event("Enrolled", &["student:1", "course:1"]),
+                    event("Enrolled", &["student:2", "course:1"]),
+                ],
+                &mut next,
+            )
+            .unwrap();
+        store
+            .insert(
+                &mut || Batch::Fjall(database.batch()),
+                &System,
+                vec![event("Dropped", &["student:1", "course:1"])],
+                &mut next,
+            )
+            .unwrap();
+
+        let statistics = store.statistics().unwrap();
+
+        assert_eq!(statistics.events(), 3);
+        assert_eq!(statistics.ty(&Name::new("Enrolled").unwrap()), 2);
+        assert_eq!(statistics.ty(&Name::new("Dropped").unwrap()), 1);
+        assert_eq!(statistics.tag(&Tag::new("course:1").unwrap()), 3);
+        assert_eq!(statistics.tag(&Tag::new("student:2").unwrap()), 1);
+        assert_eq!(statistics.tag(&Tag::new("student:3").unwrap()), 0);
+    }
+
+    // A store whose events predate the cardinalities has them rebuilt from the
+    // indices when it is opened.
+    #[test]
+    fn cardinalities_are_rebuilt_on_open() {
+        let database = Database::builder(temp_path())
+            .temporary(true)
+            .open()
+            .unwrap();
+        let store = Store::open(&database).unwrap();
+
+        let mut next = Position::new(0);
+
+        store
+            .insert(
+                &mut || Batch::Fjall(database.batch()),
+                &System,
+                vec![
+                    event("Enrolled", &["course:1"]),
+                    event("Enrolled", &["course:2"]),
+                ],
+                &mut next,
+            )
+            .unwrap();
+
+        let expected = store.statistics().unwrap();
+
+        database
+            .keyspace("cardinalities", KeyspaceCreateOptions::default)
+            .unwrap()
+            .clear()
+            .unwrap();
+
+        assert_eq!(store.statistics().unwrap().types().count(), 0);
+        assert_eq!(
+            Store::open(&database).unwrap().statistics().unwrap(),
+            expected
+        );
+    }
+
+    // The catalog lists each type (with its per-version counts) and each tag,
+    // with their names and first and last positions; a name missing from the
+    // dictionary (as for events written before it existed) is `None`.
+    #[test]
+    fn catalog_lists_types_and_tags() {
+        let database = Database::builder(temp_path())
+            .temporary(true)
+            .open()
+            .unwrap();
+        let store = Store::open(&database).unwrap();
+
+        let mut next = Position::new(0);
+
+        store
+            .insert(
+                &mut || Batch::Fjall(database.batch()),
+                &System,
+                vec![
+                    event_v("Enrolled", 0, &["course:1"]),
+                    event_v("Dropped", 0, &["course:1"]),
+                    event_v("Enrolled", 1, &["course:2"]),
+                    event_v("Enrolled", 1, &["course:1"]),
+                ],
+                &mut next,
+            )
+            .unwrap();
+
+        let catalog = store.catalog().unwrap();
+
+        assert_eq!(catalog.types.len(), 2);
+        assert_eq!(catalog.tags.len(), 2);
+
+        let enrolled = catalog.ty(&Name::new("Enrolled").unwrap()).unwrap();
+
+        assert_eq!(enrolled.name.as_deref(), Some("Enrolled"));
+        assert_eq!(enrolled.count, 3);
+        assert_eq!(enrolled.versions.iter().collect::<Vec<_>>(), [
+            (&Version::new(0), &1),
+            (&Version::new(1), &2)
+        ]);
+        assert_eq!(enrolled.first.position(), Position::new(0));
+        assert_eq!(enrolled.last.position(), Position::new(3));
+
+        let course = catalog.tag(&Tag::new("course:1").unwrap()).unwrap();
+
+        assert_eq!(course.tag.as_deref(), Some("course:1"));
+        assert_eq!(course.count, 3);
+        assert_eq!(course.first.position(), Position::new(0));
+        assert_eq!(course.last.position(), Position::new(3));
+
+        database
+            .keyspace("names", KeyspaceCreateOptions::default)
+            .unwrap()
+            .clear()
+            .unwrap();
+
+        let catalog = store.catalog().unwrap();
+
+        assert!(catalog.types.iter().all(|entry| entry.name.is_none()));
+        assert_eq!(catalog.ty(&Name::new("Dropped").unwrap()).unwrap().count, 1);
+    }
+
+    // A selection matching most of the stream is run as a filtered full scan,
+    // which yields exactly what the index path would, in both directions.
+    #[test]
+    fn broad_selection_scans_and_filters() {
+        let database = Database::builder(temp_path())
+            .temporary(true)
+            .open()
+            .unwrap();
+        let store = Store::open(&database).unwrap();
+
+        let mut next = Position::new(0);
+
+        store
+            .insert(
+                &mut || Batch::Fjall(database.batch()),
+                &System,
+                vec![
+                    event("Enrolled", &["course:1"]),
+                    event("Dropped", &["course:1"]),
+                    event("Enrolled", &["course:2"]),
+                    event("Enrolled", &["course:1"]),
+                ],
+                &mut next,
+            )
+            .unwrap();
+
+        let selections = [Selection::new([Selector::types_and_tags(
+            [TypeSelector::new("Enrolled").unwrap()],
+            [Tag::new("course:1").unwrap()],
+        )])];
+
+        assert!(matches!(store.explain(&selections, None), Plan::Filter {
+            from: None
+        }));
+
+        let forward = store
+            .iterate(&selections, None)
+            .map(|event| event.unwrap().meta().position())
+            .collect::<Vec<_>>();
+        let backward = store
+            .iterate(&selections, None)
+            .rev()
+            .map(|event| event.unwrap().meta().position())
+            .collect::<Vec<_>>();
+        let from = store
+            .iterate(&selections, Some(Position::new(1)))
+            .map(|event| event.unwrap().meta().position())
+            .collect::<Vec<_>>();
+
+        assert_eq!(forward, vec![Position::new(0), Position::new(3)]);
+        assert_eq!(backward, vec![Position::new(3), Position::new(0)]);
+        assert_eq!(from, vec![Position::new(3)]);
+
+        let analysis = store.analyze(&selections, None).unwrap();
+
+        assert_eq!(analysis.events, 4);
+        assert_eq!(analysis.postings, 0);
+    }
+
+    // Truncating part of a batch whose events share a timestamp removes the
+    // truncated events' postings (their timestamp postings included) and
+    // leaves the surviving events' alone.
+    #[test]
+    fn truncate_removes_part_of_a_batch() {
+        let database = Database::builder(temp_path())
+            .temporary(true)
+            .open()
+            .unwrap();
+        let store = Store::open(&database).unwrap();
+        let indices = database
+            .keyspace("indices", KeyspaceCreateOptions::default)
+            .unwrap();
+        let timestamps = |nanos: u64| {
+            indices
+                .prefix([&[1], &nanos.to_be_bytes()[..]].concat())
+                .map(|guard| u64::from_be_bytes(guard.key().unwrap()[9..].try_into().unwrap()))
+                .collect::<Vec<_>>()
+        };
+        let positions = |tag: &str| {
+            let selections = [Selection::new([Selector::tags([Tag::new(tag).unwrap()])])];
+
+            store
+                .iterate(&selections, None)
+                .map(|event| event.unwrap().meta().position().0)
+                .collect::<Vec<_>>()
+        };
+
+        let mut next = Position::new(0);
+
+        store
+            .import(
+                &mut || Batch::Fjall(database.batch()),
+                vec![
+                    (Timestamp::new(1), event("Enrolled", &["course:1"])),
+                    (Timestamp::new(1), event("Dropped", &["course:1"])),
+                    (Timestamp::new(1), event("Enrolled", &["course:2"])),
+                    (Timestamp::new(2), event("Enrolled", &["course:1"])),
+                ],
+                &mut next,
+                true,
+            )
+            .unwrap();
+
+        assert_eq!(timestamps(1), [0, 1, 2]);
+        assert_eq!(timestamps(2), [3]);
+
+        store
+            .truncate(
+                &mut || Batch::Fjall(database.batch()),
+                Position::new(1),
+                &mut next,
+            )
+            .unwrap();
+
+        assert_eq!(next, Position::new(2));
+        assert_eq!(timestamps(1), [0, 1]);
+        assert_eq!(timestamps(2), [0u64; 0]);
+        assert_eq!(positions("course:1"), [0, 1]);
+        assert_eq!(positions("course:2"), [0u64; 0]);
+    }
+
+    // A stream written before events carried their batch (and timestamp
+    // postings their position) is refused for reading, as its records would be
+    // misread: the first bytes of each payload as the batch, its top bit as the
+    // redaction mark. Opening it for writing upgrades it, once.
+    #[test]
+    fn open_upgrades_a_stream_stored_in_an_earlier_format() {
+        let database = Database::builder(temp_path())
+            .temporary(true)
+            .open()
+            .unwrap();
+        let events = database
+            .keyspace("events", KeyspaceCreateOptions::default)
+            .unwrap();
+        let indices = database
+            .keyspace("indices", KeyspaceCreateOptions::default)
+            .unwrap();
+        let record = |version: u8, tags: &[u64], data: &[u8]| {
+            let mut record = Vec::new();
+
+            record.extend(7u64.to_be_bytes()); // Name
+            record.push(version); // Version
+            record.push(u8::try_from(tags.len()).unwrap()); // Tag Count
+
+            for tag in tags {
+                record.extend(tag.to_be_bytes()); // Tag
+            }
+
+            record.extend(1u64.to_be_bytes()); // Timestamp
+            record.extend(data); // Data
+            record
+        };
+
+        events
+            .insert(0u64.to_be_bytes(), record(0, &[], &[0xff; 16]))
+            .unwrap();
+        events
+            .insert(1u64.to_be_bytes(), record(1, &[9], b"ab"))
+            .unwrap();
+        indices
+            .insert([&[1], &1u64.to_be_bytes()[..]].concat(), 1u64.to_be_bytes())
+            .unwrap();
+
+        assert!(Store::read(&database).is_err());
+
+        Store::open(&database).unwrap();
+
+        let store = Store::open(&database).unwrap();
+        let first = store.events.get(Position::new(0)).unwrap().unwrap();
+        let second = store.events.get(Position::new(1)).unwrap().unwrap();
+
+        assert_eq!(store.meta.format().unwrap(), Some(FORMAT));
+        assert_eq!(first.meta().batch(), Position::new(0));
+        assert!(!first.meta().redacted());
+        assert_eq!(first.meta().timestamp(), Timestamp::new(1));
+        assert_eq!(first.data().as_ref(), [0xff; 16]);
+        assert_eq!(second.meta().batch(), Position::new(1));
+        assert_eq!(second.facets().tags().len(), 1);
+        assert_eq!(second.data().as_ref(), b"ab");
+
+        let timestamps = indices
+            .prefix([1])
+            .map(|guard| guard.key().unwrap().to_vec())
+            .collect::<Vec<_>>();
+
+        assert_eq!(timestamps, [
+            [&[1], &1u64.to_be_bytes()[..], &0u64.to_be_bytes()[..]].concat(),
+            [&[1], &1u64.to_be_bytes()[..], &1u64.to_be_bytes()[..]].concat(),
+        ]);
+
+        assert!(Store::read(&database).is_ok());
+    }
 }
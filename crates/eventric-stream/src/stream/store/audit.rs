@@ -0,0 +1,152 @@
+use bytes::{
+    Buf as _,
+    BufMut as _,
+};
+use derive_more::Debug;
+use error_stack::ResultExt;
+use fancy_constructor::new;
+
+use crate::{
+    error::{
+        Error,
+        Result,
+    },
+    stream::{
+        Position,
+        Timestamp,
+        audit::Redaction,
+        store::{
+            POSITION_LEN,
+            storage::{
+                Batch,
+                Fence,
+                Keyspace,
+                Storage,
+            },
+        },
+    },
+};
+
+// =================================================================================================
+// Audit
+// =================================================================================================
+
+// Constants
+
+static AUDIT_KEY_LEN: usize = POSITION_LEN + size_of::<u32>();
+
+// -------------------------------------------------------------------------------------------------
+
+// Audit Key Writer
+
+type AuditKey = [u8; AUDIT_KEY_LEN];
+
+struct AuditKeyWriter(Position, u32);
+
+impl From<AuditKeyWriter> for AuditKey {
+    fn from(AuditKeyWriter(position, sequence): AuditKeyWriter) -> Self {
+        let mut key = AuditKey::default();
+
+        {
+            let mut key = &mut key[..];
+
+            key.put_u64(position.0); // Position
+            key.put_u32(sequence); // Sequence (of the event's redactions)
+        }
+
+        key
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Audit
+
+/// The redactions made to events' payloads, keyed by the event's position and
+/// then in the order they were made, so an event redacted more than once keeps
+/// every record.
+#[derive(new, Clone, Debug)]
+#[new(const_fn, vis())]
+pub struct Audit {
+    keyspace: Keyspace,
+}
+
+impl Audit {
+    pub fn open(storage: &dyn Storage) -> Result<Self> {
+        storage.keyspace("audit").map(Self::new)
+    }
+
+    /// The audit trail of a stream forked from this one at `position`, in
+    /// `storage`: the redactions of the events before `position`, and its own.
+    pub fn fork(&self, storage: &dyn Storage, position: Position) -> Result<Self> {
+        storage.keyspace("audit").map(|delta| {
+            Self::new(Keyspace::layered(
+                self.keyspace.clone(),
+                delta,
+                Fence::Key(position),
+            ))
+        })
+    }
+}
+
+impl Audit {
+    /// The redactions of the event at `position`, oldest first.
+    pub fn get(&self, position: Position) -> Result<Vec<Redaction>> {
+        self.keyspace
+            .prefix(position.0.to_be_bytes())
+            .map(|item| {
+                let (_, value) = item
+                    .change_context(Error)
+                    .attach("failed to read value from audit keyspace")?;
+
+                let mut value = &value[..];
+
+                let hash = value.get_u64();
+                let timestamp = Timestamp(value.get_u64());
+                let reason = String::from_utf8_lossy(value).into_owned();
+
+                Ok(Redaction::new(hash, reason, timestamp))
+            })
+            .collect()
+    }
+}
+
+impl Audit {
+    /// Record `redaction` of the event at `position`, after any earlier ones,
+    /// in `batch`.
+    pub fn insert(
+        &self,
+        batch: &mut Batch,
+        position: Position,
+        redaction: &Redaction,
+    ) -> Result<()> {
+        let sequence = self.get(position)?.len();
+        let sequence = u32::try_from(sequence)
+            .change_context(Error)
+            .attach("too many redactions of one event")?;
+
+        let key: AuditKey = AuditKeyWriter(position, sequence).into();
+        let mut value = Vec::new();
+
+        value.put_u64(redaction.hash()); // Original Data (hash)
+        value.put_u64(redaction.timestamp().0); // Timestamp
+        value.put_slice(redaction.reason().as_bytes()); // Reason
+
+        batch.insert(&self.keyspace, key, value);
+
+        Ok(())
+    }
+
+    /// Remove every redaction of the event at `position`, in `batch`.
+    pub fn remove(&self, batch: &mut Batch, position: Position) -> Result<()> {
+        for item in self.keyspace.prefix(position.0.to_be_bytes()) {
+            let (key, _) = item
+                .change_context(Error)
+                .attach("failed to read key from audit keyspace")?;
+
+            batch.remove(&self.keyspace, key);
+        }
+
+        Ok(())
+    }
+}
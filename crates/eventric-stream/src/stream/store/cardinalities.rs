@@ -0,0 +1,277 @@
+use std::collections::{
+    BTreeMap,
+    BTreeSet,
+};
+
+use bytes::{
+    Buf as _,
+    BufMut as _,
+};
+use derive_more::Debug;
+use error_stack::ResultExt;
+use fancy_constructor::new;
+
+use crate::{
+    error::{
+        Error,
+        Result,
+    },
+    event::{
+        Event,
+        Name,
+        Tag,
+    },
+    stream::{
+        operate::select::{
+            Selector,
+            TypeSelector,
+        },
+        store::{
+            HASH_LEN,
+            ID_LEN,
+            storage::{
+                Batch,
+                Keyspace,
+                Storage,
+            },
+        },
+    },
+};
+
+// =================================================================================================
+// Cardinalities
+// =================================================================================================
+
+// Constants
+
+static CARDINALITY_KEY_LEN: usize = ID_LEN + HASH_LEN;
+static TAG_CARDINALITY_ID: u8 = 0;
+static TYPE_CARDINALITY_ID: u8 = 2;
+
+// -------------------------------------------------------------------------------------------------
+
+// Cardinality Key Writer
+
+type CardinalityKey = [u8; CARDINALITY_KEY_LEN];
+
+struct CardinalityKeyWriter(u8, u64);
+
+impl From<CardinalityKeyWriter> for CardinalityKey {
+    fn from(CardinalityKeyWriter(id, hash): CardinalityKeyWriter) -> Self {
+        let mut key = CardinalityKey::default();
+
+        {
+            let mut key = &mut key[..];
+
+            key.put_u8(id); // Tag or Type
+            key.put_u64(hash); // Tag or Type Name (hash)
+        }
+
+        key
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Cardinalities
+
+/// The per-type and per-tag posting counts: how many events carry each type
+/// name and each tag. Maintained in the same batch as the postings themselves,
+/// so the counts are always exactly those of the committed indices.
+#[derive(new, Clone, Debug)]
+#[new(const_fn, vis())]
+pub struct Cardinalities {
+    keyspace: Keyspace,
+}
+
+impl Cardinalities {
+    pub fn open(storage: &dyn Storage) -> Result<Self> {
+        storage.keyspace("cardinalities").map(Self::new)
+    }
+
+    /// The counts of a stream forked from this one, in `storage`: these
+    /// counts less `past` (the postings of the events past the fork), written
+    /// in `batch`.
+    pub fn fork(&self, storage: &dyn Storage, batch: &mut Batch, past: &Deltas) -> Result<Self> {
+        let forked = Self::open(storage)?;
+
+        for item in self.keyspace.iter() {
+            let (key, value) = item
+                .change_context(Error)
+                .attach("failed to read cardinality")?;
+
+            let mut id_hash = &key[..];
+            let id_hash = (id_hash.get_u8(), id_hash.get_u64());
+            let count =
+                value.as_ref().get_u64() - past.0.get(&id_hash).copied().unwrap_or_default();
+
+            if count > 0 {
+                batch.insert(&forked.keyspace, key, count.to_be_bytes());
+            }
+        }
+
+        Ok(forked)
+    }
+
+    pub fn is_empty(&self) -> Result<bool> {
+        self.keyspace
+            .is_empty()
+            .change_context(Error)
+            .attach("failed to check cardinalities keyspace")
+    }
+}
+
+impl Cardinalities {
+    /// The number of events with type name `name`.
+    pub fn ty(&self, name: &Name<u64>) -> Result<u64> {
+        self.get(TYPE_CARDINALITY_ID, name.0)
+    }
+
+    /// The number of events carrying `tag`.
+    pub fn tag(&self, tag: &Tag<u64>) -> Result<u64> {
+        self.get(TAG_CARDINALITY_ID, tag.0)
+    }
+
+    /// Every (type name hash, count) and every (tag hash, count).
+    pub fn all(&self) -> Result<(BTreeMap<u64, u64>, BTreeMap<u64, u64>)> {
+        let mut types = BTreeMap::new();
+        let mut tags = BTreeMap::new();
+
+        for item in self.keyspace.iter() {
+            let (key, value) = item
+                .change_context(Error)
+                .attach("failed to read cardinality")?;
+
+            let mut key = &key[..];
+            let (id, hash) = (key.get_u8(), key.get_u64());
+            let count = value.as_ref().get_u64();
+
+            if id == TYPE_CARDINALITY_ID {
+                types.insert(hash, count);
+            } else {
+                tags.insert(hash, count);
+            }
+        }
+
+        Ok((types, tags))
+    }
+
+    /// An upper bound on the number of events `selector` matches: the postings
+    /// of its types, or of its rarest tag if that is smaller. Counts are only a
+    /// hint, so one which cannot be read is taken as unbounded (the scan itself
+    /// then surfaces the error).
+    pub fn estimate(&self, selector: &Selector<u64>) -> u64 {
+        let Selector(types, tags) = selector;
+
+        // A selector without types is not narrowed by them (see `Selector`).
+        let types = if types.is_empty() {
+            u64::MAX
+        } else {
+            self.estimate_types(types)
+        };
+        let tags = tags
+            .iter()
+            .flatten()
+            .map(|tag| self.estimate_tag(tag))
+            .min()
+            .unwrap_or(u64::MAX);
+
+        types.min(tags)
+    }
+
+    /// The postings of `types`, as [`estimate`](Self::estimate) reads them.
+    pub fn estimate_types(&self, types: &BTreeSet<TypeSelector<u64>>) -> u64 {
+        types.iter().fold(0, |estimate, ty| {
+            estimate.saturating_add(self.ty(&ty.0).unwrap_or(u64::MAX))
+        })
+    }
+
+    /// The postings of `tag`, as [`estimate`](Self::estimate) reads them.
+    pub fn estimate_tag(&self, tag: &Tag<u64>) -> u64 {
+        self.tag(tag).unwrap_or(u64::MAX)
+    }
+
+    fn get(&self, id: u8, hash: u64) -> Result<u64> {
+        let key: CardinalityKey = CardinalityKeyWriter(id, hash).into();
+        let value = self
+            .keyspace
+            .get(key)
+            .change_context(Error)
+            .attach("failed to get value from cardinalities keyspace")?;
+
+        Ok(value.map_or(0, |value| value.as_ref().get_u64()))
+    }
+}
+
+impl Cardinalities {
+    /// Add `deltas` (the postings of a batch's events) to the counts, in
+    /// `batch`.
+    pub fn insert(&self, batch: &mut Batch, deltas: Deltas) -> Result<()> {
+        // The single writer makes the read-modify-write safe: nothing else can
+        // change a count between this read and the batch's commit.
+        for ((id, hash), delta) in deltas.0 {
+            let count = self.get(id, hash)? + delta;
+            let key: CardinalityKey = CardinalityKeyWriter(id, hash).into();
+
+            batch.insert(&self.keyspace, key, count.to_be_bytes());
+        }
+
+        Ok(())
+    }
+
+    /// Subtract `deltas` (the postings of events being removed) from the
+    /// counts, in `batch`, removing those which fall to zero.
+    pub fn remove(&self, batch: &mut Batch, deltas: Deltas) -> Result<()> {
+        for ((id, hash), delta) in deltas.0 {
+            let count = self.get(id, hash)?.saturating_sub(delta);
+            let key: CardinalityKey = CardinalityKeyWriter(id, hash).into();
+
+            if count == 0 {
+                batch.remove(&self.keyspace, key);
+            } else {
+                batch.insert(&self.keyspace, key, count.to_be_bytes());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Replace the counts with those of `types` and `tags` (as counted from the
+    /// indices), in `batch`. Used to build the counts for a stream written
+    /// before they were maintained.
+    pub fn rebuild(
+        &self,
+        batch: &mut Batch,
+        types: &BTreeMap<u64, u64>,
+        tags: &BTreeMap<u64, u64>,
+    ) {
+        for (id, counts) in [(TYPE_CARDINALITY_ID, types), (TAG_CARDINALITY_ID, tags)] {
+            for (hash, count) in counts {
+                let key: CardinalityKey = CardinalityKeyWriter(id, *hash).into();
+
+                batch.insert(&self.keyspace, key, count.to_be_bytes());
+            }
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Deltas
+
+/// The count increments accumulated over one batch's events, keyed by
+/// (cardinality ID, hash).
+#[derive(Debug, Default)]
+pub struct Deltas(BTreeMap<(u8, u64), u64>);
+
+impl Deltas {
+    pub fn add(&mut self, event: &Event<(), u64>) {
+        let name = event.facets().ty().name();
+
+        *self.0.entry((TYPE_CARDINALITY_ID, name.0)).or_default() += 1;
+
+        for tag in event.facets().tags() {
+            *self.0.entry((TAG_CARDINALITY_ID, tag.0)).or_default() += 1;
+        }
+    }
+}
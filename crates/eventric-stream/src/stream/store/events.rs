@@ -1,3 +1,8 @@
+use std::{
+    ops::Range,
+    sync::Arc,
+};
+
 use bytes::{
     Buf as _,
     BufMut as _,
@@ -5,14 +10,7 @@ use bytes::{
 use derive_more::Debug;
 use error_stack::ResultExt;
 use fancy_constructor::new;
-use fjall::{
-    Database,
-    Guard,
-    Keyspace,
-    KeyspaceCreateOptions,
-    OwnedWriteBatch as Batch,
-    Slice,
-};
+use fjall::Slice;
 
 use crate::{
     error::{
@@ -32,6 +30,14 @@ use crate::{
         Metadata,
         Position,
         Timestamp,
+        operate::explain::Counters,
+        store::storage::{
+            Batch,
+            Fence,
+            Iter,
+            Keyspace,
+            Storage,
+        },
     },
 };
 
@@ -54,8 +60,34 @@ impl From<EventReader<'_>> for Event<Metadata, u64> {
         let facets = event::Facets::new(ty, tags);
 
         let timestamp = Timestamp(value.get_u64());
-        let meta = Metadata::new(position, timestamp);
+        let meta = Metadata::marked(position, timestamp, value.get_u64());
+        let data = Data(value.iter().map(ToOwned::to_owned).collect::<Vec<_>>());
+
+        Self::new(data, facets, meta)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Legacy Event Reader
+
+// An event as stored before events recorded their batch: with nothing between
+// its timestamp and its data. Which events were appended together was not
+// recorded, so each reads as a batch of its own (and none can be redacted).
+struct LegacyEventReader<'a>(Position, &'a Slice);
+
+impl From<LegacyEventReader<'_>> for Event<Metadata, u64> {
+    fn from(LegacyEventReader(position, value): LegacyEventReader<'_>) -> Self {
+        let mut value = &value[..];
+
+        let name = Name(value.get_u64());
+        let version = Version(value.get_u8());
+        let ty = Type::new(name, version);
+        let tags = (0..value.get_u8()).map(|_| Tag(value.get_u64())).collect();
+        let facets = event::Facets::new(ty, tags);
 
+        let timestamp = Timestamp(value.get_u64());
+        let meta = Metadata::new(position, timestamp, position);
         let data = Data(value.iter().map(ToOwned::to_owned).collect::<Vec<_>>());
 
         Self::new(data, facets, meta)
@@ -66,10 +98,10 @@ impl From<EventReader<'_>> for Event<Metadata, u64> {
 
 // Event Writer
 
-struct EventWriter<'a>(&'a Event<(), u64>, &'a Timestamp);
+struct EventWriter<'a>(&'a Event<(), u64>, &'a Metadata);
 
 impl From<EventWriter<'_>> for Vec<u8> {
-    fn from(EventWriter(event, timestamp): EventWriter<'_>) -> Self {
+    fn from(EventWriter(event, meta): EventWriter<'_>) -> Self {
         let mut value = Vec::new();
         let ty = event.facets().ty();
         let tags = event.facets().tags();
@@ -82,7 +114,8 @@ impl From<EventWriter<'_>> for Vec<u8> {
             value.put_u64(tag.0); // Tag (hash)
         }
 
-        value.put_u64(timestamp.0); // Timestamp
+        value.put_u64(meta.1.0); // Timestamp
+        value.put_u64(meta.marked_batch()); // Batch (first position) & Redacted
         value.put_slice(event.data().as_ref()); // Data
 
         value
@@ -95,32 +128,46 @@ impl From<EventWriter<'_>> for Vec<u8> {
 
 #[derive(new, Clone, Debug)]
 pub struct Events {
-    #[debug("Keyspace")]
     keyspace: Keyspace,
 }
 
 impl Events {
-    pub fn open(database: &Database) -> Result<Self> {
-        database
-            .keyspace("events", KeyspaceCreateOptions::default)
-            .map(Self::new)
-            .change_context(Error)
-            .attach("failed to open events keyspace")
+    pub fn open(storage: &dyn Storage) -> Result<Self> {
+        storage.keyspace("events").map(Self::new)
+    }
+
+    /// The position a forked stream's events were forked at: those before it
+    /// are read from the stream it was forked from, and cannot be removed.
+    /// `None` for a stream which is not a fork.
+    pub fn fork_position(&self) -> Option<Position> {
+        match self.keyspace.fence() {
+            Some(Fence::Key(position)) => Some(position),
+            _ => None,
+        }
+    }
+
+    /// The events of a stream forked from this one at `position`, in
+    /// `storage`: these events before `position`, and those it appends.
+    pub fn fork(&self, storage: &dyn Storage, position: Position) -> Result<Self> {
+        storage.keyspace("events").map(|delta| {
+            Self::new(Keyspace::layered(
+                self.keyspace.clone(),
+                delta,
+                Fence::Key(position),
+            ))
+        })
     }
 }
 
 impl Events {
     pub fn len(&self) -> Result<u64> {
-        let len = match self.keyspace.last_key_value() {
-            Some(guard) => guard
-                .key()
-                .map(|key| key.as_ref().get_u64() + 1)
-                .change_context(Error)
-                .attach("failed to get last key value")?,
-            _ => 0,
-        };
+        let key = self
+            .keyspace
+            .last_key()
+            .change_context(Error)
+            .attach("failed to get last key value")?;
 
-        Ok(len)
+        Ok(key.map_or(0, |key| key.as_ref().get_u64() + 1))
     }
 }
 
@@ -140,10 +187,38 @@ impl Events {
 impl Events {
     pub fn insert(&self, batch: &mut Batch, event: &Event<(), u64>, meta: &Metadata) {
         let key = meta.0.0.to_be_bytes(); // Position
-        let value: Vec<u8> = EventWriter(event, &meta.1).into(); // Event & Timestamp
+        let value: Vec<u8> = EventWriter(event, meta).into(); // Event, Timestamp & Batch
 
         batch.insert(&self.keyspace, key, value);
     }
+
+    pub fn remove(&self, batch: &mut Batch, position: Position) {
+        let key = position.0.to_be_bytes(); // Position
+
+        batch.remove(&self.keyspace, key);
+    }
+
+    /// Rewrite the events in `range`, stored as they were before events
+    /// recorded their batch, as they are now, in `batch`, returning their
+    /// metadata.
+    pub fn upgrade(&self, batch: &mut Batch, range: Range<Position>) -> Result<Vec<Metadata>> {
+        let iter = self
+            .keyspace
+            .range(range.start.0.to_be_bytes()..range.end.0.to_be_bytes());
+
+        iter.map(|item| {
+            let (key, value) = item
+                .change_context(Error)
+                .attach("failed to read event to upgrade")?;
+            let Event(data, facets, meta) =
+                LegacyEventReader(PositionReader(&key).into(), &value).into();
+
+            self.insert(batch, &Event::new(data, facets, ()), &meta);
+
+            Ok(meta)
+        })
+        .collect()
+    }
 }
 
 impl Events {
@@ -155,6 +230,14 @@ impl Events {
 
         EventsIter::new(iter)
     }
+
+    pub fn range(&self, range: Range<Position>) -> EventsIter {
+        let iter = self
+            .keyspace
+            .range(range.start.0.to_be_bytes()..range.end.0.to_be_bytes());
+
+        EventsIter::new(iter)
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -164,13 +247,23 @@ impl Events {
 #[derive(new, Debug)]
 #[new(const_fn)]
 pub struct EventsIter {
-    #[debug("Iter")]
-    iter: fjall::Iter,
+    #[new(val(None))]
+    counters: Option<Arc<Counters>>,
+    iter: Iter,
 }
 
 impl EventsIter {
-    fn next_map(guard: Guard) -> <Self as Iterator>::Item {
-        match guard.into_inner() {
+    /// Count each event read into `counters`.
+    pub fn instrument(&mut self, counters: &Arc<Counters>) {
+        self.counters = Some(Arc::clone(counters));
+    }
+
+    fn next_map(&self, item: fjall::Result<(Slice, Slice)>) -> <Self as Iterator>::Item {
+        if let Some(counters) = &self.counters {
+            counters.event();
+        }
+
+        match item {
             Ok((key, value)) => Ok(EventReader(PositionReader(&key).into(), &value).into()),
             Err(err) => Err(err)
                 .change_context(Error)
@@ -181,7 +274,9 @@ impl EventsIter {
 
 impl DoubleEndedIterator for EventsIter {
     fn next_back(&mut self) -> Option<Self::Item> {
-        self.iter.next_back().map(Self::next_map)
+        let item = self.iter.next_back()?;
+
+        Some(self.next_map(item))
     }
 }
 
@@ -189,7 +284,9 @@ impl Iterator for EventsIter {
     type Item = Result<Event<Metadata, u64>>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.iter.next().map(Self::next_map)
+        let item = self.iter.next()?;
+
+        Some(self.next_map(item))
     }
 }
 
@@ -0,0 +1,79 @@
+use std::{
+    collections::BTreeMap,
+    sync::{
+        Arc,
+        Mutex,
+        MutexGuard,
+    },
+};
+
+use derive_more::Debug;
+
+use crate::stream::Position;
+
+// =================================================================================================
+// Forks
+// =================================================================================================
+
+// Forks
+
+/// The live forks of a store, by the position each was forked at. A fork
+/// reads its base's events (and their postings and redactions) before that
+/// position through to the base, so the base may not rewrite or remove them
+/// while the fork is open. Shared by every clone of the store.
+#[derive(Clone, Debug, Default)]
+pub struct Forks(#[debug("Forks")] Arc<Mutex<BTreeMap<Position, usize>>>);
+
+impl Forks {
+    /// Record a fork at `position`, live until the returned [`Pin`] is
+    /// dropped.
+    pub fn pin(&self, position: Position) -> Pin {
+        *guard(&self.0).entry(position).or_default() += 1;
+
+        Pin {
+            forks: self.clone(),
+            position,
+        }
+    }
+
+    /// The highest position a live fork was forked at, below which the store's
+    /// events are read by a fork.
+    pub fn fence(&self) -> Option<Position> {
+        guard(&self.0)
+            .last_key_value()
+            .map(|(position, _)| *position)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Pin
+
+/// A live fork's record in the [`Forks`] of the store it was forked from,
+/// removed when the fork's store (and every clone of it) is dropped.
+#[derive(Debug)]
+pub struct Pin {
+    forks: Forks,
+    position: Position,
+}
+
+impl Drop for Pin {
+    fn drop(&mut self) {
+        let mut forks = guard(&self.forks.0);
+
+        if let Some(count) = forks.get_mut(&self.position) {
+            *count -= 1;
+
+            if *count == 0 {
+                forks.remove(&self.position);
+            }
+        }
+    }
+}
+
+fn guard(forks: &Mutex<BTreeMap<Position, usize>>) -> MutexGuard<'_, BTreeMap<Position, usize>> {
+    match forks.lock() {
+        Ok(forks) => forks,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
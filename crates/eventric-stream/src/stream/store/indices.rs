@@ -1,6 +1,10 @@
-use std::ops::{
-    ControlFlow,
-    Range,
+use std::{
+    collections::BTreeMap,
+    ops::{
+        ControlFlow,
+        Range,
+    },
+    sync::Arc,
 };
 
 use bytes::{
@@ -16,14 +20,7 @@ use error_stack::{
     ResultExt,
 };
 use fancy_constructor::new;
-use fjall::{
-    Database,
-    Guard,
-    Keyspace,
-    KeyspaceCreateOptions,
-    OwnedWriteBatch as Batch,
-    Slice,
-};
+use fjall::Slice;
 
 use crate::{
     error::{
@@ -45,14 +42,28 @@ use crate::{
         Metadata,
         Position,
         Timestamp,
-        operate::select::{
-            Selector,
-            TypeSelector,
+        operate::{
+            explain::{
+                Counters,
+                Node,
+            },
+            select::{
+                Selector,
+                TypeSelector,
+            },
         },
         store::{
             HASH_LEN,
             ID_LEN,
             POSITION_LEN,
+            cardinalities::Cardinalities,
+            storage::{
+                Batch,
+                Fence,
+                Iter,
+                Keyspace,
+                Storage,
+            },
         },
     },
 };
@@ -70,11 +81,23 @@ pub struct Indices {
 }
 
 impl Indices {
-    pub fn open(database: &Database) -> Result<Self> {
-        let keyspace = database
-            .keyspace("indices", KeyspaceCreateOptions::default)
-            .change_context(Error)
-            .attach("failed to open indices keyspace")?;
+    pub fn open(storage: &dyn Storage) -> Result<Self> {
+        let keyspace = storage.keyspace("indices")?;
+
+        let tags = Tags::new(keyspace.clone());
+        let timestamps = Timestamps::new(keyspace.clone());
+        let types = Types::new(keyspace);
+
+        Ok(Self::new(tags, timestamps, types))
+    }
+
+    /// The indices of a stream forked from this one at `position`, in
+    /// `storage`: the postings of the events before `position`, and of those
+    /// it appends.
+    pub fn fork(&self, storage: &dyn Storage, position: Position) -> Result<Self> {
+        let delta = storage.keyspace("indices")?;
+        let fence = Fence::Entry(position, posting);
+        let keyspace = Keyspace::layered(self.types.keyspace.clone(), delta, fence);
 
         let tags = Tags::new(keyspace.clone());
         let timestamps = Timestamps::new(keyspace.clone());
@@ -84,25 +107,132 @@ impl Indices {
     }
 }
 
+// The position of the event an index entry is for: the end of its key.
+fn posting(key: &[u8], _: &[u8]) -> Position {
+    let mut position = &key[key.len() - POSITION_LEN..];
+
+    Position::new(position.get_u64())
+}
+
 impl Indices {
     pub fn insert(&self, batch: &mut Batch, event: &Event<(), u64>, meta: &Metadata) {
         self.tags.insert(batch, event, meta);
         self.timestamps.insert(batch, meta);
         self.types.insert(batch, event, meta);
     }
+
+    /// Remove the postings of `event` (as [`insert`](Self::insert) wrote
+    /// them), in `batch`.
+    pub fn remove(&self, batch: &mut Batch, event: &Event<(), u64>, meta: &Metadata) {
+        self.tags.remove(batch, event, meta);
+        self.timestamps.remove(batch, meta);
+        self.types.remove(batch, event, meta);
+    }
+
+    /// Replace the timestamp posting of the event with `meta`, as it was
+    /// written before timestamp postings were keyed by position, with its
+    /// posting as [`insert`](Self::insert) writes it now, in `batch`. Its tag
+    /// and type postings are written as they always were.
+    pub fn upgrade(&self, batch: &mut Batch, meta: &Metadata) {
+        self.timestamps.upgrade(batch, meta);
+    }
 }
 
 impl Indices {
-    pub fn iterate<'a, S>(&self, selectors: S, from: Option<Position>) -> IndicesIter
+    /// Count the postings of every type name and every tag, by a full scan of
+    /// each index. Used to rebuild the cardinalities.
+    pub fn count(&self) -> Result<(BTreeMap<u64, u64>, BTreeMap<u64, u64>)> {
+        let mut types = BTreeMap::new();
+        let mut tags = BTreeMap::new();
+
+        for posting in self.type_postings() {
+            *types.entry(posting?.0).or_default() += 1;
+        }
+
+        for posting in self.tag_postings() {
+            *tags.entry(posting?.0).or_default() += 1;
+        }
+
+        Ok((types, tags))
+    }
+
+    /// Every tag posting, as (tag hash, position), in tag hash then position
+    /// order.
+    pub fn tag_postings(&self) -> impl Iterator<Item = Result<(u64, Position)>> {
+        self.tags.keyspace.prefix([TAG_INDEX_ID]).map(|item| {
+            let (key, _) = item
+                .change_context(Error)
+                .attach("failed to read tag posting")?;
+
+            let mut key = &key[ID_LEN..];
+
+            Ok((key.get_u64(), Position::new(key.get_u64())))
+        })
+    }
+
+    /// Every type posting, as (type name hash, position, version), in type
+    /// name hash then position order.
+    pub fn type_postings(&self) -> impl Iterator<Item = Result<(u64, Position, Version)>> {
+        self.types.keyspace.prefix([TYPE_INDEX_ID]).map(|item| {
+            let (key, value) = item
+                .change_context(Error)
+                .attach("failed to read type posting")?;
+
+            let mut key = &key[ID_LEN..];
+
+            Ok((
+                key.get_u64(),
+                Position::new(key.get_u64()),
+                TypeVersionReader(&value).into(),
+            ))
+        })
+    }
+}
+
+impl Indices {
+    pub fn iterate<'a, S>(
+        &self,
+        selectors: S,
+        from: Option<Position>,
+        cardinalities: &Cardinalities,
+    ) -> IndicesIter
     where
         S: IntoIterator<Item = &'a Selector<u64>>,
     {
         Union::iter(selectors.into_iter().map(|selector| match selector {
             Selector(types, None) => self.types.iterate(types.iter(), from),
-            Selector(types, Some(tags)) => Intersection::iter([
-                self.types.iterate(types.iter(), from),
-                self.tags.iterate(tags.iter(), from),
-            ]),
+            Selector(types, Some(tags)) if types.is_empty() => {
+                let mut tags = tags.iter().collect::<Vec<_>>();
+
+                tags.sort_by_key(|tag| cardinalities.estimate_tag(tag));
+
+                self.tags.iterate(tags.into_iter(), from)
+            }
+            Selector(types, Some(tags)) => {
+                // Order each intersection's children rarest first, so the child
+                // driving the leapfrog is the one with the fewest postings and
+                // the others are sought past the most positions.
+                let mut tags = tags
+                    .iter()
+                    .map(|tag| (cardinalities.estimate_tag(tag), tag))
+                    .collect::<Vec<_>>();
+
+                tags.sort_by_key(|(estimate, _)| *estimate);
+
+                let types_estimate = cardinalities.estimate_types(types);
+                let tags_estimate = tags.first().map_or(u64::MAX, |(estimate, _)| *estimate);
+
+                let types = self.types.iterate(types.iter(), from);
+                let tags = self
+                    .tags
+                    .iterate(tags.into_iter().map(|(_, tag)| tag), from);
+
+                if tags_estimate < types_estimate {
+                    Intersection::iter([tags, types])
+                } else {
+                    Intersection::iter([types, tags])
+                }
+            }
         }))
     }
 }
@@ -143,6 +273,32 @@ impl Iterator for IndicesIter {
     }
 }
 
+impl IndicesIter {
+    /// The shape of the iterator tree, with the hashes at each leaf.
+    pub fn node(&self) -> Node {
+        match self {
+            Self::Intersection(iter) => Node::Intersection(iter.iters().map(Self::node).collect()),
+            Self::Union(iter) => Node::Union(iter.iters().map(Self::node).collect()),
+            Self::Tags(iter) => Node::Tag { hash: iter.tag.0 },
+            Self::Types(iter) => Node::Type {
+                hash: iter.name.0,
+                versions: iter.range.clone(),
+            },
+        }
+    }
+
+    /// Have every leaf scan count its postings read and seeks performed into
+    /// `counters`.
+    pub fn instrument(&mut self, counters: &Arc<Counters>) {
+        match self {
+            Self::Intersection(iter) => iter.iters_mut().for_each(|iter| iter.instrument(counters)),
+            Self::Union(iter) => iter.iters_mut().for_each(|iter| iter.instrument(counters)),
+            Self::Tags(iter) => iter.counters = Some(Arc::clone(counters)),
+            Self::Types(iter) => iter.counters = Some(Arc::clone(counters)),
+        }
+    }
+}
+
 impl Seek<Position> for IndicesIter {
     // Skip every node forward to `target`: combinators seek their children, leaf
     // scans re-seek the underlying fjall range. This is what `Intersection` calls
@@ -243,7 +399,6 @@ impl From<TagPositionReader<'_>> for Position {
 
 #[derive(new, Clone, Debug)]
 struct Tags {
-    #[debug("Keyspace")]
     keyspace: Keyspace,
 }
 
@@ -256,6 +411,14 @@ impl Tags {
             batch.insert(&self.keyspace, key, value);
         }
     }
+
+    fn remove(&self, batch: &mut Batch, event: &Event<(), u64>, meta: &Metadata) {
+        for tag in event.facets().tags() {
+            let key: TagKey = TagKeyWriter(tag, &meta.0).into(); // Tag & Position
+
+            batch.remove(&self.keyspace, key);
+        }
+    }
 }
 
 impl Tags {
@@ -292,19 +455,19 @@ impl Tags {
 #[derive(new, Debug)]
 #[new(const_fn)]
 pub struct TagsIter {
-    #[debug("Keyspace")]
     keyspace: Keyspace,
     tag: Tag<u64>,
     lower: Position,
-    #[debug("Iter")]
-    iter: fjall::Iter,
+    iter: Iter,
+    #[new(val(None))]
+    counters: Option<Arc<Counters>>,
 }
 
 impl TagsIter {
     #[rustfmt::skip]
-    fn next_map(guard: Guard) -> <Self as Iterator>::Item {
-        match guard.key() {
-            Ok(key) => Ok(TagPositionReader(&key).into()),
+    fn next_map(item: fjall::Result<(Slice, Slice)>) -> <Self as Iterator>::Item {
+        match item {
+            Ok((key, _)) => Ok(TagPositionReader(&key).into()),
             Err(err) => Err(err).change_context(Error).attach("failed to map next tag"),
         }
     }
@@ -314,6 +477,8 @@ impl Seek<Position> for TagsIter {
     // Re-range the scan to `[tag, target] .. [tag, MAX]`, so the next item is the
     // first position `>= target` for this tag — one LSM seek instead of stepping.
     fn seek(&mut self, target: Position) {
+        self.sought();
+
         let from: TagKey = TagKeyWriter(&self.tag, &target).into();
         let to: TagKey = TagKeyWriter(&self.tag, &Position::MAX).into();
 
@@ -324,6 +489,8 @@ impl Seek<Position> for TagsIter {
     // target, preserving the query's lower bound), so the next `next_back` is the
     // last position `<= target` for this tag.
     fn seek_back(&mut self, target: Position) {
+        self.sought();
+
         let from: TagKey = TagKeyWriter(&self.tag, &self.lower).into();
         let to: TagKey = TagKeyWriter(&self.tag, &target).into();
 
@@ -331,9 +498,27 @@ impl Seek<Position> for TagsIter {
     }
 }
 
+impl TagsIter {
+    fn read(&self, item: fjall::Result<(Slice, Slice)>) -> <Self as Iterator>::Item {
+        if let Some(counters) = &self.counters {
+            counters.posting();
+        }
+
+        Self::next_map(item)
+    }
+
+    fn sought(&self) {
+        if let Some(counters) = &self.counters {
+            counters.seek();
+        }
+    }
+}
+
 impl DoubleEndedIterator for TagsIter {
     fn next_back(&mut self) -> Option<Self::Item> {
-        self.iter.next_back().map(Self::next_map)
+        let item = self.iter.next_back()?;
+
+        Some(self.read(item))
     }
 }
 
@@ -341,7 +526,9 @@ impl Iterator for TagsIter {
     type Item = Result<Position>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.iter.next().map(Self::next_map)
+        let item = self.iter.next()?;
+
+        Some(self.read(item))
     }
 }
 
@@ -350,8 +537,9 @@ impl Iterator for TagsIter {
 // Timestamp Constants
 
 static TIMESTAMP_INDEX_ID: u8 = 1;
-static TIMESTAMP_KEY_LEN: usize = ID_LEN + TIMESTAMP_LEN;
+static TIMESTAMP_KEY_LEN: usize = TIMESTAMP_PREFIX_LEN + POSITION_LEN;
 static TIMESTAMP_LEN: usize = size_of::<u64>();
+static TIMESTAMP_PREFIX_LEN: usize = ID_LEN + TIMESTAMP_LEN;
 
 // -------------------------------------------------------------------------------------------------
 
@@ -359,17 +547,18 @@ static TIMESTAMP_LEN: usize = size_of::<u64>();
 
 type TimestampKey = [u8; TIMESTAMP_KEY_LEN];
 
-struct TimestampKeyWriter<'a>(&'a Timestamp);
+struct TimestampKeyWriter<'a>(&'a Timestamp, &'a Position);
 
 impl From<TimestampKeyWriter<'_>> for TimestampKey {
-    fn from(TimestampKeyWriter(timestamp): TimestampKeyWriter<'_>) -> Self {
+    fn from(TimestampKeyWriter(timestamp, position): TimestampKeyWriter<'_>) -> Self {
         let mut key = TimestampKey::default();
 
         {
             let mut key = &mut key[..];
 
             key.put_u8(TIMESTAMP_INDEX_ID);
-            key.put_u64(timestamp.0);
+            key.put_u64(timestamp.0); // Timestamp
+            key.put_u64(position.0); // Position
         }
 
         key
@@ -378,21 +567,63 @@ impl From<TimestampKeyWriter<'_>> for TimestampKey {
 
 // -------------------------------------------------------------------------------------------------
 
+// Timestamp Prefix Writer
+
+type TimestampPrefix = [u8; TIMESTAMP_PREFIX_LEN];
+
+struct TimestampPrefixWriter<'a>(&'a Timestamp);
+
+impl From<TimestampPrefixWriter<'_>> for TimestampPrefix {
+    fn from(TimestampPrefixWriter(timestamp): TimestampPrefixWriter<'_>) -> Self {
+        let mut prefix = TimestampPrefix::default();
+
+        {
+            let mut prefix = &mut prefix[..];
+
+            prefix.put_u8(TIMESTAMP_INDEX_ID);
+            prefix.put_u64(timestamp.0);
+        }
+
+        prefix
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
 // Timestamps
 
 #[derive(new, Clone, Debug)]
 struct Timestamps {
-    #[debug("Keyspace")]
     keyspace: Keyspace,
 }
 
 impl Timestamps {
+    // A posting per event, keyed by its timestamp and position (like a tag
+    // posting), so events sharing a timestamp each have their own, and one is
+    // removed without touching the others.
     fn insert(&self, batch: &mut Batch, meta: &Metadata) {
-        let key: TimestampKey = TimestampKeyWriter(&meta.1).into(); // Timestamp
-        let value = meta.0.0.to_be_bytes(); // Position
+        let key: TimestampKey = TimestampKeyWriter(&meta.1, &meta.0).into(); // Timestamp & Position
+        let value = []; // Empty
 
         batch.insert(&self.keyspace, key, value);
     }
+
+    fn remove(&self, batch: &mut Batch, meta: &Metadata) {
+        let key: TimestampKey = TimestampKeyWriter(&meta.1, &meta.0).into(); // Timestamp & Position
+
+        batch.remove(&self.keyspace, key);
+    }
+
+    // A posting keyed by the timestamp alone (as they once were) held only the
+    // last position stamped with it, so the events sharing a timestamp each get
+    // their own posting, and the one they shared goes.
+    fn upgrade(&self, batch: &mut Batch, meta: &Metadata) {
+        let prefix: TimestampPrefix = TimestampPrefixWriter(&meta.1).into(); // Timestamp
+
+        batch.remove(&self.keyspace, prefix);
+
+        self.insert(batch, meta);
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -484,7 +715,6 @@ impl From<TypeVersionReader<'_>> for Version {
 
 #[derive(new, Clone, Debug)]
 struct Types {
-    #[debug("Keyspace")]
     keyspace: Keyspace,
 }
 
@@ -496,6 +726,12 @@ impl Types {
 
         batch.insert(&self.keyspace, key, value);
     }
+
+    fn remove(&self, batch: &mut Batch, event: &Event<(), u64>, meta: &Metadata) {
+        let key: TypeKey = TypeKeyWriter(event.facets().ty().name(), &meta.0).into(); // Type Name & Position
+
+        batch.remove(&self.keyspace, key);
+    }
 }
 
 impl Types {
@@ -534,19 +770,21 @@ impl Types {
 #[derive(new, Debug)]
 #[new(const_fn)]
 pub struct TypesIter {
-    #[debug("Keyspace")]
     keyspace: Keyspace,
     name: Name<u64>,
     lower: Position,
-    #[debug("Iter")]
-    iter: fjall::Iter,
+    iter: Iter,
     range: Range<Version>,
+    #[new(val(None))]
+    counters: Option<Arc<Counters>>,
 }
 
 impl Seek<Position> for TypesIter {
     // Re-range the scan forward to `target` for this type name; the version filter
     // is unaffected (it is applied per item in `next_map`).
     fn seek(&mut self, target: Position) {
+        self.sought();
+
         let from: TypeKey = TypeKeyWriter(&self.name, &target).into();
         let to: TypeKey = TypeKeyWriter(&self.name, &Position::MAX).into();
 
@@ -556,6 +794,8 @@ impl Seek<Position> for TypesIter {
     // The reverse: re-range to `[name, lower] ..= [name, target]` (inclusive of
     // target, preserving the query's lower bound); the version filter rides along.
     fn seek_back(&mut self, target: Position) {
+        self.sought();
+
         let from: TypeKey = TypeKeyWriter(&self.name, &self.lower).into();
         let to: TypeKey = TypeKeyWriter(&self.name, &target).into();
 
@@ -563,6 +803,14 @@ impl Seek<Position> for TypesIter {
     }
 }
 
+impl TypesIter {
+    fn sought(&self) {
+        if let Some(counters) = &self.counters {
+            counters.seek();
+        }
+    }
+}
+
 impl TypesIter {
     #[inline]
     fn check<T, U>(mut f: impl FnMut(T) -> Option<U>) -> impl FnMut((), T) -> ControlFlow<U> {
@@ -572,8 +820,16 @@ impl TypesIter {
         }
     }
 
-    fn next_map(guard: Guard, range: &Range<Version>) -> Option<<Self as Iterator>::Item> {
-        match guard.into_inner() {
+    fn next_map(
+        item: fjall::Result<(Slice, Slice)>,
+        range: &Range<Version>,
+        counters: Option<&Counters>,
+    ) -> Option<<Self as Iterator>::Item> {
+        if let Some(counters) = counters {
+            counters.posting();
+        }
+
+        match item {
             Ok((key, value)) => range
                 .contains::<Version>(&TypeVersionReader(&value).into())
                 .then(|| Ok(TypePositionReader(&key).into())),
@@ -589,7 +845,10 @@ impl TypesIter {
 impl DoubleEndedIterator for TypesIter {
     fn next_back(&mut self) -> Option<Self::Item> {
         self.iter
-            .try_rfold((), Self::check(|x| Self::next_map(x, &self.range)))
+            .try_rfold(
+                (),
+                Self::check(|x| Self::next_map(x, &self.range, self.counters.as_deref())),
+            )
             .break_value()
     }
 }
@@ -599,7 +858,10 @@ impl Iterator for TypesIter {
 
     fn next(&mut self) -> Option<Self::Item> {
         self.iter
-            .try_fold((), Self::check(|x| Self::next_map(x, &self.range)))
+            .try_fold(
+                (),
+                Self::check(|x| Self::next_map(x, &self.range, self.counters.as_deref())),
+            )
             .break_value()
     }
 }
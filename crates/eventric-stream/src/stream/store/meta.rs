@@ -0,0 +1,165 @@
+use std::ops::Range;
+
+use bytes::{
+    Buf as _,
+    BufMut as _,
+};
+use error_stack::ResultExt;
+use fancy_constructor::new;
+
+use crate::{
+    error::{
+        Error,
+        Result,
+    },
+    stream::{
+        Durability,
+        Position,
+        store::storage::{
+            Batch,
+            Keyspace,
+            Storage,
+        },
+    },
+};
+
+// =================================================================================================
+// Meta
+// =================================================================================================
+
+// Constants
+
+static EPOCH_KEY: &[u8] = b"epoch";
+static FORMAT_KEY: &[u8] = b"format";
+static UNINDEXED_KEY: &[u8] = b"unindexed";
+static UPGRADED_KEY: &[u8] = b"upgraded";
+
+/// The format a stream's records are stored in by this version: each event
+/// with its batch (and redaction mark) between its timestamp and its data, and
+/// a timestamp posting per event, keyed by its position. A stream with events
+/// and no format recorded was written before this one, with neither.
+pub static FORMAT: u64 = 2;
+
+// -------------------------------------------------------------------------------------------------
+
+// Meta
+
+/// The stream's own bookkeeping, apart from its events: the writer epoch,
+/// advanced each time the stream is opened for writing, so every writer the
+/// stream has had is told apart by a number no earlier writer held; the
+/// [`FORMAT`] its records are stored in, and how far an upgrade from an earlier
+/// one has got; and the range of events imported without their postings, still
+/// to be indexed.
+#[derive(new, Clone, Debug)]
+#[new(const_fn, vis())]
+pub struct Meta {
+    keyspace: Keyspace,
+}
+
+impl Meta {
+    pub fn open(storage: &dyn Storage) -> Result<Self> {
+        storage.keyspace("meta").map(Self::new)
+    }
+}
+
+impl Meta {
+    /// The epoch of the stream's latest writer: `0` if it has never been
+    /// opened for writing.
+    pub fn epoch(&self) -> Result<u64> {
+        let value = self
+            .keyspace
+            .get(EPOCH_KEY)
+            .change_context(Error)
+            .attach("failed to get epoch from meta keyspace")?;
+
+        Ok(value.map_or(0, |value| value.as_ref().get_u64()))
+    }
+
+    /// Advance the epoch for a new writer, returning it. Committed durably
+    /// (whatever the stream's durability), so an epoch is never handed out
+    /// twice, even across a crash.
+    pub fn advance(&self, storage: &dyn Storage) -> Result<u64> {
+        let epoch = self.epoch()? + 1;
+        let mut batch = storage.batch(Durability::Sync);
+
+        batch.insert(&self.keyspace, EPOCH_KEY, epoch.to_be_bytes());
+        batch
+            .commit()
+            .change_context(Error)
+            .attach("failed to commit writer epoch")?;
+
+        Ok(epoch)
+    }
+}
+
+impl Meta {
+    /// The range of events written without their postings (and counts), if
+    /// any: recorded in the batch which writes them, so it survives a crash.
+    pub fn unindexed(&self) -> Result<Option<Range<Position>>> {
+        let value = self
+            .keyspace
+            .get(UNINDEXED_KEY)
+            .change_context(Error)
+            .attach("failed to get unindexed range from meta keyspace")?;
+
+        Ok(value.map(|value| {
+            let mut value = value.as_ref();
+
+            Position::new(value.get_u64())..Position::new(value.get_u64())
+        }))
+    }
+
+    /// Record `range` as the events still to be indexed, in `batch` — or none,
+    /// if it is empty.
+    pub fn defer(&self, batch: &mut Batch, range: Range<Position>) {
+        if range.is_empty() {
+            batch.remove(&self.keyspace, UNINDEXED_KEY);
+
+            return;
+        }
+
+        let mut value = Vec::with_capacity(2 * size_of::<u64>());
+
+        value.put_u64(range.start.0);
+        value.put_u64(range.end.0);
+
+        batch.insert(&self.keyspace, UNINDEXED_KEY, value);
+    }
+}
+
+impl Meta {
+    /// The format the stream's records are stored in, if recorded.
+    pub fn format(&self) -> Result<Option<u64>> {
+        let value = self
+            .keyspace
+            .get(FORMAT_KEY)
+            .change_context(Error)
+            .attach("failed to get format from meta keyspace")?;
+
+        Ok(value.map(|value| value.as_ref().get_u64()))
+    }
+
+    /// Record the stream's records as stored in the current [`FORMAT`], in
+    /// `batch`, ending any upgrade.
+    pub fn format_current(&self, batch: &mut Batch) {
+        batch.insert(&self.keyspace, FORMAT_KEY, FORMAT.to_be_bytes());
+        batch.remove(&self.keyspace, UPGRADED_KEY);
+    }
+
+    /// The position before which the records of a stream being upgraded from
+    /// an earlier format are rewritten, if an upgrade has begun.
+    pub fn upgraded(&self) -> Result<Option<Position>> {
+        let value = self
+            .keyspace
+            .get(UPGRADED_KEY)
+            .change_context(Error)
+            .attach("failed to get upgrade progress from meta keyspace")?;
+
+        Ok(value.map(|value| Position::new(value.as_ref().get_u64())))
+    }
+
+    /// Record the records before `position` as upgraded, in `batch`.
+    pub fn upgrade(&self, batch: &mut Batch, position: Position) {
+        batch.insert(&self.keyspace, UPGRADED_KEY, position.0.to_be_bytes());
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::BTreeMap;
+
+use bytes::BufMut as _;
+use derive_more::Debug;
+use error_stack::ResultExt;
+use fancy_constructor::new;
+
+use crate::{
+    error::{
+        Error,
+        Result,
+    },
+    event::Event,
+    stream::{
+        Metadata,
+        store::{
+            HASH_LEN,
+            ID_LEN,
+            storage::{
+                Batch,
+                Fence,
+                Keyspace,
+                Storage,
+            },
+        },
+    },
+    utils::hashing,
+};
+
+// =================================================================================================
+// Names
+// =================================================================================================
+
+// Constants
+
+static NAME_KEY_LEN: usize = ID_LEN + HASH_LEN;
+static TAG_NAME_ID: u8 = 0;
+static TYPE_NAME_ID: u8 = 2;
+
+// -------------------------------------------------------------------------------------------------
+
+// Name Key Writer
+
+type NameKey = [u8; NAME_KEY_LEN];
+
+struct NameKeyWriter(u8, u64);
+
+impl From<NameKeyWriter> for NameKey {
+    fn from(NameKeyWriter(id, hash): NameKeyWriter) -> Self {
+        let mut key = NameKey::default();
+
+        {
+            let mut key = &mut key[..];
+
+            key.put_u8(id); // Tag or Type
+            key.put_u64(hash); // Tag or Type Name (hash)
+        }
+
+        key
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Names
+
+/// The string forms of the hashed type names and tags, so they can be listed
+/// by name: everything else stores only the hash. Written with the first
+/// event to carry each one, so a name is recoverable for any event appended
+/// since the dictionary was introduced.
+#[derive(new, Clone, Debug)]
+#[new(const_fn, vis())]
+pub struct Names {
+    keyspace: Keyspace,
+}
+
+impl Names {
+    pub fn open(storage: &dyn Storage) -> Result<Self> {
+        storage.keyspace("names").map(Self::new)
+    }
+
+    /// The names of a stream forked from this one, in `storage`: every name
+    /// recorded here (a superset of those before the fork, which is harmless
+    /// for a dictionary), and its own.
+    pub fn fork(&self, storage: &dyn Storage) -> Result<Self> {
+        storage
+            .keyspace("names")
+            .map(|delta| Self::new(Keyspace::layered(self.keyspace.clone(), delta, Fence::Open)))
+    }
+}
+
+impl Names {
+    /// The string form of the type name hashed to `hash`, if recorded.
+    pub fn ty(&self, hash: u64) -> Result<Option<String>> {
+        self.get(TYPE_NAME_ID, hash)
+    }
+
+    /// The string form of the tag hashed to `hash`, if recorded.
+    pub fn tag(&self, hash: u64) -> Result<Option<String>> {
+        self.get(TAG_NAME_ID, hash)
+    }
+
+    fn get(&self, id: u8, hash: u64) -> Result<Option<String>> {
+        let key: NameKey = NameKeyWriter(id, hash).into();
+        let value = self
+            .keyspace
+            .get(key)
+            .change_context(Error)
+            .attach("failed to get value from names keyspace")?;
+
+        Ok(value.map(|value| String::from_utf8_lossy(&value).into_owned()))
+    }
+
+    /// The recorded string forms of the type names and of the tags carried by
+    /// `events`, keyed by hash, as (types, tags). Hashes with no recorded name
+    /// are left out.
+    pub fn resolve(
+        &self,
+        events: &[Event<Metadata, u64>],
+    ) -> Result<(BTreeMap<u64, String>, BTreeMap<u64, String>)> {
+        let mut types = BTreeMap::new();
+        let mut tags = BTreeMap::new();
+
+        for event in events {
+            let hash = event.facets().ty().name().0;
+
+            if !types.contains_key(&hash)
+                && let Some(name) = self.ty(hash)?
+            {
+                types.insert(hash, name);
+            }
+
+            for tag in event.facets().tags() {
+                if !tags.contains_key(&tag.0)
+                    && let Some(name) = self.tag(tag.0)?
+                {
+                    tags.insert(tag.0, name);
+                }
+            }
+        }
+
+        Ok((types, tags))
+    }
+}
+
+impl Names {
+    /// Record the names in `pending` not yet recorded, in `batch`.
+    pub fn insert(&self, batch: &mut Batch, pending: Pending) -> Result<()> {
+        for ((id, hash), name) in pending.0 {
+            let key: NameKey = NameKeyWriter(id, hash).into();
+            let known = self
+                .keyspace
+                .contains_key(key)
+                .change_context(Error)
+                .attach("failed to check names keyspace")?;
+
+            if !known {
+                batch.insert(&self.keyspace, key, name);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Pending
+
+/// The distinct names carried by one batch's events, keyed by (name ID, hash).
+#[derive(Debug, Default)]
+pub struct Pending(BTreeMap<(u8, u64), String>);
+
+impl Pending {
+    pub fn add(&mut self, event: &Event<(), String>) {
+        let name = &event.facets().ty().name().0;
+
+        self.0
+            .entry((TYPE_NAME_ID, hashing::hash(name)))
+            .or_insert_with(|| name.clone());
+
+        for tag in event.facets().tags() {
+            self.0
+                .entry((TAG_NAME_ID, hashing::hash(&tag.0)))
+                .or_insert_with(|| tag.0.clone());
+        }
+    }
+
+    /// The names resolved elsewhere (by [`Names::resolve`]), keyed by hash.
+    pub fn resolved(types: BTreeMap<u64, String>, tags: BTreeMap<u64, String>) -> Self {
+        let types = types
+            .into_iter()
+            .map(|(hash, name)| ((TYPE_NAME_ID, hash), name));
+        let tags = tags
+            .into_iter()
+            .map(|(hash, name)| ((TAG_NAME_ID, hash), name));
+
+        Self(types.chain(tags).collect())
+    }
+}
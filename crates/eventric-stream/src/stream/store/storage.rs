@@ -0,0 +1,353 @@
+mod layered;
+mod memory;
+
+use std::{
+    ops::{
+        Bound,
+        RangeBounds,
+    },
+    sync::Arc,
+};
+
+use derive_more::Debug;
+use error_stack::ResultExt as _;
+use fancy_constructor::new;
+use fjall::{
+    Database,
+    Guard,
+    KeyspaceCreateOptions,
+    OwnedWriteBatch,
+    PersistMode,
+    Slice,
+};
+
+pub use self::{
+    layered::Fence,
+    memory::Memory,
+};
+use crate::{
+    error::{
+        Error,
+        Result,
+    },
+    stream::Durability,
+};
+
+// =================================================================================================
+// Storage
+// =================================================================================================
+
+/// The backend the store's keyspaces live in: opens them by name, and makes
+/// the write batches which commit to them. Implemented by a fjall `Database`
+/// (on disk) and by [`Memory`] (a process-local map, for fast tests), which
+/// the store then uses identically — so every `Select`/`Append`/DCB semantic
+/// is shared code above this trait.
+pub trait Storage: Send + Sync {
+    /// Open the keyspace `name`, creating it if absent. Opening a name again
+    /// yields the same keyspace.
+    fn keyspace(&self, name: &str) -> Result<Keyspace>;
+
+    /// Whether the keyspace `name` exists, without creating it.
+    fn exists(&self, name: &str) -> bool;
+
+    /// A new, empty write batch, to be committed with `durability`.
+    fn batch(&self, durability: Durability) -> Batch;
+
+    /// Make every commit so far durable (a no-op where nothing is).
+    fn persist(&self) -> Result<()>;
+}
+
+impl Storage for Database {
+    fn keyspace(&self, name: &str) -> Result<Keyspace> {
+        Database::keyspace(self, name, KeyspaceCreateOptions::default)
+            .map(Keyspace::Fjall)
+            .change_context(Error)
+            .attach_with(|| format!("failed to open {name} keyspace"))
+    }
+
+    fn exists(&self, name: &str) -> bool {
+        Database::keyspace_exists(self, name)
+    }
+
+    fn batch(&self, durability: Durability) -> Batch {
+        let mode = match durability {
+            Durability::Buffer => PersistMode::Buffer,
+            Durability::Sync => PersistMode::SyncAll,
+        };
+
+        Batch::Fjall(Database::batch(self).durability(Some(mode)))
+    }
+
+    fn persist(&self) -> Result<()> {
+        Database::persist(self, PersistMode::SyncAll)
+            .change_context(Error)
+            .attach("failed to persist database")
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Namespaced
+
+/// A named stream's view of a shared [`Storage`]: every keyspace it opens is
+/// prefixed with the stream's name (`<name>.events`, and so on), so streams
+/// sharing a backend never share a keyspace.
+#[derive(new, Debug)]
+#[new(const_fn)]
+pub struct Namespaced {
+    name: String,
+    #[debug("Storage")]
+    storage: Arc<dyn Storage>,
+}
+
+impl Storage for Namespaced {
+    fn keyspace(&self, name: &str) -> Result<Keyspace> {
+        self.storage.keyspace(&format!("{}.{name}", self.name))
+    }
+
+    fn exists(&self, name: &str) -> bool {
+        self.storage.exists(&format!("{}.{name}", self.name))
+    }
+
+    fn batch(&self, durability: Durability) -> Batch {
+        self.storage.batch(durability)
+    }
+
+    fn persist(&self) -> Result<()> {
+        self.storage.persist()
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Keyspace
+
+/// A keyspace of one [`Storage`] backend: an ordered map of byte keys to byte
+/// values. Reads return fjall's error type on both backends (the in-memory
+/// one never fails), so callers attach context to them in one way. A forked
+/// stream's keyspaces are layered over those of the stream it was forked from
+/// (see [`Keyspace::layered`]).
+#[derive(Clone, Debug)]
+pub enum Keyspace {
+    Fjall(#[debug("Keyspace")] fjall::Keyspace),
+    Layered(Box<layered::Keyspace>),
+    Memory(memory::Keyspace),
+}
+
+impl Keyspace {
+    /// A keyspace written to `delta`, reading through to `base` up to the
+    /// `fence` where `delta` holds no entry.
+    pub fn layered(base: Self, delta: Self, fence: Fence) -> Self {
+        Self::Layered(Box::new(layered::Keyspace::new(base, delta, fence)))
+    }
+
+    /// The fence of a layered keyspace's base, or `None` if it is not layered.
+    pub fn fence(&self) -> Option<Fence> {
+        match self {
+            Self::Layered(keyspace) => Some(keyspace.fence),
+            _ => None,
+        }
+    }
+}
+
+impl Keyspace {
+    pub fn contains_key<K>(&self, key: K) -> fjall::Result<bool>
+    where
+        K: AsRef<[u8]>,
+    {
+        match self {
+            Self::Fjall(keyspace) => keyspace.contains_key(key),
+            Self::Layered(keyspace) => keyspace.get(key.as_ref()).map(|value| value.is_some()),
+            Self::Memory(keyspace) => Ok(keyspace.get(key.as_ref()).is_some()),
+        }
+    }
+
+    pub fn get<K>(&self, key: K) -> fjall::Result<Option<Slice>>
+    where
+        K: AsRef<[u8]>,
+    {
+        match self {
+            Self::Fjall(keyspace) => keyspace.get(key),
+            Self::Layered(keyspace) => keyspace.get(key.as_ref()),
+            Self::Memory(keyspace) => Ok(keyspace.get(key.as_ref())),
+        }
+    }
+
+    pub fn is_empty(&self) -> fjall::Result<bool> {
+        match self {
+            Self::Fjall(keyspace) => keyspace.is_empty(),
+            Self::Layered(_) => self.iter().next().transpose().map(|item| item.is_none()),
+            Self::Memory(keyspace) => Ok(keyspace.is_empty()),
+        }
+    }
+
+    pub fn last_key(&self) -> fjall::Result<Option<Slice>> {
+        match self {
+            Self::Fjall(keyspace) => keyspace.last_key_value().map(Guard::key).transpose(),
+            Self::Layered(keyspace) => keyspace.last_key(),
+            Self::Memory(keyspace) => Ok(keyspace.last_key()),
+        }
+    }
+}
+
+impl Keyspace {
+    pub fn iter(&self) -> Iter {
+        match self {
+            Self::Fjall(keyspace) => Iter::Fjall(keyspace.iter()),
+            Self::Layered(keyspace) => {
+                Iter::Layered(Box::new(keyspace.range(Bound::Unbounded, Bound::Unbounded)))
+            }
+            Self::Memory(keyspace) => {
+                Iter::Memory(keyspace.range(Bound::Unbounded, Bound::Unbounded))
+            }
+        }
+    }
+
+    pub fn prefix<K>(&self, prefix: K) -> Iter
+    where
+        K: AsRef<[u8]>,
+    {
+        match self {
+            Self::Fjall(keyspace) => Iter::Fjall(keyspace.prefix(prefix)),
+            Self::Layered(keyspace) => {
+                let prefix = prefix.as_ref();
+
+                Iter::Layered(Box::new(
+                    keyspace.range(Bound::Included(prefix.into()), successor(prefix)),
+                ))
+            }
+            Self::Memory(keyspace) => {
+                let prefix = prefix.as_ref();
+
+                Iter::Memory(keyspace.range(Bound::Included(prefix.into()), successor(prefix)))
+            }
+        }
+    }
+
+    pub fn range<K, R>(&self, range: R) -> Iter
+    where
+        K: AsRef<[u8]>,
+        R: RangeBounds<K>,
+    {
+        match self {
+            Self::Fjall(keyspace) => Iter::Fjall(keyspace.range(range)),
+            Self::Layered(keyspace) => {
+                let lower = range.start_bound().map(|key| key.as_ref().into());
+                let upper = range.end_bound().map(|key| key.as_ref().into());
+
+                Iter::Layered(Box::new(keyspace.range(lower, upper)))
+            }
+            Self::Memory(keyspace) => {
+                let lower = range.start_bound().map(|key| key.as_ref().into());
+                let upper = range.end_bound().map(|key| key.as_ref().into());
+
+                Iter::Memory(keyspace.range(lower, upper))
+            }
+        }
+    }
+}
+
+// The exclusive upper bound of the keys starting with `prefix`: the prefix with
+// its last byte below `0xFF` incremented (and anything after it dropped), or
+// unbounded if there is no such byte.
+fn successor(prefix: &[u8]) -> Bound<Slice> {
+    match prefix.iter().rposition(|byte| *byte < u8::MAX) {
+        Some(index) => {
+            let mut upper = prefix[..=index].to_vec();
+
+            upper[index] += 1;
+
+            Bound::Excluded(upper.into())
+        }
+        None => Bound::Unbounded,
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Iterator
+
+/// A double-ended scan over a snapshot of a [`Keyspace`] range, yielding
+/// `(key, value)` pairs in key order.
+#[derive(Debug)]
+pub enum Iter {
+    Fjall(#[debug("Iter")] fjall::Iter),
+    Layered(Box<layered::Iter>),
+    Memory(memory::Iter),
+}
+
+impl DoubleEndedIterator for Iter {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::Fjall(iter) => iter.next_back().map(Guard::into_inner),
+            Self::Layered(iter) => iter.next_back(),
+            Self::Memory(iter) => iter.next_back().map(Ok),
+        }
+    }
+}
+
+impl Iterator for Iter {
+    type Item = fjall::Result<(Slice, Slice)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::Fjall(iter) => iter.next().map(Guard::into_inner),
+            Self::Layered(iter) => iter.next(),
+            Self::Memory(iter) => iter.next().map(Ok),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Batch
+
+/// An atomic set of writes to the keyspaces of one [`Storage`] backend, made
+/// by [`Storage::batch`]. A write to a layered keyspace is made to its delta.
+#[derive(Debug)]
+pub enum Batch {
+    Fjall(#[debug("Batch")] OwnedWriteBatch),
+    Memory(memory::Batch),
+}
+
+impl Batch {
+    pub fn insert<K, V>(&mut self, keyspace: &Keyspace, key: K, value: V)
+    where
+        K: Into<Slice>,
+        V: Into<Slice>,
+    {
+        match (self, keyspace) {
+            (batch, Keyspace::Layered(keyspace)) => batch.insert(&keyspace.delta, key, value),
+            (Self::Fjall(batch), Keyspace::Fjall(keyspace)) => batch.insert(keyspace, key, value),
+            (Self::Memory(batch), Keyspace::Memory(keyspace)) => {
+                batch.insert(keyspace, key.into(), value.into());
+            }
+            _ => unreachable!("batch and keyspace from different storage backends"),
+        }
+    }
+
+    pub fn remove<K>(&mut self, keyspace: &Keyspace, key: K)
+    where
+        K: Into<Slice>,
+    {
+        match (self, keyspace) {
+            (batch, Keyspace::Layered(keyspace)) => batch.remove(&keyspace.delta, key),
+            (Self::Fjall(batch), Keyspace::Fjall(keyspace)) => batch.remove(keyspace, key),
+            (Self::Memory(batch), Keyspace::Memory(keyspace)) => {
+                batch.remove(keyspace, key.into());
+            }
+            _ => unreachable!("batch and keyspace from different storage backends"),
+        }
+    }
+
+    pub fn commit(self) -> fjall::Result<()> {
+        match self {
+            Self::Fjall(batch) => batch.commit(),
+            Self::Memory(batch) => {
+                batch.commit();
+
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,292 @@
+use std::{
+    cmp::Ordering,
+    ops::Bound,
+};
+
+use bytes::Buf as _;
+use derive_more::Debug;
+use double_ended_peekable::{
+    DoubleEndedPeekable,
+    DoubleEndedPeekableExt as _,
+};
+use fancy_constructor::new;
+use fjall::Slice;
+
+use crate::stream::{
+    Position,
+    store::storage,
+};
+
+// =================================================================================================
+// Layered
+// =================================================================================================
+
+// Fence
+
+/// Where the base of a layered [`Keyspace`] ends: the position it was forked
+/// at, past which the base's entries (written to it since) are not part of the
+/// layered keyspace, and how an entry's position is found.
+#[derive(Clone, Copy, Debug)]
+pub enum Fence {
+    /// Every entry of the base belongs (as for names, which a stream only ever
+    /// adds to).
+    Open,
+    /// The key begins with the entry's position, so the base is read as a
+    /// range ending at the fence.
+    Key(Position),
+    /// The entry's position is read from its key and value by the function.
+    Entry(Position, fn(&[u8], &[u8]) -> Position),
+}
+
+impl Fence {
+    fn admits(&self, key: &[u8], value: &[u8]) -> bool {
+        match self {
+            Self::Open => true,
+            Self::Key(fence) => Position::new((&key[..]).get_u64()) < *fence,
+            Self::Entry(fence, position) => position(key, value) < *fence,
+        }
+    }
+
+    // An upper bound of a scan of the base, narrowed to end at a key fence.
+    fn upper(&self, upper: Bound<Slice>) -> Bound<Slice> {
+        let Self::Key(fence) = self else {
+            return upper;
+        };
+
+        let fence = Slice::from(fence.0.to_be_bytes());
+
+        match upper {
+            Bound::Included(upper) if upper < fence => Bound::Included(upper),
+            Bound::Excluded(upper) if upper <= fence => Bound::Excluded(upper),
+            _ => Bound::Excluded(fence),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Keyspace
+
+/// A keyspace read through to a read-only base beneath it: entries are
+/// written to the delta above, which shadows the base where both hold a key,
+/// and the base is read only up to its [`Fence`]. The base is never written —
+/// a remove takes an entry from the delta only.
+#[derive(new, Clone, Debug)]
+#[new(const_fn)]
+pub struct Keyspace {
+    base: storage::Keyspace,
+    pub(super) delta: storage::Keyspace,
+    pub(super) fence: Fence,
+}
+
+impl Keyspace {
+    pub fn get(&self, key: &[u8]) -> fjall::Result<Option<Slice>> {
+        if let Some(value) = self.delta.get(key)? {
+            return Ok(Some(value));
+        }
+
+        Ok(self
+            .base
+            .get(key)?
+            .filter(|value| self.fence.admits(key, value)))
+    }
+
+    pub fn last_key(&self) -> fjall::Result<Option<Slice>> {
+        self.range(Bound::Unbounded, Bound::Unbounded)
+            .next_back()
+            .transpose()
+            .map(|item| item.map(|(key, _)| key))
+    }
+
+    pub fn range(&self, lower: Bound<Slice>, upper: Bound<Slice>) -> Iter {
+        let base = self
+            .base
+            .range((lower.clone(), self.fence.upper(upper.clone())));
+        let delta = self.delta.range((lower, upper));
+
+        Iter::new(
+            base.double_ended_peekable(),
+            delta.double_ended_peekable(),
+            self.fence,
+        )
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Iterator
+
+type Item = fjall::Result<(Slice, Slice)>;
+
+/// A scan of a layered [`Keyspace`]: the scans of the base (skipping entries
+/// past the fence) and of the delta, merged in key order, with the delta's
+/// entry taken where both hold a key. An error from either is yielded as it
+/// is met.
+#[derive(new, Debug)]
+#[new(const_fn, vis())]
+pub struct Iter {
+    base: DoubleEndedPeekable<storage::Iter>,
+    delta: DoubleEndedPeekable<storage::Iter>,
+    fence: Fence,
+}
+
+impl Iter {
+    // Drop the base entries the fence does not admit from the front, leaving
+    // the next admitted one (or an error) to peek.
+    fn fence_front(&mut self) {
+        while self
+            .base
+            .next_if(|item| matches!(item, Ok((key, value)) if !self.fence.admits(key, value)))
+            .is_some()
+        {}
+    }
+
+    // As `fence_front`, from the back.
+    fn fence_back(&mut self) {
+        while self
+            .base
+            .next_back_if(|item| matches!(item, Ok((key, value)) if !self.fence.admits(key, value)))
+            .is_some()
+        {}
+    }
+}
+
+impl DoubleEndedIterator for Iter {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.fence_back();
+
+        let side = match (self.base.peek_back(), self.delta.peek_back()) {
+            (None, None) => return None,
+            (Some(Err(_)), _) | (Some(_), None) => Side::Base,
+            (_, Some(Err(_))) | (None, Some(_)) => Side::Delta,
+            (Some(Ok((base, _))), Some(Ok((delta, _)))) => match base.cmp(delta) {
+                Ordering::Greater => Side::Base,
+                Ordering::Less => Side::Delta,
+                Ordering::Equal => Side::Both,
+            },
+        };
+
+        match side {
+            Side::Base => self.base.next_back(),
+            Side::Both => self.base.next_back().and(self.delta.next_back()),
+            Side::Delta => self.delta.next_back(),
+        }
+    }
+}
+
+impl Iterator for Iter {
+    type Item = Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.fence_front();
+
+        let side = match (self.base.peek(), self.delta.peek()) {
+            (None, None) => return None,
+            (Some(Err(_)), _) | (Some(_), None) => Side::Base,
+            (_, Some(Err(_))) | (None, Some(_)) => Side::Delta,
+            (Some(Ok((base, _))), Some(Ok((delta, _)))) => match base.cmp(delta) {
+                Ordering::Less => Side::Base,
+                Ordering::Greater => Side::Delta,
+                Ordering::Equal => Side::Both,
+            },
+        };
+
+        match side {
+            Side::Base => self.base.next(),
+            Side::Both => self.base.next().and(self.delta.next()),
+            Side::Delta => self.delta.next(),
+        }
+    }
+}
+
+// The side of a layered scan the next entry is taken from: `Both` holding the
+// key, when the delta's entry is taken and the base's dropped.
+enum Side {
+    Base,
+    Both,
+    Delta,
+}
+
+// =================================================================================================
+// Tests
+// =================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use fjall::Slice;
+
+    use crate::stream::{
+        Durability,
+        Position,
+        store::storage::{
+            Fence,
+            Keyspace,
+            Memory,
+            Storage as _,
+        },
+    };
+
+    fn key(position: u64) -> [u8; 8] {
+        position.to_be_bytes()
+    }
+
+    // A layered scan merges the delta into the base in key order from either
+    // end, the delta shadowing the base where both hold a key, and the base
+    // read only up to its fence.
+    #[test]
+    fn scans_merge_the_delta_over_the_fenced_base() {
+        let (base, delta) = (Memory::default(), Memory::default());
+        let (lower, upper) = (
+            base.keyspace("test").unwrap(),
+            delta.keyspace("test").unwrap(),
+        );
+        let mut batch = base.batch(Durability::Buffer);
+
+        for position in [0, 1, 2, 5] {
+            batch.insert(&lower, key(position), "base");
+        }
+
+        batch.commit().unwrap();
+
+        let keyspace = Keyspace::layered(lower, upper, Fence::Key(Position::new(3)));
+        let mut batch = delta.batch(Durability::Buffer);
+
+        for position in [2, 3] {
+            batch.insert(&keyspace, key(position), "delta");
+        }
+
+        batch.commit().unwrap();
+
+        let entries = |iter: &mut dyn Iterator<Item = fjall::Result<(Slice, Slice)>>| {
+            iter.map(|item| {
+                let (key, value) = item.unwrap();
+
+                (key[7], String::from_utf8(value.to_vec()).unwrap())
+            })
+            .collect::<Vec<_>>()
+        };
+
+        let expected = [(0, "base"), (1, "base"), (2, "delta"), (3, "delta")]
+            .map(|(key, value)| (key, value.to_owned()));
+
+        assert_eq!(entries(&mut keyspace.iter()), expected);
+        assert_eq!(
+            entries(&mut keyspace.iter().rev()),
+            expected.iter().rev().cloned().collect::<Vec<_>>()
+        );
+
+        let mut iter = keyspace.iter();
+
+        assert_eq!(iter.next().unwrap().unwrap().0[7], 0);
+        assert_eq!(iter.next_back().unwrap().unwrap().0[7], 3);
+        assert_eq!(entries(&mut iter), expected[1..3]);
+
+        let range = keyspace.range(key(1)..);
+
+        assert_eq!(range.count(), 3);
+        assert_eq!(keyspace.get(key(5)).unwrap(), None);
+        assert_eq!(keyspace.get(key(2)).unwrap().unwrap().as_ref(), b"delta");
+        assert_eq!(keyspace.last_key().unwrap().unwrap().as_ref(), key(3));
+        assert!(!keyspace.is_empty().unwrap());
+    }
+}
@@ -0,0 +1,355 @@
+use std::{
+    collections::{
+        BTreeMap,
+        HashMap,
+    },
+    ops::Bound,
+    sync::{
+        Arc,
+        Mutex,
+        MutexGuard,
+        RwLock,
+        RwLockReadGuard,
+        RwLockWriteGuard,
+    },
+};
+
+use derive_more::Debug;
+use fjall::Slice;
+
+use crate::{
+    error::Result,
+    stream::{
+        Durability,
+        store::storage::{
+            self,
+            Storage,
+        },
+    },
+};
+
+// =================================================================================================
+// Memory
+// =================================================================================================
+
+/// The in-memory [`Storage`] backend: each keyspace is a `BTreeMap`, shared
+/// by every handle which opens it and dropped with the last of them. Every
+/// keyspace's map sits behind the one lock, so a batch's writes to several
+/// keyspaces are seen all at once, as a fjall batch's are. Nothing touches
+/// disk, so nothing is durable either — `Durability` and `persist` are
+/// accepted and ignored.
+#[derive(Debug, Default)]
+pub struct Memory {
+    #[debug("Keyspaces")]
+    keyspaces: Mutex<HashMap<String, Keyspace>>,
+    #[debug("Maps")]
+    maps: Arc<RwLock<Vec<Arc<Map>>>>,
+}
+
+impl Memory {
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Keyspace>> {
+        match self.keyspaces.lock() {
+            Ok(keyspaces) => keyspaces,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+impl Storage for Memory {
+    fn keyspace(&self, name: &str) -> Result<storage::Keyspace> {
+        let keyspace = self
+            .lock()
+            .entry(name.to_owned())
+            .or_insert_with(|| {
+                let mut maps = write(&self.maps);
+
+                maps.push(Arc::default());
+
+                Keyspace::new(maps.len() - 1, Arc::clone(&self.maps))
+            })
+            .clone();
+
+        Ok(storage::Keyspace::Memory(keyspace))
+    }
+
+    fn exists(&self, name: &str) -> bool {
+        self.lock().contains_key(name)
+    }
+
+    fn batch(&self, _: Durability) -> storage::Batch {
+        storage::Batch::Memory(Batch::default())
+    }
+
+    fn persist(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+fn read(maps: &RwLock<Vec<Arc<Map>>>) -> RwLockReadGuard<'_, Vec<Arc<Map>>> {
+    match maps.read() {
+        Ok(maps) => maps,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+fn write(maps: &RwLock<Vec<Arc<Map>>>) -> RwLockWriteGuard<'_, Vec<Arc<Map>>> {
+    match maps.write() {
+        Ok(maps) => maps,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Keyspace
+
+type Map = BTreeMap<Slice, Slice>;
+
+/// One in-memory keyspace: its index among its [`Memory`]'s maps. Each map is
+/// copy-on-write: a scan holds the `Arc` of the map as it was when the scan
+/// began (its snapshot, as a fjall iterator has), and a commit clones the map
+/// only if such a scan is still open.
+#[derive(Clone, Debug)]
+pub struct Keyspace(Arc<Slot>);
+
+impl Keyspace {
+    fn new(index: usize, maps: Arc<RwLock<Vec<Arc<Map>>>>) -> Self {
+        Self(Arc::new(Slot { index, maps }))
+    }
+}
+
+#[derive(Debug)]
+struct Slot {
+    index: usize,
+    #[debug("Maps")]
+    maps: Arc<RwLock<Vec<Arc<Map>>>>,
+}
+
+impl Keyspace {
+    pub fn get(&self, key: &[u8]) -> Option<Slice> {
+        self.snapshot().get(key).cloned()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshot().is_empty()
+    }
+
+    pub fn last_key(&self) -> Option<Slice> {
+        self.snapshot().last_key_value().map(|(key, _)| key.clone())
+    }
+
+    pub fn range(&self, lower: Bound<Slice>, upper: Bound<Slice>) -> Iter {
+        Iter {
+            map: self.snapshot(),
+            lower,
+            upper,
+        }
+    }
+
+    fn snapshot(&self) -> Arc<Map> {
+        Arc::clone(&read(&self.0.maps)[self.0.index])
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Iterator
+
+/// A scan over a snapshot of a [`Keyspace`], narrowing its bounds past each
+/// item taken from either end.
+#[derive(Debug)]
+pub struct Iter {
+    #[debug("Map")]
+    map: Arc<Map>,
+    lower: Bound<Slice>,
+    upper: Bound<Slice>,
+}
+
+impl Iter {
+    // Whether no key lies within the bounds (as when the two ends have met),
+    // which `BTreeMap::range` would otherwise panic on.
+    fn is_exhausted(&self) -> bool {
+        match (&self.lower, &self.upper) {
+            (Bound::Included(lower), Bound::Included(upper)) => lower > upper,
+            (
+                Bound::Included(lower) | Bound::Excluded(lower),
+                Bound::Included(upper) | Bound::Excluded(upper),
+            ) => lower >= upper,
+            _ => false,
+        }
+    }
+
+    fn bounds(&self) -> (Bound<Slice>, Bound<Slice>) {
+        (self.lower.clone(), self.upper.clone())
+    }
+}
+
+impl DoubleEndedIterator for Iter {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.is_exhausted() {
+            return None;
+        }
+
+        let (key, value) = self.map.range(self.bounds()).next_back()?;
+
+        self.upper = Bound::Excluded(key.clone());
+
+        Some((key.clone(), value.clone()))
+    }
+}
+
+impl Iterator for Iter {
+    type Item = (Slice, Slice);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_exhausted() {
+            return None;
+        }
+
+        let (key, value) = self.map.range(self.bounds()).next()?;
+
+        self.lower = Bound::Excluded(key.clone());
+
+        Some((key.clone(), value.clone()))
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Batch
+
+/// The writes (inserts, and removes as `None`) of an in-memory batch, in
+/// order, applied on commit under the lock over every keyspace's map.
+#[derive(Debug, Default)]
+pub struct Batch {
+    #[debug("Writes")]
+    writes: Vec<(Keyspace, Slice, Option<Slice>)>,
+}
+
+impl Batch {
+    pub fn insert(&mut self, keyspace: &Keyspace, key: Slice, value: Slice) {
+        self.writes.push((keyspace.clone(), key, Some(value)));
+    }
+
+    pub fn remove(&mut self, keyspace: &Keyspace, key: Slice) {
+        self.writes.push((keyspace.clone(), key, None));
+    }
+
+    pub fn commit(self) {
+        // Group the writes by the `Memory` their keyspace belongs to (only ever
+        // the one the batch was made by, as a layered keyspace writes to its
+        // delta), in order.
+        let mut groups = Vec::<(Arc<RwLock<Vec<Arc<Map>>>>, Vec<_>)>::new();
+
+        for (keyspace, key, value) in self.writes {
+            let Slot { index, maps } = &*keyspace.0;
+            let write = (*index, key, value);
+
+            match groups
+                .iter_mut()
+                .find(|(group, _)| Arc::ptr_eq(group, maps))
+            {
+                Some((_, writes)) => writes.push(write),
+                None => groups.push((Arc::clone(maps), vec![write])),
+            }
+        }
+
+        // Hold the lock over every map for the whole of a group, so no reader
+        // sees some of the batch's keyspaces written and others not.
+        for (maps, writes) in groups {
+            let mut maps = write(&maps);
+
+            for (index, key, value) in writes {
+                let map = Arc::make_mut(&mut maps[index]);
+
+                match value {
+                    Some(value) => map.insert(key, value),
+                    None => map.remove(&key),
+                };
+            }
+        }
+    }
+}
+
+// =================================================================================================
+// Tests
+// =================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::Memory;
+    use crate::stream::{
+        Durability,
+        store::storage::Storage as _,
+    };
+
+    // A scan taken from both ends stops where the ends meet, sees the map as it
+    // was when the scan began, and a prefix ending in `0xFF` bytes is bounded
+    // by its successor rather than running on to the end of the keyspace.
+    #[test]
+    fn scans_are_snapshots_bounded_from_both_ends() {
+        let memory = Memory::default();
+        let keyspace = memory.keyspace("test").unwrap();
+        let mut batch = memory.batch(Durability::Buffer);
+
+        for key in [[0, 1], [0, 2], [0, 0xff], [1, 0]] {
+            batch.insert(&keyspace, key, []);
+        }
+
+        batch.commit().unwrap();
+
+        let mut iter = keyspace.iter();
+        let mut batch = memory.batch(Durability::Buffer);
+
+        batch.insert(&keyspace, [2, 0], []);
+        batch.commit().unwrap();
+
+        let first = iter.next().unwrap().unwrap().0;
+        let last = iter.next_back().unwrap().unwrap().0;
+        let rest = iter.map(|item| item.unwrap().0).collect::<Vec<_>>();
+
+        assert_eq!(first, [0, 1]);
+        assert_eq!(last, [1, 0]);
+        assert_eq!(rest, [[0, 2], [0, 0xff]]);
+        assert_eq!(keyspace.iter().count(), 5);
+        assert_eq!(keyspace.prefix([0, 0xff]).count(), 1);
+        assert_eq!(keyspace.prefix([0]).rev().count(), 3);
+    }
+
+    // A batch writing to two keyspaces is seen whole: whatever a reader finds
+    // in the first keyspace written, it then finds in the second.
+    #[test]
+    fn batches_are_seen_whole_across_keyspaces() {
+        let memory = Memory::default();
+        let (first, second) = (
+            memory.keyspace("first").unwrap(),
+            memory.keyspace("second").unwrap(),
+        );
+
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                for key in 0..2_000_u32 {
+                    let mut batch = memory.batch(Durability::Buffer);
+
+                    batch.insert(&first, key.to_be_bytes(), []);
+                    batch.insert(&second, key.to_be_bytes(), []);
+                    batch.commit().unwrap();
+                }
+            });
+
+            loop {
+                let Some(key) = first.last_key().unwrap() else {
+                    continue;
+                };
+
+                assert!(second.contains_key(&key).unwrap());
+
+                if key.as_ref() == 1_999_u32.to_be_bytes() {
+                    break;
+                }
+            }
+        });
+    }
+}
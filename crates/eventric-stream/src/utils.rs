@@ -1,6 +1,6 @@
 //! Crate utilities: a stable `hashing` function and a small `validation`
 //! framework (both internal), plus [`temp_path`] for creating temporary
-//! stream-storage paths.
+//! stream-storage paths and the `parity!` test macro.
 
 pub(crate) mod hashing;
 pub(crate) mod validation;
@@ -24,3 +24,35 @@ pub fn temp_path() -> PathBuf {
 
     Path::new(&temp_dir).join(random.to_string())
 }
+
+// -------------------------------------------------------------------------------------------------
+
+// Parity
+
+// Re-exported for `parity!`, so its users need not depend on it themselves.
+#[doc(hidden)]
+pub use pastey::paste;
+
+/// Define, for each backend-independent test (a fn of the
+/// [`Backend`](crate::stream::Backend)), a `#[test]` running it against each
+/// backend, so the in-memory backend is held to the same suite as the on-disk
+/// one.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! parity {
+    ($($test:ident),* $(,)?) => {
+        $crate::utils::paste! {
+            $(
+                #[test]
+                fn [<$test _on_disk>]() {
+                    $test($crate::stream::Backend::Disk);
+                }
+
+                #[test]
+                fn [<$test _in_memory>]() {
+                    $test($crate::stream::Backend::Memory);
+                }
+            )*
+        }
+    };
+}
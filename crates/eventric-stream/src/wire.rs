@@ -0,0 +1,735 @@
+//! The wire encoding of the values a remote stream server and its clients
+//! exchange: [`Encode`] and [`Decode`] for positions, [`Condition`]s,
+//! candidate events, matched events and append receipts, plus the primitives
+//! they are built from. All integers are big-endian and every variable-length value is
+//! length-prefixed, so a message can be read by a client in any language:
+//!
+//! - a string is a `u32` byte length and UTF-8 bytes; data is a `u32` length
+//!   and bytes
+//! - a type name or tag in a condition is a *name*: a `u8` form, then either
+//!   the string (form `0`), hashed by the server, or its hash as a `u64` (form
+//!   `1`)
+//! - a condition is an optional position (a `u8` flag, `1` followed by a `u64`
+//!   if present), an optional batch position (likewise), a `u32` selection
+//!   count, and per selection a `u32` selector
+//!   count; per selector, a `u32` type count, per type a name and the first and
+//!   last-exclusive versions (`u8` each), then a `u8` tags flag, `1` followed by
+//!   a `u32` tag count and the tag names if present
+//! - a candidate event is its type name (string), version (`u8`), a `u8` tag
+//!   count and the tags (strings), and its data
+//! - a matched event is its position, timestamp and batch (`u64` each, the
+//!   batch with its top bit set if the event's payload has been redacted), type
+//!   name hash (`u64`), version (`u8`), a `u8` tag count and the tag hashes (`u64`
+//!   each), its data, and its mask as a `u32` count of `u8` flags (`1` where
+//!   the selection at that index matched)
+//! - an append receipt is its batch and first position (`u64` each), and a
+//!   `u32` count of the appended events' timestamps (`u64` each)
+//! - a durability is a `u8`: `0` to buffer, `1` to sync
+//! - a plan is a `u8` path (`0` events, `1` filter, `2` indices, `3` batch),
+//!   its optional position (as in a condition), for the batch path the
+//!   position of an event in the batch (`u64`) and, for the indices path, its
+//!   root node: a
+//!   `u8` kind, then for an intersection (`0`) or union (`1`) a `u32` child
+//!   count and the children, for a tag (`2`) its hash (`u64`), and for a type
+//!   (`3`) its hash and first and last-exclusive versions
+//! - an analysis is its plan, then its postings, seeks, events and duration
+//!   in nanoseconds (`u64` each)
+
+use std::{
+    collections::BTreeSet,
+    ops::Range,
+    time::Duration,
+};
+
+use bytes::{
+    Buf as _,
+    BufMut as _,
+};
+use error_stack::{
+    Report,
+    ResultExt as _,
+};
+use smallvec::SmallVec;
+
+use crate::{
+    error::{
+        Error,
+        Result,
+    },
+    event::{
+        Data,
+        Event,
+        Facets,
+        Name,
+        Tag,
+        Type,
+        Version,
+    },
+    stream::{
+        Durability,
+        Metadata,
+        Position,
+        Timestamp,
+        operate::{
+            Condition,
+            Selection,
+            append::AppendReceipt,
+            explain::{
+                Analysis,
+                Node,
+                Plan,
+            },
+            select::{
+                EventAndMask,
+                Mask,
+                Selector,
+                TypeSelector,
+            },
+        },
+    },
+};
+
+// =================================================================================================
+// Wire
+// =================================================================================================
+
+// Constants
+
+static NAME_FORM_STRING: u8 = 0;
+static NAME_FORM_HASH: u8 = 1;
+
+// -------------------------------------------------------------------------------------------------
+
+// Encode & Decode
+
+/// A value with a wire encoding, appended to a byte buffer.
+pub trait Encode {
+    /// Append the value's encoding to `bytes`.
+    fn encode(&self, bytes: &mut Vec<u8>);
+}
+
+/// A value with a wire encoding, read from the front of a byte slice.
+pub trait Decode: Sized {
+    /// Read a value from the front of `bytes`, advancing past it. Fails on
+    /// truncated or invalid input.
+    fn decode(bytes: &mut &[u8]) -> Result<Self>;
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Primitives
+
+macro_rules! integer {
+    ($integer:ty, $put:ident, $get:ident) => {
+        impl Encode for $integer {
+            fn encode(&self, bytes: &mut Vec<u8>) {
+                bytes.$put(*self);
+            }
+        }
+
+        impl Decode for $integer {
+            fn decode(bytes: &mut &[u8]) -> Result<Self> {
+                bytes
+                    .$get()
+                    .change_context(Error)
+                    .attach("message is truncated")
+            }
+        }
+    };
+}
+
+integer!(u8, put_u8, try_get_u8);
+integer!(u32, put_u32, try_get_u32);
+integer!(u64, put_u64, try_get_u64);
+
+impl Encode for [u8] {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        len(self.len()).encode(bytes);
+        bytes.put_slice(self);
+    }
+}
+
+impl Decode for Vec<u8> {
+    fn decode(bytes: &mut &[u8]) -> Result<Self> {
+        let len = u32::decode(bytes)? as usize;
+
+        if bytes.len() < len {
+            return Err(Report::new(Error).attach("message is truncated"));
+        }
+
+        let (slice, rest) = bytes.split_at(len);
+
+        *bytes = rest;
+
+        Ok(slice.to_vec())
+    }
+}
+
+impl Encode for str {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.as_bytes().encode(bytes);
+    }
+}
+
+impl Decode for String {
+    fn decode(bytes: &mut &[u8]) -> Result<Self> {
+        String::from_utf8(Vec::decode(bytes)?)
+            .change_context(Error)
+            .attach("string is not UTF-8")
+    }
+}
+
+impl Encode for Position {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.0.encode(bytes);
+    }
+}
+
+impl Decode for Position {
+    fn decode(bytes: &mut &[u8]) -> Result<Self> {
+        u64::decode(bytes).map(Self::new)
+    }
+}
+
+// The length of an encoded sequence, as its `u32` prefix.
+fn len(len: usize) -> u32 {
+    u32::try_from(len).expect("encoded length > u32::MAX")
+}
+
+fn position(position: Option<Position>, bytes: &mut Vec<u8>) {
+    match position {
+        Some(position) => {
+            1u8.encode(bytes);
+            position.encode(bytes);
+        }
+        None => 0u8.encode(bytes),
+    }
+}
+
+fn flag(bytes: &mut &[u8]) -> Result<bool> {
+    match u8::decode(bytes)? {
+        0 => Ok(false),
+        1 => Ok(true),
+        flag => Err(Report::new(Error).attach(format!("invalid flag {flag}"))),
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Condition
+
+impl Encode for Condition {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        position(self.position, bytes);
+        position(self.batch, bytes);
+        len(self.selections.len()).encode(bytes);
+
+        for selection in &self.selections {
+            len(selection.selectors.len()).encode(bytes);
+
+            for Selector(types, tags) in &selection.selectors {
+                len(types.len()).encode(bytes);
+
+                for TypeSelector(name, versions) in types {
+                    hash(name.0, bytes);
+                    versions.start.0.encode(bytes);
+                    versions.end.0.encode(bytes);
+                }
+
+                match tags {
+                    Some(tags) => {
+                        1u8.encode(bytes);
+                        len(tags.len()).encode(bytes);
+
+                        for tag in tags {
+                            hash(tag.0, bytes);
+                        }
+                    }
+                    None => 0u8.encode(bytes),
+                }
+            }
+        }
+    }
+}
+
+impl Decode for Condition {
+    fn decode(bytes: &mut &[u8]) -> Result<Self> {
+        let position = flag(bytes)?.then(|| Position::decode(bytes)).transpose()?;
+        let batch = flag(bytes)?.then(|| Position::decode(bytes)).transpose()?;
+
+        let selections = (0..u32::decode(bytes)?)
+            .map(|_| {
+                let selectors = (0..u32::decode(bytes)?)
+                    .map(|_| selector(bytes))
+                    .collect::<Result<_>>()?;
+
+                Ok(Selection { selectors })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            batch,
+            position,
+            selections,
+        })
+    }
+}
+
+fn selector(bytes: &mut &[u8]) -> Result<Selector<u64>> {
+    let types = (0..u32::decode(bytes)?)
+        .map(|_| {
+            let name = Name(name(bytes, |name| {
+                Name::new(name).map(|name| Name::<u64>::from(name).0)
+            })?);
+            let versions = Range {
+                start: Version::new(u8::decode(bytes)?),
+                end: Version::new(u8::decode(bytes)?),
+            };
+
+            Ok(TypeSelector(name, versions))
+        })
+        .collect::<Result<BTreeSet<_>>>()?;
+
+    let tags = flag(bytes)?
+        .then(|| {
+            (0..u32::decode(bytes)?)
+                .map(|_| {
+                    name(bytes, |tag| {
+                        Tag::new(tag).map(|tag| Tag::<u64>::from(tag).0)
+                    })
+                    .map(Tag)
+                })
+                .collect::<Result<BTreeSet<_>>>()
+        })
+        .transpose()?;
+
+    Ok(Selector(types, tags))
+}
+
+fn hash(hash: u64, bytes: &mut Vec<u8>) {
+    NAME_FORM_HASH.encode(bytes);
+    hash.encode(bytes);
+}
+
+// Read the hash of a name in either form: a string, validated and hashed by
+// `hashed`, or the hash itself.
+fn name<F>(bytes: &mut &[u8], hashed: F) -> Result<u64>
+where
+    F: FnOnce(String) -> Result<u64>,
+{
+    match u8::decode(bytes)? {
+        form if form == NAME_FORM_STRING => hashed(String::decode(bytes)?),
+        form if form == NAME_FORM_HASH => u64::decode(bytes),
+        form => Err(Report::new(Error).attach(format!("invalid name form {form}"))),
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Candidate Event
+
+impl Encode for Event<(), String> {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        let ty = self.facets().ty();
+        let tags = self.facets().tags();
+
+        ty.name().0.encode(bytes);
+        ty.version().0.encode(bytes);
+        tag_count(tags.len()).encode(bytes);
+
+        for tag in tags {
+            tag.0.encode(bytes);
+        }
+
+        self.data().as_ref().encode(bytes);
+    }
+}
+
+impl Decode for Event<(), String> {
+    fn decode(bytes: &mut &[u8]) -> Result<Self> {
+        let name = Name::new(String::decode(bytes)?)?;
+        let version = Version::new(u8::decode(bytes)?);
+        let tags = (0..u8::decode(bytes)?)
+            .map(|_| Tag::new(String::decode(bytes)?))
+            .collect::<Result<_>>()?;
+        let data = Data::new(Vec::decode(bytes)?)?;
+
+        Ok(Self::new(
+            data,
+            Facets::new(Type::new(name, version), tags),
+            (),
+        ))
+    }
+}
+
+fn tag_count(count: usize) -> u8 {
+    u8::try_from(count).expect("tag count > u8::MAX (rejected at append)")
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Matched Event
+
+impl Encode for EventAndMask {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        let meta = self.event.meta();
+        let ty = self.event.facets().ty();
+        let tags = self.event.facets().tags();
+
+        meta.position().encode(bytes);
+        meta.timestamp().0.encode(bytes);
+        meta.marked_batch().encode(bytes);
+        ty.name().0.encode(bytes);
+        ty.version().0.encode(bytes);
+        tag_count(tags.len()).encode(bytes);
+
+        for tag in tags {
+            tag.0.encode(bytes);
+        }
+
+        self.event.data().as_ref().encode(bytes);
+        len(self.mask.0.len()).encode(bytes);
+
+        for matched in &self.mask.0 {
+            u8::from(*matched).encode(bytes);
+        }
+    }
+}
+
+impl Decode for EventAndMask {
+    fn decode(bytes: &mut &[u8]) -> Result<Self> {
+        let position = Position::decode(bytes)?;
+        let timestamp = Timestamp::new(u64::decode(bytes)?);
+        let batch = u64::decode(bytes)?;
+        let name = Name(u64::decode(bytes)?);
+        let version = Version::new(u8::decode(bytes)?);
+        let tags = (0..u8::decode(bytes)?)
+            .map(|_| u64::decode(bytes).map(Tag))
+            .collect::<Result<_>>()?;
+        let data = Data::new(Vec::decode(bytes)?)?;
+        let mask = (0..u32::decode(bytes)?)
+            .map(|_| flag(bytes))
+            .collect::<Result<SmallVec<_>>>()?;
+
+        let facets = Facets::new(Type::new(name, version), tags);
+        let event = Event::new(data, facets, Metadata::marked(position, timestamp, batch));
+
+        Ok(Self::new(event, Mask::new(mask)))
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Append Receipt
+
+impl Encode for AppendReceipt {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.batch().encode(bytes);
+        self.first().encode(bytes);
+        len(self.timestamps().len()).encode(bytes);
+
+        for timestamp in self.timestamps() {
+            timestamp.0.encode(bytes);
+        }
+    }
+}
+
+impl Decode for AppendReceipt {
+    fn decode(bytes: &mut &[u8]) -> Result<Self> {
+        let batch = Position::decode(bytes)?;
+        let first = Position::decode(bytes)?;
+        let timestamps = (0..u32::decode(bytes)?)
+            .map(|_| u64::decode(bytes).map(Timestamp::new))
+            .collect::<Result<Vec<_>>>()?;
+
+        if timestamps.is_empty() {
+            return Err(Report::new(Error).attach("append receipt has no events"));
+        }
+
+        Ok(Self::new(batch, first, timestamps))
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Durability
+
+impl Encode for Durability {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        match self {
+            Self::Buffer => 0u8.encode(bytes),
+            Self::Sync => 1u8.encode(bytes),
+        }
+    }
+}
+
+impl Decode for Durability {
+    fn decode(bytes: &mut &[u8]) -> Result<Self> {
+        match u8::decode(bytes)? {
+            0 => Ok(Self::Buffer),
+            1 => Ok(Self::Sync),
+            durability => {
+                Err(Report::new(Error).attach(format!("invalid durability {durability}")))
+            }
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Plan & Analysis
+
+impl Encode for Plan {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        let (path, from, batch, root) = match self {
+            Self::Events { from } => (0u8, from, None, None),
+            Self::Filter { from } => (1, from, None, None),
+            Self::Indices { from, root } => (2, from, None, Some(root)),
+            Self::Batch { from, position } => (3, from, Some(position), None),
+        };
+
+        path.encode(bytes);
+        position(*from, bytes);
+
+        if let Some(batch) = batch {
+            batch.encode(bytes);
+        }
+
+        if let Some(root) = root {
+            root.encode(bytes);
+        }
+    }
+}
+
+impl Decode for Plan {
+    fn decode(bytes: &mut &[u8]) -> Result<Self> {
+        let path = u8::decode(bytes)?;
+        let from = flag(bytes)?.then(|| Position::decode(bytes)).transpose()?;
+
+        match path {
+            0 => Ok(Self::Events { from }),
+            1 => Ok(Self::Filter { from }),
+            2 => Ok(Self::Indices {
+                from,
+                root: Node::decode(bytes)?,
+            }),
+            3 => Ok(Self::Batch {
+                from,
+                position: Position::decode(bytes)?,
+            }),
+            path => Err(Report::new(Error).attach(format!("invalid plan path {path}"))),
+        }
+    }
+}
+
+impl Encode for Node {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        match self {
+            Self::Intersection(nodes) | Self::Union(nodes) => {
+                u8::from(matches!(self, Self::Union(_))).encode(bytes);
+                len(nodes.len()).encode(bytes);
+
+                for node in nodes {
+                    node.encode(bytes);
+                }
+            }
+            Self::Tag { hash } => {
+                2u8.encode(bytes);
+                hash.encode(bytes);
+            }
+            Self::Type { hash, versions } => {
+                3u8.encode(bytes);
+                hash.encode(bytes);
+                versions.start.0.encode(bytes);
+                versions.end.0.encode(bytes);
+            }
+        }
+    }
+}
+
+impl Decode for Node {
+    fn decode(bytes: &mut &[u8]) -> Result<Self> {
+        let nodes = |bytes: &mut &[u8]| {
+            (0..u32::decode(bytes)?)
+                .map(|_| Self::decode(bytes))
+                .collect::<Result<_>>()
+        };
+
+        match u8::decode(bytes)? {
+            0 => nodes(bytes).map(Self::Intersection),
+            1 => nodes(bytes).map(Self::Union),
+            2 => Ok(Self::Tag {
+                hash: u64::decode(bytes)?,
+            }),
+            3 => Ok(Self::Type {
+                hash: u64::decode(bytes)?,
+                versions: Range {
+                    start: Version::new(u8::decode(bytes)?),
+                    end: Version::new(u8::decode(bytes)?),
+                },
+            }),
+            kind => Err(Report::new(Error).attach(format!("invalid plan node {kind}"))),
+        }
+    }
+}
+
+impl Encode for Analysis {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        let duration = u64::try_from(self.duration.as_nanos()).unwrap_or(u64::MAX);
+
+        self.plan.encode(bytes);
+        self.postings.encode(bytes);
+        self.seeks.encode(bytes);
+        self.events.encode(bytes);
+        duration.encode(bytes);
+    }
+}
+
+impl Decode for Analysis {
+    fn decode(bytes: &mut &[u8]) -> Result<Self> {
+        Ok(Self {
+            plan: Plan::decode(bytes)?,
+            postings: u64::decode(bytes)?,
+            seeks: u64::decode(bytes)?,
+            events: u64::decode(bytes)?,
+            duration: Duration::from_nanos(u64::decode(bytes)?),
+        })
+    }
+}
+
+// =================================================================================================
+// Tests
+// =================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Decode as _,
+        Encode as _,
+    };
+    use crate::{
+        event::{
+            Data,
+            Event,
+            Facets,
+            Name,
+            Tag,
+            Type,
+            Version,
+        },
+        stream::{
+            Backend,
+            Position,
+            Stream,
+            operate::{
+                Condition,
+                Selection,
+                append::Append as _,
+                explain::Plan,
+                select::{
+                    EventAndMask,
+                    Select as _,
+                    Selector,
+                    TypeSelector,
+                },
+            },
+        },
+        utils::{
+            hashing,
+            temp_path,
+        },
+    };
+
+    fn condition() -> Condition {
+        Condition::new().selections([
+            Selection::new([Selector::types([TypeSelector::new("Invoiced").unwrap()])]),
+            Selection::new([Selector::types_and_tags(
+                [TypeSelector::with_versions("Paid", Version::new(1)..).unwrap()],
+                [Tag::new("invoice:1").unwrap()],
+            )]),
+        ])
+    }
+
+    // Conditions, candidate events and matched events survive their encoding,
+    // and a name sent as a string selects as its hash does.
+    #[test]
+    fn values_round_trip_their_encoding() {
+        let mut stream = Stream::builder(temp_path())
+            .backend(Backend::Memory)
+            .open()
+            .unwrap();
+
+        let ty = Type::new(Name::new("Paid").unwrap(), Version::new(1));
+        let tags = [Tag::new("invoice:1").unwrap()].into();
+        let event = Event::new(Data::new("paid").unwrap(), Facets::new(ty, tags), ());
+
+        let mut bytes = Vec::new();
+
+        event.encode(&mut bytes);
+
+        let decoded = Event::<(), String>::decode(&mut bytes.as_slice()).unwrap();
+
+        stream.append([decoded], Condition::new()).unwrap();
+
+        let mut bytes = Vec::new();
+
+        condition().batch(Position::new(0)).encode(&mut bytes);
+
+        let decoded = Condition::decode(&mut bytes.as_slice()).unwrap();
+        let selected = stream.select(decoded).next().unwrap().unwrap();
+
+        assert_eq!(selected.mask.as_ref(), [false, true]);
+
+        let mut bytes = Vec::new();
+
+        selected.encode(&mut bytes);
+
+        let decoded = EventAndMask::decode(&mut bytes.as_slice()).unwrap();
+
+        assert_eq!(decoded.event.meta(), selected.event.meta());
+        assert_eq!(decoded.mask, selected.mask);
+        assert!(EventAndMask::decode(&mut &bytes[..bytes.len() - 1]).is_err());
+
+        // The same condition, with its names sent as strings.
+        let mut bytes = vec![0, 0, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0];
+
+        "Paid".encode(&mut bytes);
+        bytes.extend([1, 255, 0]);
+
+        let decoded = Condition::decode(&mut bytes.as_slice()).unwrap();
+        let mut rehashed = Vec::new();
+
+        decoded.encode(&mut rehashed);
+
+        assert_eq!(&rehashed[15..23], hashing::hash(&"Paid").to_be_bytes());
+        assert_eq!(stream.select(decoded).count(), 1);
+
+        for plan in [
+            stream.explain(condition()),
+            stream.explain(condition().batch(Position::new(0))),
+        ] {
+            let mut bytes = Vec::new();
+
+            plan.encode(&mut bytes);
+
+            assert_eq!(Plan::decode(&mut bytes.as_slice()).unwrap(), plan);
+        }
+
+        // A redacted event keeps its redaction mark.
+        let (reader, mut writer) = stream.split();
+
+        writer
+            .redact(Position::new(0), Data::new("void").unwrap(), "scrubbed")
+            .unwrap();
+
+        let redacted = reader.select(Condition::new()).next().unwrap().unwrap();
+        let mut bytes = Vec::new();
+
+        redacted.encode(&mut bytes);
+
+        let decoded = EventAndMask::decode(&mut bytes.as_slice()).unwrap();
+
+        assert!(decoded.event.meta().redacted());
+        assert_eq!(decoded.event.meta(), redacted.event.meta());
+    }
+}
@@ -0,0 +1,274 @@
+//! Integration tests for the `async` feature's executor-agnostic surface over
+//! the `Owner`/`Proxy` wrapper: an async append resolves on the writer's reply
+//! (carrying a `Conflict` marker on rejection), `select_async` streams the same
+//! results as `select`, a subscription yields existing matches, then live
+//! commits, and ends when the writer thread exits, and a backpressured append
+//! waits to be woken by the writer rather than spinning.
+
+use std::{
+    collections::BTreeSet,
+    iter,
+    pin::pin,
+    sync::{
+        Arc,
+        atomic::{
+            AtomicUsize,
+            Ordering,
+        },
+        mpsc,
+    },
+    task::Context,
+    thread,
+    time::Duration,
+};
+
+use eventric_stream::{
+    error::Conflict,
+    event::{
+        Data,
+        Event,
+        Facets,
+        Name,
+        Tag,
+        Type,
+        Version,
+    },
+    stream::{
+        Position,
+        Stream,
+        concurrent::{
+            asynchronous::{
+                AsyncAppend as _,
+                AsyncSelect as _,
+            },
+            owner::Owner,
+            proxy::PendingAppend,
+        },
+        operate::{
+            Condition,
+            Selection,
+            append::Append as _,
+            select::{
+                Selector,
+                TypeSelector,
+            },
+        },
+    },
+    utils::temp_path,
+};
+use futures::{
+    StreamExt as _,
+    executor::block_on,
+    task::{
+        self,
+        ArcWake,
+    },
+};
+
+// =================================================================================================
+// Helpers
+// =================================================================================================
+
+fn owner() -> Owner {
+    Owner::new(Stream::builder(temp_path()).temporary(true).open().unwrap())
+}
+
+fn event(name: &str, data: &str, tags: &[&str]) -> Event<(), String> {
+    let ty = Type::new(Name::new(name).unwrap(), Version::new(0));
+    let tags = tags
+        .iter()
+        .map(|tag| Tag::new(*tag).unwrap())
+        .collect::<BTreeSet<_>>();
+
+    Event::new(Data::new(data).unwrap(), Facets::new(ty, tags), ())
+}
+
+// A waker which counts its wakes.
+#[derive(Default)]
+struct Counting(AtomicUsize);
+
+impl ArcWake for Counting {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        arc_self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+fn selecting(name: &str) -> Condition {
+    Condition::new().selections([Selection::new([Selector::types([
+        TypeSelector::new(name).unwrap()
+    ])])])
+}
+
+// =================================================================================================
+// Tests
+// =================================================================================================
+
+// 1. An async append resolves to the position the writer thread committed at.
+#[test]
+fn append_async_resolves_on_the_writer_reply() {
+    let owner = owner();
+    let mut proxy = owner.proxy();
+
+    let first = block_on(proxy.append_async([event("Opened", "a", &[])], Condition::new()));
+    let second = block_on(proxy.append_async(
+        [event("Opened", "b", &[]), event("Opened", "c", &[])],
+        Condition::new(),
+    ));
+
+    assert_eq!(first.unwrap().last(), Position::new(0));
+    assert_eq!(second.unwrap().last(), Position::new(2));
+}
+
+// 2. A rejected async append carries the `Conflict` marker, exactly as the
+//    blocking append does.
+#[test]
+fn append_async_rejection_carries_the_conflict_marker() {
+    let owner = owner();
+    let mut proxy = owner.proxy();
+
+    block_on(proxy.append_async([event("Opened", "a", &[])], Condition::new())).unwrap();
+
+    let report = block_on(proxy.append_async(
+        [event("Opened", "b", &[])],
+        selecting("Opened").from(Position::MIN),
+    ))
+    .expect_err("conflicting append must be rejected");
+
+    assert!(report.downcast_ref::<Conflict>().is_some());
+}
+
+// 3. `select_async` streams the same masked results as the blocking `select`.
+#[test]
+fn select_async_streams_masked_results() {
+    let owner = owner();
+    let mut proxy = owner.proxy();
+
+    proxy
+        .append(
+            [
+                event("Opened", "a", &[]),
+                event("Closed", "b", &[]),
+                event("Opened", "c", &[]),
+            ],
+            Condition::new(),
+        )
+        .unwrap();
+
+    let positions = block_on(
+        proxy
+            .select_async(selecting("Opened"))
+            .map(|result| result.unwrap())
+            .collect::<Vec<_>>(),
+    )
+    .into_iter()
+    .map(|event| {
+        assert_eq!(event.mask.as_ref(), [true].as_slice());
+
+        event.event.meta().position()
+    })
+    .collect::<Vec<_>>();
+
+    assert_eq!(positions, vec![Position::new(0), Position::new(2)]);
+}
+
+// 4. A subscription yields the matches already present, then each match as it
+//    is committed from another thread, skipping non-matching events, and ends
+//    once the writer thread has exited.
+#[test]
+fn subscription_yields_existing_then_live_matches() {
+    let owner = owner();
+    let mut proxy = owner.proxy();
+
+    proxy
+        .append([event("Opened", "a", &[])], Condition::new())
+        .unwrap();
+
+    let mut subscription = owner.proxy().subscribe(selecting("Opened"));
+
+    let existing = block_on(subscription.next()).unwrap().unwrap();
+    assert_eq!(existing.event.meta().position(), Position::new(0));
+
+    let writer = thread::spawn(move || {
+        proxy
+            .append([event("Closed", "b", &[])], Condition::new())
+            .unwrap();
+        proxy
+            .append([event("Opened", "c", &[])], Condition::new())
+            .unwrap();
+    });
+
+    let live = block_on(subscription.next()).unwrap().unwrap();
+    assert_eq!(live.event.meta().position(), Position::new(2));
+    assert_eq!(live.event.data().as_ref(), b"c");
+
+    writer.join().unwrap();
+    owner.into_inner().unwrap();
+
+    assert!(block_on(subscription.next()).is_none());
+}
+
+// 5. An append which finds the writer's channel full is not woken until the
+//    writer takes an operation from it, then enqueues and resolves.
+#[test]
+fn backpressured_append_async_waits_for_the_writer() {
+    let owner = Owner::builder(Stream::builder(temp_path()).temporary(true).open().unwrap())
+        .capacity(1)
+        .build();
+
+    let (started, waiting) = mpsc::channel();
+    let (gate, opened) = mpsc::channel::<()>();
+    let events = iter::once_with(move || {
+        started.send(()).unwrap();
+        opened.recv().ok();
+
+        event("Audited", "gated", &[])
+    });
+
+    let busy = thread::spawn({
+        let mut proxy = owner.proxy();
+
+        move || proxy.append(events, Condition::new()).unwrap()
+    });
+
+    waiting.recv().unwrap();
+
+    let mut proxy = owner.proxy();
+
+    // Enqueued, filling the channel, while the writer is busy.
+    let enqueued = proxy
+        .append_timeout(
+            [event("Audited", "x", &[])],
+            Condition::new(),
+            Duration::from_millis(50),
+        )
+        .unwrap_err();
+
+    assert!(enqueued.downcast_ref::<PendingAppend>().is_some());
+
+    let counting = Arc::new(Counting::default());
+    let waker = task::waker(Arc::clone(&counting));
+    let mut context = Context::from_waker(&waker);
+    let mut future = pin!(proxy.append_async([event("Audited", "y", &[])], Condition::new()));
+
+    assert!(future.as_mut().poll(&mut context).is_pending());
+
+    thread::sleep(Duration::from_millis(50));
+
+    assert_eq!(counting.0.load(Ordering::SeqCst), 0);
+
+    gate.send(()).unwrap();
+    busy.join().unwrap();
+
+    for _ in 0..500 {
+        if counting.0.load(Ordering::SeqCst) > 0 {
+            break;
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    assert!(counting.0.load(Ordering::SeqCst) > 0);
+    let receipt = block_on(future).unwrap();
+
+    assert_eq!(receipt.first(), Position::new(2));
+}
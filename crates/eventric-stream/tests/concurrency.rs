@@ -1,16 +1,35 @@
 //! Integration tests for the multi-thread `Owner`/`Proxy` wrapper: concurrent
 //! appends serialise through the single writer thread (no gaps/dupes), reads
-//! observe committed writes, and a rejected (DCB) append carries its `Conflict`
-//! marker back across the channel.
+//! observe committed writes, a rejected (DCB) append carries its `Conflict`
+//! marker back across the channel, the owner's lifecycle (drained shutdown,
+//! failure status, opt-in restart) holds, its configuration (channel
+//! capacity, observers) takes effect, and bounded appends report backpressure,
+//! timeouts and pending outcomes.
 
 use std::{
     collections::BTreeSet,
+    iter,
+    sync::{
+        Arc,
+        Mutex,
+        mpsc::{
+            self,
+            Receiver,
+            Sender,
+        },
+    },
     thread,
+    time::Duration,
 };
 
 use error_stack::Report;
 use eventric_stream::{
-    error::Conflict,
+    error::{
+        Backpressure,
+        Conflict,
+        Error,
+        Timeout,
+    },
     event::{
         Data,
         Event,
@@ -21,9 +40,17 @@ use eventric_stream::{
         Version,
     },
     stream::{
+        Durability,
         Position,
         Stream,
-        concurrent::owner::Owner,
+        concurrent::{
+            owner::{
+                Owner,
+                Status,
+            },
+            proxy::PendingAppend,
+        },
+        observe::Observation,
         operate::{
             Condition,
             Selection,
@@ -134,7 +161,8 @@ fn reads_see_committed_writes() {
             [event("StudentSubscribedToCourse", "hi", &["student:1"])],
             Condition::new(),
         )
-        .unwrap();
+        .unwrap()
+        .last();
 
     // The single appended event lands at the head position.
     assert_eq!(position, Position::MIN);
@@ -167,7 +195,8 @@ fn conflicting_append_is_rejected_with_conflict_marker() {
             [event("CourseCapacityChanged", "full", &["course:42"])],
             Condition::new(),
         )
-        .unwrap();
+        .unwrap()
+        .last();
     assert_eq!(position, Position::MIN);
 
     // The selection B guards against: any "CourseCapacityChanged" event.
@@ -210,6 +239,330 @@ fn conflicting_append_is_rejected_with_conflict_marker() {
                 )
                 .unwrap()])])]),
         )
-        .expect("non-conflicting append must succeed");
+        .expect("non-conflicting append must succeed")
+        .last();
     assert_eq!(next, Position::MIN + 1);
 }
+
+// 4. A graceful shutdown drains every append already queued before stopping:
+//    each append acknowledged to its caller is in the reclaimed stream.
+#[test]
+fn shutdown_drains_queued_appends() {
+    const THREADS: u64 = 4;
+    const PER_THREAD: u64 = 25;
+
+    let owner = owner();
+
+    let handles = (0..THREADS)
+        .map(|_| {
+            let mut proxy = owner.proxy();
+
+            thread::spawn(move || {
+                (0..PER_THREAD)
+                    .filter(|_| {
+                        proxy
+                            .append([event("Appended", "x", &[])], Condition::new())
+                            .is_ok()
+                    })
+                    .count() as u64
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let stream = owner.shutdown(Duration::from_secs(10)).unwrap();
+
+    let acknowledged = handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .sum::<u64>();
+
+    assert_eq!(stream.len(), acknowledged);
+}
+
+// 5. A panic on the writer thread (here from the caller's event iterator, which
+//    runs there) fails the owner: the caller is answered with an error, the
+//    status reports the failure, and later writes fail with it attached.
+#[test]
+fn writer_panic_fails_the_owner() {
+    let owner = owner();
+    let mut proxy = owner.proxy();
+
+    assert!(matches!(owner.status(), Status::Running));
+    assert!(proxy.append(panicking(), Condition::new()).is_err());
+
+    assert!(matches!(settled(&owner), Status::Failed(_)));
+    assert!(
+        proxy
+            .append([event("Appended", "x", &[])], Condition::new())
+            .is_err()
+    );
+}
+
+// 6. With restart enabled, the same panic is contained: the writer restarts on a
+//    fresh `Writer` with its cursor intact, and the next append lands at the
+//    next position.
+#[test]
+fn writer_panic_restarts_when_enabled() {
+    let owner = Owner::builder(Stream::builder(temp_path()).temporary(true).open().unwrap())
+        .restart(true)
+        .build();
+    let mut proxy = owner.proxy();
+
+    proxy
+        .append([event("Appended", "a", &[])], Condition::new())
+        .unwrap();
+
+    assert!(proxy.append(panicking(), Condition::new()).is_err());
+
+    let position = proxy
+        .append([event("Appended", "b", &[])], Condition::new())
+        .unwrap()
+        .last();
+
+    assert_eq!(position, Position::MIN + 1);
+    assert!(matches!(owner.status(), Status::Running));
+    assert!(matches!(
+        owner
+            .shutdown(Duration::from_secs(10))
+            .map(|stream| stream.len()),
+        Ok(2)
+    ));
+}
+
+// 7. A minimal channel capacity still serialises every write (each waits for
+//    room rather than failing), and a per-append durability override travels
+//    across the channel to the writer.
+#[test]
+fn minimal_capacity_and_durability_override() {
+    let owner = Owner::builder(Stream::builder(temp_path()).temporary(true).open().unwrap())
+        .capacity(1)
+        .build();
+
+    let handles = (0..4)
+        .map(|_| {
+            let mut proxy = owner.proxy();
+
+            thread::spawn(move || {
+                for _ in 0..10 {
+                    proxy
+                        .append_durable(
+                            [event("Audited", "x", &[])],
+                            Condition::new(),
+                            Durability::Sync,
+                        )
+                        .unwrap();
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(owner.into_inner().unwrap().len(), 40);
+}
+
+// 8. An observer registered on the owner sees the writer channel's depth as
+//    each operation is taken, alongside the appends committed through it.
+#[test]
+fn owner_observer_sees_queue_depth() {
+    let observations = Arc::new(Mutex::new(Vec::new()));
+    let owner = Owner::builder(Stream::builder(temp_path()).temporary(true).open().unwrap())
+        .capacity(4)
+        .observer({
+            let observations = Arc::clone(&observations);
+
+            move |observation: &Observation| {
+                observations.lock().unwrap().push(observation.clone());
+            }
+        })
+        .build();
+
+    let mut proxy = owner.proxy();
+
+    for _ in 0..3 {
+        proxy
+            .append([event("Audited", "x", &[])], Condition::new())
+            .unwrap();
+    }
+
+    owner.into_inner().unwrap();
+
+    let observations = observations.lock().unwrap();
+    let depths = observations
+        .iter()
+        .filter(|observation| matches!(observation, Observation::QueueDepth { capacity: 4, .. }))
+        .count();
+    let commits = observations
+        .iter()
+        .filter(|observation| matches!(observation, Observation::AppendCommitted { count: 1, .. }))
+        .count();
+
+    // Three appends, then the exit.
+    assert_eq!(depths, 4);
+    assert_eq!(commits, 3);
+}
+
+// 9. With the writer busy and its channel full, `try_append` fails at once with
+//    the `Backpressure` marker, and `append_timeout` with `Timeout` (and
+//    `Backpressure`, as it was never enqueued). An append which was enqueued
+//    but not answered in time carries a `PendingAppend`, through which its
+//    outcome is given once the writer gets to it.
+#[test]
+fn bounded_appends_report_backpressure_and_pending_outcomes() {
+    let owner = Owner::builder(Stream::builder(temp_path()).temporary(true).open().unwrap())
+        .capacity(1)
+        .build();
+
+    let (started, gate, events) = gated();
+    let busy = thread::spawn({
+        let mut proxy = owner.proxy();
+
+        move || proxy.append(events, Condition::new()).unwrap()
+    });
+
+    started.recv().unwrap();
+
+    let mut proxy = owner.proxy();
+
+    // Enqueued, filling the channel, but not answered while the writer is busy.
+    let timed_out = proxy
+        .append_timeout(
+            [event("Audited", "x", &[])],
+            Condition::new(),
+            Duration::from_millis(50),
+        )
+        .unwrap_err();
+    let pending = timed_out.downcast_ref::<PendingAppend>().unwrap();
+
+    assert!(timed_out.downcast_ref::<Timeout>().is_some());
+    assert!(timed_out.downcast_ref::<Backpressure>().is_none());
+    assert!(pending.try_outcome().is_none());
+
+    let full = proxy
+        .try_append([event("Audited", "y", &[])], Condition::new())
+        .unwrap_err();
+
+    assert!(full.downcast_ref::<Backpressure>().is_some());
+
+    let full = proxy
+        .append_timeout(
+            [event("Audited", "y", &[])],
+            Condition::new(),
+            Duration::from_millis(50),
+        )
+        .unwrap_err();
+
+    assert!(full.downcast_ref::<Timeout>().is_some());
+    assert!(full.downcast_ref::<Backpressure>().is_some());
+    assert!(full.downcast_ref::<PendingAppend>().is_none());
+
+    gate.send(()).unwrap();
+
+    assert_eq!(busy.join().unwrap().first(), Position::new(0));
+    assert_eq!(pending.wait().unwrap().first(), Position::new(1));
+    assert!(pending.wait().is_err());
+    assert_eq!(owner.into_inner().unwrap().len(), 2);
+}
+
+// 10. Transactions serialise read-modify-writes on the writer thread: N threads
+//     contend for a capacity-limited course, each reading the seats taken and
+//     reserving one only while any are left. None conflicts or retries, and
+//     exactly the capacity is reserved. A closure's own error is returned as
+//     it is.
+#[test]
+fn transactions_serialise_read_modify_writes() {
+    const CAPACITY: usize = 25;
+    const THREADS: usize = 4;
+
+    let owner = owner();
+    let seats = || {
+        Selection::new([Selector::types(
+            [TypeSelector::new("SeatReserved").unwrap()],
+        )])
+    };
+
+    let handles = (0..THREADS)
+        .map(|_| {
+            let proxy = owner.proxy();
+
+            thread::spawn(move || {
+                (0..10)
+                    .filter(|_| {
+                        proxy
+                            .transact(move |transaction| {
+                                let taken = transaction
+                                    .select(Condition::new().selections([seats()]))
+                                    .count();
+
+                                if taken == CAPACITY {
+                                    return Ok(false);
+                                }
+
+                                let head = transaction.head();
+
+                                transaction
+                                    .append(
+                                        [event("SeatReserved", "x", &["course:42"])],
+                                        Condition::new().from(head).selections([seats()]),
+                                    )
+                                    .map(|receipt| receipt.first() == head)
+                            })
+                            .unwrap()
+                    })
+                    .count()
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let reserved = handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .sum::<usize>();
+
+    assert_eq!(reserved, CAPACITY);
+
+    let refused = owner
+        .proxy()
+        .transact(|_| Err::<(), _>(Report::new(Error).attach("refused")))
+        .unwrap_err();
+
+    assert!(refused.downcast_ref::<&str>().is_some());
+    assert_eq!(owner.into_inner().unwrap().len(), CAPACITY as u64);
+}
+
+// An event iterator which, once the writer thread starts on it, says so and
+// then holds the writer until the gate opens.
+fn gated() -> (
+    Receiver<()>,
+    Sender<()>,
+    impl Iterator<Item = Event<(), String>> + Send + 'static,
+) {
+    let (started, waiting) = mpsc::channel();
+    let (gate, opened) = mpsc::channel::<()>();
+    let events = iter::once_with(move || {
+        started.send(()).unwrap();
+        opened.recv().ok();
+
+        event("Audited", "gated", &[])
+    });
+
+    (waiting, gate, events)
+}
+
+fn panicking() -> impl Iterator<Item = Event<(), String>> + Send + 'static {
+    iter::from_fn(|| panic!("event iterator panicked"))
+}
+
+// The status once the writer thread has settled (or after a bounded wait).
+fn settled(owner: &Owner) -> Status {
+    for _ in 0..500 {
+        match owner.status() {
+            Status::Running => thread::sleep(Duration::from_millis(10)),
+            status => return status,
+        }
+    }
+
+    owner.status()
+}
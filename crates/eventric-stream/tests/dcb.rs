@@ -0,0 +1,453 @@
+//! The DCB specification's conformance scenarios, run against the `dcb`
+//! compatibility layer over a `Stream` on each backend: reads by query (all
+//! events, types, tags, types and tags, several items, and the empty edge
+//! cases) with their options, and appends with and without a condition,
+//! including the exclusivity of its `after` position.
+
+use eventric_stream::{
+    error::Conflict,
+    stream::{
+        Backend,
+        Position,
+        Stream,
+        dcb::{
+            AppendCondition,
+            Event,
+            EventStore,
+            Query,
+            QueryItem,
+            ReadOptions,
+        },
+    },
+    utils::temp_path,
+};
+
+// =================================================================================================
+// Helpers
+// =================================================================================================
+
+// Run `scenario` against a store on each backend.
+fn scenario(scenario: fn(EventStore)) {
+    for backend in [Backend::Disk, Backend::Memory] {
+        let stream = Stream::builder(temp_path())
+            .backend(backend)
+            .temporary(true)
+            .open()
+            .unwrap();
+
+        scenario(EventStore::new(stream));
+    }
+}
+
+fn event(ty: &str, data: &str, tags: &[&str]) -> Event {
+    Event::new(ty, data, tags.iter().map(|tag| (*tag).to_owned()).collect())
+}
+
+// The fixture most scenarios read: five events of three types, tagged by the
+// entities they concern.
+fn seed(store: &mut EventStore) {
+    store
+        .append(
+            [
+                event("CourseDefined", "c1", &["course:c1"]),
+                event("CourseDefined", "c2", &["course:c2"]),
+                event("StudentSubscribed", "s1c1", &["course:c1", "student:s1"]),
+                event("StudentSubscribed", "s1c2", &["course:c2", "student:s1"]),
+                event("CourseRenamed", "c1", &["course:c1"]),
+            ],
+            None,
+        )
+        .unwrap();
+}
+
+fn read(store: &EventStore, query: &Query, options: ReadOptions) -> Vec<Position> {
+    store
+        .read(query, options)
+        .unwrap()
+        .map(|event| event.unwrap().position)
+        .collect()
+}
+
+fn at<const N: usize>(positions: [u64; N]) -> Vec<Position> {
+    positions.into_iter().map(Position::new).collect()
+}
+
+fn item() -> QueryItem {
+    QueryItem::new()
+}
+
+// =================================================================================================
+// Read
+// =================================================================================================
+
+#[test]
+fn read_from_an_empty_store_returns_nothing() {
+    scenario(|store| {
+        assert_eq!(read(&store, &Query::all(), ReadOptions::default()), at([]));
+    });
+}
+
+#[test]
+fn read_all_returns_every_event_as_appended() {
+    scenario(|mut store| {
+        seed(&mut store);
+
+        let events = store
+            .read(&Query::all(), ReadOptions::default())
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(events.len(), 5);
+        assert_eq!(events[2].position, Position::new(2));
+        assert_eq!(
+            events[2].event,
+            event("StudentSubscribed", "s1c1", &["course:c1", "student:s1"])
+        );
+    });
+}
+
+#[test]
+fn read_by_type() {
+    scenario(|mut store| {
+        seed(&mut store);
+
+        let query = Query::from_items([item().types(["CourseDefined"])]);
+
+        assert_eq!(read(&store, &query, ReadOptions::default()), at([0, 1]));
+    });
+}
+
+#[test]
+fn read_by_several_types() {
+    scenario(|mut store| {
+        seed(&mut store);
+
+        let query = Query::from_items([item().types(["CourseDefined", "CourseRenamed"])]);
+
+        assert_eq!(read(&store, &query, ReadOptions::default()), at([0, 1, 4]));
+    });
+}
+
+#[test]
+fn read_by_tag() {
+    scenario(|mut store| {
+        seed(&mut store);
+
+        let query = Query::from_items([item().tags(["course:c1"])]);
+
+        assert_eq!(read(&store, &query, ReadOptions::default()), at([0, 2, 4]));
+    });
+}
+
+#[test]
+fn read_by_several_tags_requires_all_of_them() {
+    scenario(|mut store| {
+        seed(&mut store);
+
+        let query = Query::from_items([item().tags(["course:c2", "student:s1"])]);
+
+        assert_eq!(read(&store, &query, ReadOptions::default()), at([3]));
+    });
+}
+
+#[test]
+fn read_by_types_and_tags() {
+    scenario(|mut store| {
+        seed(&mut store);
+
+        let query = Query::from_items([item()
+            .types(["CourseDefined", "CourseRenamed"])
+            .tags(["course:c1"])]);
+
+        assert_eq!(read(&store, &query, ReadOptions::default()), at([0, 4]));
+    });
+}
+
+#[test]
+fn read_by_several_items_matches_any_of_them() {
+    scenario(|mut store| {
+        seed(&mut store);
+
+        let query = Query::from_items([
+            item().types(["CourseDefined"]).tags(["course:c2"]),
+            item().types(["StudentSubscribed"]).tags(["course:c1"]),
+            item().tags(["course:c1"]).types(["CourseRenamed"]),
+        ]);
+
+        assert_eq!(read(&store, &query, ReadOptions::default()), at([1, 2, 4]));
+    });
+}
+
+#[test]
+fn read_by_an_empty_item_matches_every_event() {
+    scenario(|mut store| {
+        seed(&mut store);
+
+        let query = Query::from_items([item().types(["CourseRenamed"]), item()]);
+
+        assert_eq!(
+            read(&store, &query, ReadOptions::default()),
+            at([0, 1, 2, 3, 4])
+        );
+    });
+}
+
+#[test]
+fn read_by_a_query_without_items_matches_nothing() {
+    scenario(|mut store| {
+        seed(&mut store);
+
+        assert_eq!(
+            read(&store, &Query::from_items([]), ReadOptions::default()),
+            at([])
+        );
+    });
+}
+
+#[test]
+fn read_by_an_unknown_type_or_tag_matches_nothing() {
+    scenario(|mut store| {
+        seed(&mut store);
+
+        let query = Query::from_items([
+            item().types(["CourseCancelled"]),
+            item().tags(["course:c3"]),
+        ]);
+
+        assert_eq!(read(&store, &query, ReadOptions::default()), at([]));
+    });
+}
+
+#[test]
+fn read_from_a_position_is_inclusive() {
+    scenario(|mut store| {
+        seed(&mut store);
+
+        let query = Query::from_items([item().tags(["course:c1"])]);
+        let options = ReadOptions::default().from(Position::new(2));
+
+        assert_eq!(read(&store, &query, options), at([2, 4]));
+        assert_eq!(
+            read(&store, &Query::all(), options.from(Position::new(5))),
+            at([])
+        );
+    });
+}
+
+#[test]
+fn read_backwards() {
+    scenario(|mut store| {
+        seed(&mut store);
+
+        let query = Query::from_items([item().tags(["course:c1"])]);
+        let options = ReadOptions::default().backwards(true);
+
+        assert_eq!(read(&store, &query, options), at([4, 2, 0]));
+        assert_eq!(
+            read(&store, &query, options.from(Position::new(3))),
+            at([2, 0])
+        );
+    });
+}
+
+#[test]
+fn read_with_a_limit() {
+    scenario(|mut store| {
+        seed(&mut store);
+
+        let options = ReadOptions::default().limit(2);
+
+        assert_eq!(read(&store, &Query::all(), options), at([0, 1]));
+        assert_eq!(
+            read(&store, &Query::all(), options.backwards(true)),
+            at([4, 3])
+        );
+        assert_eq!(
+            read(
+                &store,
+                &Query::all(),
+                options.from(Position::new(1)).limit(10)
+            ),
+            at([1, 2, 3, 4])
+        );
+    });
+}
+
+// =================================================================================================
+// Append
+// =================================================================================================
+
+#[test]
+fn append_without_a_condition_returns_the_last_position() {
+    scenario(|mut store| {
+        let position = store
+            .append([event("CourseDefined", "c1", &["course:c1"])], None)
+            .unwrap();
+
+        assert_eq!(position, Position::new(0));
+
+        let position = store
+            .append(
+                [
+                    event("CourseDefined", "c2", &["course:c2"]),
+                    event("CourseDefined", "c3", &["course:c3"]),
+                ],
+                None,
+            )
+            .unwrap();
+
+        assert_eq!(position, Position::new(2));
+        assert_eq!(
+            read(&store, &Query::all(), ReadOptions::default()),
+            at([0, 1, 2])
+        );
+    });
+}
+
+#[test]
+fn append_fails_if_a_matching_event_exists() {
+    scenario(|mut store| {
+        seed(&mut store);
+
+        let condition = AppendCondition::new(Query::from_items([item()
+            .types(["CourseDefined"])
+            .tags(["course:c1"])]));
+
+        let report = store
+            .append(
+                [event("CourseDefined", "c1", &["course:c1"])],
+                Some(condition),
+            )
+            .expect_err("a matching event must fail the append");
+
+        assert!(report.contains::<Conflict>());
+        assert_eq!(read(&store, &Query::all(), ReadOptions::default()).len(), 5);
+    });
+}
+
+#[test]
+fn append_succeeds_if_no_matching_event_exists() {
+    scenario(|mut store| {
+        seed(&mut store);
+
+        let condition = AppendCondition::new(Query::from_items([item()
+            .types(["CourseDefined"])
+            .tags(["course:c3"])]));
+
+        let position = store
+            .append(
+                [event("CourseDefined", "c3", &["course:c3"])],
+                Some(condition),
+            )
+            .unwrap();
+
+        assert_eq!(position, Position::new(5));
+    });
+}
+
+#[test]
+fn append_condition_after_is_exclusive() {
+    scenario(|mut store| {
+        seed(&mut store);
+
+        let query = Query::from_items([item().tags(["course:c1"])]);
+
+        // The last event tagged `course:c1` is at position 4: a decision made
+        // after reading it is not failed by it...
+        let condition = AppendCondition::new(query.clone()).after(Position::new(4));
+
+        store
+            .append(
+                [event("CourseRenamed", "c1", &["course:c1"])],
+                Some(condition),
+            )
+            .unwrap();
+
+        // ...but one made before reading it is.
+        let condition = AppendCondition::new(query.clone()).after(Position::new(3));
+        let report = store
+            .append(
+                [event("CourseRenamed", "c1", &["course:c1"])],
+                Some(condition),
+            )
+            .expect_err("an event after `after` must fail the append");
+
+        assert!(report.contains::<Conflict>());
+
+        // Events which do not match are never a conflict, after or not.
+        let condition = AppendCondition::new(Query::from_items([item().tags(["course:c3"])]))
+            .after(Position::new(0));
+
+        store
+            .append(
+                [event("CourseDefined", "c3", &["course:c3"])],
+                Some(condition),
+            )
+            .unwrap();
+    });
+}
+
+#[test]
+fn append_condition_on_every_event() {
+    scenario(|mut store| {
+        let condition = AppendCondition::new(Query::all());
+
+        store
+            .append(
+                [event("CourseDefined", "c1", &["course:c1"])],
+                Some(condition.clone()),
+            )
+            .unwrap();
+
+        let report = store
+            .append(
+                [event("CourseDefined", "c2", &["course:c2"])],
+                Some(condition.clone()),
+            )
+            .expect_err("any event must fail the append");
+
+        assert!(report.contains::<Conflict>());
+
+        store
+            .append(
+                [event("CourseDefined", "c2", &["course:c2"])],
+                Some(condition.after(Position::new(0))),
+            )
+            .unwrap();
+    });
+}
+
+#[test]
+fn append_condition_without_items_never_fails() {
+    scenario(|mut store| {
+        seed(&mut store);
+
+        let condition = AppendCondition::new(Query::from_items([]));
+
+        store
+            .append(
+                [event("CourseDefined", "c3", &["course:c3"])],
+                Some(condition),
+            )
+            .unwrap();
+    });
+}
+
+#[test]
+fn append_rejects_invalid_events() {
+    scenario(|mut store| {
+        assert!(store.append([event("", "data", &[])], None).is_err());
+        assert!(
+            store
+                .append([event("CourseDefined", "", &[])], None)
+                .is_err()
+        );
+        assert!(
+            store
+                .append([event("CourseDefined", "data", &[" course"])], None)
+                .is_err()
+        );
+        assert!(store.into_inner().is_empty());
+    });
+}
@@ -0,0 +1,156 @@
+//! Integration tests for log-shipping replication: a follower on another
+//! thread, fed over a local socket, ends up with the leader's events at the
+//! leader's positions and timestamps, and a follower reopened on its own
+//! database resumes where it stopped.
+
+#![cfg(unix)]
+
+use std::{
+    collections::BTreeSet,
+    fs,
+    os::unix::net::{
+        UnixListener,
+        UnixStream,
+    },
+    path::Path,
+    thread,
+};
+
+use eventric_stream::{
+    event::{
+        Data,
+        Event,
+        Facets,
+        Name,
+        Tag,
+        Type,
+        Version,
+    },
+    stream::{
+        Metadata,
+        Position,
+        Stream,
+        Writer,
+        operate::{
+            Condition,
+            append::Append as _,
+            select::Select,
+        },
+        replicate::{
+            Follower,
+            Shipper,
+            transport::socket::{
+                Receiver,
+                Transmitter,
+            },
+        },
+    },
+    utils::temp_path,
+};
+
+// =================================================================================================
+// Helpers
+// =================================================================================================
+
+fn event(name: &str, data: &str, tags: &[&str]) -> Event<(), String> {
+    let ty = Type::new(Name::new(name).unwrap(), Version::new(0));
+    let tags = tags
+        .iter()
+        .map(|tag| Tag::new(*tag).unwrap())
+        .collect::<BTreeSet<_>>();
+
+    Event::new(Data::new(data).unwrap(), Facets::new(ty, tags), ())
+}
+
+fn append(writer: &mut Writer, count: usize) {
+    for index in 0..count {
+        writer
+            .append(
+                vec![event("Invoiced", "invoice", &[&format!("invoice:{index}")])],
+                Condition::new(),
+            )
+            .unwrap();
+    }
+}
+
+fn metadata<S>(stream: &S) -> Vec<Metadata>
+where
+    S: Select,
+{
+    stream
+        .select(Condition::new())
+        .map(|selected| *selected.unwrap().event.meta())
+        .collect()
+}
+
+// Follow on another thread, over a connection accepted on the socket at
+// `socket`, into the stream at `path`, until the leader hangs up.
+fn follow(path: &Path, socket: &Path) -> thread::JoinHandle<Follower> {
+    let listener = UnixListener::bind(socket).unwrap();
+    let path = path.to_owned();
+
+    thread::spawn(move || {
+        let (connection, _) = listener.accept().unwrap();
+        let mut follower = Follower::new(Stream::builder(path).open().unwrap());
+
+        follower.follow(&mut Receiver::new(connection)).unwrap();
+        follower
+    })
+}
+
+// =================================================================================================
+// Tests
+// =================================================================================================
+
+// A follower fed over a local socket holds the leader's events at their
+// leader positions and timestamps, and when reopened on its database resumes
+// from where it stopped rather than from the start.
+#[test]
+fn follower_replicates_over_a_local_socket_and_resumes() {
+    let socket = temp_path();
+    let path = temp_path();
+    let (reader, mut writer) = Stream::builder(temp_path())
+        .temporary(true)
+        .open()
+        .unwrap()
+        .split();
+
+    let follower = follow(&path, &socket);
+    let connection = UnixStream::connect(&socket).unwrap();
+    let mut shipper = Shipper::new(reader.clone(), Transmitter::new(connection)).limit(4);
+
+    append(&mut writer, 10);
+
+    assert_eq!(shipper.ship().unwrap(), 10);
+
+    drop(shipper);
+
+    let follower = follower.join().unwrap();
+
+    assert_eq!(metadata(&follower), metadata(&reader));
+    assert_eq!(follower.watermark().lag(), 0);
+
+    drop(follower);
+    fs::remove_file(&socket).unwrap();
+
+    append(&mut writer, 5);
+
+    let follower = follow(&path, &socket);
+    let connection = UnixStream::connect(&socket).unwrap();
+    let mut shipper =
+        Shipper::new(reader.clone(), Transmitter::new(connection)).from(Position::new(10));
+
+    assert_eq!(shipper.ship().unwrap(), 5);
+
+    drop(shipper);
+
+    let follower = follower.join().unwrap();
+
+    assert_eq!(follower.len(), 15);
+    assert_eq!(metadata(&follower), metadata(&reader));
+    assert_eq!(metadata(&follower.reader()), metadata(&reader));
+
+    drop(follower);
+    fs::remove_file(&socket).unwrap();
+    fs::remove_dir_all(&path).unwrap();
+}
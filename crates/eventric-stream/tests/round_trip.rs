@@ -2,7 +2,8 @@
 //! appending candidate events through the [`Append`] trait and reading them
 //! back through [`Select`], the masked multi-selection query path,
 //! version-range selection, the DCB (position-based) append concurrency check,
-//! and the threaded [`Owner`]/[`Proxy`] round-trip.
+//! the threaded [`Owner`]/[`Proxy`] round-trip, and selecting an append's
+//! whole batch.
 
 use std::collections::BTreeSet;
 
@@ -17,7 +18,9 @@ use eventric_stream::{
         Type,
         Version,
     },
+    parity,
     stream::{
+        Backend,
         Position,
         Stream,
         concurrent::owner::Owner,
@@ -47,17 +50,20 @@ fn event(identifier: &str, data: &str, tags: &[&str], version: u8) -> Event<(),
     Event::new(Data::new(data).unwrap(), Facets::new(ty, tags), ())
 }
 
-fn open() -> Stream {
-    Stream::builder(temp_path()).temporary(true).open().unwrap()
+fn open(backend: Backend) -> Stream {
+    Stream::builder(temp_path())
+        .backend(backend)
+        .temporary(true)
+        .open()
+        .unwrap()
 }
 
 // 1. Append a batch, full-scan it back, and assert the exact count and that
 //    positions are a contiguous 0..N ascending run.
-#[test]
-fn append_then_full_scan_yields_contiguous_positions() {
-    let mut stream = open();
+fn append_then_full_scan_yields_contiguous_positions(backend: Backend) {
+    let mut stream = open(backend);
 
-    let last = stream
+    let receipt = stream
         .append(
             vec![
                 event("StudentSubscribedToCourse", "a", &["student:1"], 0),
@@ -68,8 +74,9 @@ fn append_then_full_scan_yields_contiguous_positions() {
         )
         .unwrap();
 
-    // The append returns the position of the *last* appended event.
-    assert_eq!(last, Position::new(2));
+    // The append's receipt spans the appended events' positions.
+    assert_eq!(receipt.positions(), Position::new(0)..=Position::new(2));
+    assert_eq!(receipt.last(), Position::new(2));
 
     let events = stream
         .select(Condition::new())
@@ -101,14 +108,18 @@ fn append_then_full_scan_yields_contiguous_positions() {
         Data::new("b").unwrap(),
         Data::new("c").unwrap(),
     ]);
+
+    // And each event's metadata is the receipt's, as committed.
+    let metadata = events.iter().map(|em| *em.event.meta()).collect::<Vec<_>>();
+
+    assert_eq!(receipt.metadata().collect::<Vec<_>>(), metadata);
 }
 
 // 2. A two-selection masked query: each returned event's mask records, in
 //    order, which selections it satisfied, and only events matching at least
 //    one selection are returned.
-#[test]
-fn masked_multi_selection_query_sets_correct_bits() {
-    let mut stream = open();
+fn masked_multi_selection_query_sets_correct_bits(backend: Backend) {
+    let mut stream = open(backend);
 
     stream
         .append(
@@ -167,9 +178,8 @@ fn masked_multi_selection_query_sets_correct_bits() {
 }
 
 // A single event can satisfy more than one selection at once: both bits set.
-#[test]
-fn masked_query_event_satisfies_multiple_selections() {
-    let mut stream = open();
+fn masked_query_event_satisfies_multiple_selections(backend: Backend) {
+    let mut stream = open(backend);
 
     stream
         .append(
@@ -206,9 +216,8 @@ fn masked_query_event_satisfies_multiple_selections() {
 }
 
 // 3. A half-open version range `0..2` returns versions 0 and 1 but excludes 2.
-#[test]
-fn version_range_selection_is_half_open() {
-    let mut stream = open();
+fn version_range_selection_is_half_open(backend: Backend) {
+    let mut stream = open(backend);
 
     stream
         .append(
@@ -239,9 +248,8 @@ fn version_range_selection_is_half_open() {
 // 4. The DCB concurrency guard: an append conditioned on a window that already
 //    holds a matching event is rejected with a `Conflict`; an append whose
 //    `from` is past the head succeeds.
-#[test]
-fn dcb_conflict_on_matching_window_and_success_past_head() {
-    let mut stream = open();
+fn dcb_conflict_on_matching_window_and_success_past_head(backend: Backend) {
+    let mut stream = open(backend);
 
     // Seed three "Counter"/"k:1" events at positions 0, 1, 2.
     let last = stream
@@ -255,7 +263,7 @@ fn dcb_conflict_on_matching_window_and_success_past_head() {
         )
         .unwrap();
 
-    assert_eq!(last, Position::new(2));
+    assert_eq!(last.last(), Position::new(2));
 
     let selection = || {
         Selection::new([Selector::types_and_tags(
@@ -297,7 +305,7 @@ fn dcb_conflict_on_matching_window_and_success_past_head() {
         )
         .expect("an append whose window starts past the head cannot conflict");
 
-    assert_eq!(appended, Position::new(3));
+    assert_eq!(appended.last(), Position::new(3));
     assert_eq!(stream.select(Condition::new()).count(), 4);
 
     // A non-matching selection never conflicts even within a populated window.
@@ -313,14 +321,13 @@ fn dcb_conflict_on_matching_window_and_success_past_head() {
         )
         .expect("a selection that matches nothing must not conflict");
 
-    assert_eq!(appended, Position::new(4));
+    assert_eq!(appended.last(), Position::new(4));
 }
 
 // 5. The threaded Owner/Proxy path round-trips appended events back through a
 //    select.
-#[test]
-fn owner_proxy_round_trip() {
-    let owner = Owner::new(open());
+fn owner_proxy_round_trip(backend: Backend) {
+    let owner = Owner::new(open(backend));
     let mut proxy = owner.proxy();
 
     let last = proxy
@@ -333,7 +340,7 @@ fn owner_proxy_round_trip() {
         )
         .unwrap();
 
-    assert_eq!(last, Position::new(1));
+    assert_eq!(last.last(), Position::new(1));
 
     let events = proxy
         .select(Condition::new())
@@ -359,3 +366,83 @@ fn owner_proxy_round_trip() {
         Data::new("world").unwrap()
     ]);
 }
+
+// 6. Each event records the batch it was appended in (the position of the
+//    batch's first event), and `Condition::batch` selects the whole batch
+//    holding a position, in either direction, within its selections and `from`
+//    bound.
+fn batch_condition_selects_the_whole_append(backend: Backend) {
+    let mut stream = open(backend);
+
+    for events in [
+        vec![event("Opened", "a", &["k:1"], 0)],
+        vec![
+            event("Opened", "b", &["k:2"], 0),
+            event("Closed", "c", &["k:1"], 0),
+            event("Opened", "d", &["k:1"], 0),
+        ],
+        vec![event("Closed", "e", &["k:2"], 0)],
+    ] {
+        stream.append(events, Condition::new()).unwrap();
+    }
+
+    let batches = stream
+        .select(Condition::new())
+        .map(|em| em.unwrap().event.meta().batch())
+        .collect::<Vec<_>>();
+
+    assert_eq!(batches, [0, 1, 1, 1, 4].map(Position::new));
+
+    let positions = |condition: Condition| {
+        stream
+            .select(condition)
+            .map(|em| em.unwrap().event.meta().position())
+            .collect::<Vec<_>>()
+    };
+
+    for position in 1..=3 {
+        assert_eq!(
+            positions(Condition::new().batch(Position::new(position))),
+            [1, 2, 3].map(Position::new)
+        );
+    }
+
+    assert_eq!(positions(Condition::new().batch(Position::new(4))), [
+        Position::new(4)
+    ]);
+    assert_eq!(positions(Condition::new().batch(Position::new(5))), []);
+    assert_eq!(
+        positions(
+            Condition::new()
+                .batch(Position::new(2))
+                .from(Position::new(2))
+        ),
+        [2, 3].map(Position::new)
+    );
+    assert_eq!(
+        positions(
+            Condition::new()
+                .batch(Position::new(3))
+                .selections([Selection::new([Selector::tags([Tag::new("k:1").unwrap()])])])
+        ),
+        [2, 3].map(Position::new)
+    );
+    assert_eq!(
+        stream
+            .select(Condition::new().batch(Position::new(1)))
+            .rev()
+            .map(|em| em.unwrap().event.meta().position())
+            .collect::<Vec<_>>(),
+        [3, 2, 1].map(Position::new)
+    );
+}
+
+parity! {
+    append_then_full_scan_yields_contiguous_positions,
+    masked_multi_selection_query_sets_correct_bits,
+    masked_query_event_satisfies_multiple_selections,
+    version_range_selection_is_half_open,
+    dcb_conflict_on_matching_window_and_success_past_head,
+    owner_proxy_round_trip,
+    batch_condition_selects_the_whole_append,
+}